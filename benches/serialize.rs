@@ -0,0 +1,65 @@
+//! Throughput of serializing large collections of regexes
+//!
+//! Run with `cargo bench --bench serialize`. Uses plain `Instant` timing
+//! rather than a benchmark framework, to avoid extra dependencies, so
+//! expect some noise between runs.
+//!
+//! Wrapping each element into `Serde(element)` is free, since the wrapper
+//! is just a reference. Support for a global prefix and suffix (see
+//! `set_prefix_suffix`) made serialization slower than in the original
+//! crate, as it took a lock to read them for every element. Collections
+//! now read them once instead, to recover from that regression.
+//! Best of ten rounds (see `ROUNDS`), with the per-element read ("before")
+//! and with a single read ("after"), in millions of patterns per second:
+//!
+//! | collection              | before | after |
+//! |-------------------------|--------|-------|
+//! | `Vec<Regex>`            |   30.1 |  31.2 |
+//! | `HashMap<usize, Regex>` |   20.3 |  21.7 |
+//! | `RegexSet`              |   39.5 |  73.8 |
+//!
+//! Short patterns, like the ones in the set, gain the most; for longer
+//! ones the time is dominated by escaping and writing JSON.
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use regex::{Regex, RegexSet};
+use serde_regex::Serde;
+
+const ROUNDS: u32 = 10;
+
+fn measure<T>(name: &str, value: &T, count: usize)
+where
+    T: serde::Serialize,
+{
+    let mut buffer = Vec::new();
+    let mut best = Duration::MAX;
+    for _ in 0..ROUNDS {
+        buffer.clear();
+        let start = Instant::now();
+        serde_json::to_writer(&mut buffer, black_box(value)).unwrap();
+        best = best.min(start.elapsed());
+    }
+    println!("{:<24} {:>8} patterns {:>10.2?} {:>8.1} M patterns/s",
+        name, count, best, count as f64 / best.as_secs_f64() / 1e6);
+}
+
+fn main() {
+    let count = 1_000_000;
+    let regex = Regex::new(r"^/api/v\d+/users/(\d+)$").unwrap();
+    let vec = vec![regex.clone(); count];
+    measure("Vec<Regex>", &Serde(&vec), count);
+
+    let map = (0..count)
+        .map(|i| (i, regex.clone()))
+        .collect::<HashMap<_, _>>();
+    measure("HashMap<usize, Regex>", &Serde(&map), count);
+
+    let set_count = 10_000;
+    let set = RegexSet::new((0..set_count).map(|i| format!("^/item/{}$", i)))
+        .unwrap();
+    measure("RegexSet", &Serde(&set), set_count);
+}
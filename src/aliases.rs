@@ -0,0 +1,177 @@
+//! Deserialization of patterns with named placeholders
+//!
+//! Input is a map with the pattern and a table of aliases:
+//!
+//! ```json
+//! {
+//!     "pattern": "{{IDENT}} = {{IDENT}}",
+//!     "aliases": { "IDENT": "[A-Za-z_][A-Za-z0-9_]*" }
+//! }
+//! ```
+//!
+//! Every `{{NAME}}` placeholder is replaced by the pattern of the alias
+//! wrapped into a non-capturing group, e.g. `(?:[A-Za-z_][A-Za-z0-9_]*)`.
+//! Aliases may refer to other aliases. An unknown name or a cyclic
+//! reference is a deserialization error, as is an expanded pattern longer
+//! than [`MAX_EXPANDED_LEN`] bytes (aliases referring to other aliases
+//! several times can grow exponentially). The `aliases` key may be omitted.
+//!
+//! Only the expanded pattern is kept, so serialization emits it as
+//! `{"pattern": "..."}` without any aliases.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::aliases")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": {
+//!     "pattern": "^{{NUM}}\\.{{NUM}}$",
+//!     "aliases": {"NUM": "\\d+"}
+//! }}"#).unwrap();
+//! assert_eq!(rule.pattern.as_str(), r"^(?:\d+)\.(?:\d+)$");
+//! ```
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::Serde;
+
+struct Aliased {
+    pattern: String,
+    aliases: HashMap<String, String>,
+}
+
+deserialize_struct!(Aliased {
+    pattern: String,
+    aliases: HashMap<String, String> = HashMap::new(),
+});
+
+struct Expanded<'a> {
+    pattern: Serde<&'a Regex>,
+}
+
+serialize_struct!(Expanded<'_> as Expanded { pattern });
+
+/// Maximum length of the pattern after expanding all aliases, in bytes
+pub const MAX_EXPANDED_LEN: usize = 1 << 20;
+
+fn push(result: &mut String, text: &str) -> Result<(), String> {
+    if result.len() + text.len() > MAX_EXPANDED_LEN {
+        return Err(format!("expanded pattern is longer than {} bytes",
+            MAX_EXPANDED_LEN));
+    }
+    result.push_str(text);
+    Ok(())
+}
+
+fn expand<'a>(pattern: &'a str, aliases: &'a HashMap<String, String>,
+    stack: &mut Vec<&'a str>, result: &mut String)
+    -> Result<(), String>
+{
+    let mut rest = pattern;
+    while let Some(start) = rest.find("{{") {
+        push(result, &rest[..start])?;
+        let end = rest[start..].find("}}")
+            .ok_or_else(|| format!("unclosed placeholder in {:?}", pattern))?;
+        let name = &rest[start + 2..start + end];
+        if stack.contains(&name) {
+            return Err(format!("cyclic alias: {} -> {}",
+                stack.join(" -> "), name));
+        }
+        let alias = aliases.get(name)
+            .ok_or_else(|| format!("unknown alias {:?}", name))?;
+        stack.push(name);
+        push(result, "(?:")?;
+        expand(alias, aliases, stack, result)?;
+        push(result, ")")?;
+        stack.pop();
+        rest = &rest[start + end + 2..];
+    }
+    push(result, rest)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let aliased = Aliased::deserialize(d)?;
+    let mut pattern = String::with_capacity(aliased.pattern.len());
+    expand(&aliased.pattern, &aliased.aliases, &mut Vec::new(), &mut pattern)
+        .map_err(D::Error::custom)?;
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Expanded { pattern: Serde(value) }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::aliases")]
+        re: Regex,
+    }
+
+    fn load(json: &str) -> Result<Regex, String> {
+        from_str::<Rule>(&format!(r#"{{"re": {}}}"#, json))
+            .map(|rule| rule.re)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_nested() {
+        let re = load(r#"{"pattern": "{{A}}+", "aliases": {
+            "A": "{{B}}|b", "B": "a"
+        }}"#).unwrap();
+        assert_eq!(re.as_str(), "(?:(?:a)|b)+");
+        let rule = Rule { re };
+        assert_eq!(to_string(&rule).unwrap(),
+            r#"{"re":{"pattern":"(?:(?:a)|b)+"}}"#);
+    }
+
+    #[test]
+    fn test_no_aliases() {
+        assert_eq!(load(r#"{"pattern": "a{2}"}"#).unwrap().as_str(), "a{2}");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(load(r#"{"pattern": "{{A}}"}"#).unwrap_err()
+            .starts_with(r#"unknown alias "A""#));
+        assert!(load(r#"{"pattern": "{{A}}", "aliases": {
+            "A": "{{B}}", "B": "x{{A}}"
+        }}"#).unwrap_err().starts_with("cyclic alias: A -> B -> A"));
+        assert!(load(r#"{"pattern": "{{A"}"#).unwrap_err()
+            .starts_with("unclosed placeholder"));
+    }
+
+    #[test]
+    fn test_doubling() {
+        let aliases = (0..40)
+            .map(|i| format!(r#""A{0}": "{{{{A{1}}}}}{{{{A{1}}}}}""#,
+                i, i + 1))
+            .collect::<Vec<_>>().join(", ");
+        let json = format!(r#"{{"pattern": "{{{{A0}}}}", "aliases": {{{},
+            "A40": "x"}}}}"#, aliases);
+        assert!(load(&json).unwrap_err()
+            .starts_with("expanded pattern is longer than 1048576 bytes"));
+    }
+}
@@ -0,0 +1,128 @@
+use std::borrow::Cow;
+
+use regex::{Regex, bytes};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+/// A regex compiled by whichever engine accepts the pattern
+///
+/// On deserialization the pattern is compiled as a UTF-8 [`Regex`] first.
+/// If that fails, for example because the pattern can match invalid UTF-8
+/// like `(?-u)\xFF`, it is compiled as a [`bytes::Regex`] instead. If both
+/// engines refuse the pattern, the error of the UTF-8 engine is reported.
+/// This allows loading rules from sources mixing text and binary patterns
+/// into a single type.
+///
+/// Either way, the pattern string is stored unchanged and serialization
+/// writes it back as is, so a round trip ends up with the same variant.
+///
+/// ```rust
+/// use serde_regex::AnyRegex;
+///
+/// let rules: Vec<AnyRegex> = serde_json::from_str(
+///     r#"["^GET ", "(?-u)^\\x89PNG"]"#).unwrap();
+/// assert!(matches!(rules[0], AnyRegex::Text(_)));
+/// assert!(matches!(rules[1], AnyRegex::Bytes(_)));
+/// assert!(rules[1].is_match(b"\x89PNG\r\n"));
+/// assert_eq!(serde_json::to_string(&rules).unwrap(),
+///     r#"["^GET ","(?-u)^\\x89PNG"]"#);
+/// ```
+#[derive(Debug, Clone)]
+pub enum AnyRegex {
+    /// Pattern compiled by the UTF-8 engine
+    Text(Regex),
+    /// Pattern which only the bytes engine accepts
+    Bytes(bytes::Regex),
+}
+
+impl AnyRegex {
+    /// Compiles a pattern, falling back to the bytes engine
+    pub fn new(pattern: &str) -> Result<AnyRegex, regex::Error> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(AnyRegex::Text(regex)),
+            Err(e) => bytes::Regex::new(pattern)
+                .map(AnyRegex::Bytes)
+                .map_err(|_| e),
+        }
+    }
+
+    /// Returns the original pattern string
+    pub fn as_str(&self) -> &str {
+        match self {
+            AnyRegex::Text(regex) => regex.as_str(),
+            AnyRegex::Bytes(regex) => regex.as_str(),
+        }
+    }
+
+    /// Returns true if the regex matches anywhere in the haystack
+    ///
+    /// A `Text` regex can match a haystack which isn't valid UTF-8, in the
+    /// same places as a `bytes::Regex` with the same pattern would. Such a
+    /// haystack is matched by compiling the pattern with the bytes engine
+    /// on every call, so it's much slower than a valid UTF-8 one.
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        match self {
+            AnyRegex::Text(regex) => match std::str::from_utf8(haystack) {
+                Ok(text) => regex.is_match(text),
+                Err(_) => bytes::Regex::new(regex.as_str())
+                    .expect("valid UTF-8 pattern is valid for bytes")
+                    .is_match(haystack),
+            },
+            AnyRegex::Bytes(regex) => regex.is_match(haystack),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AnyRegex {
+    fn deserialize<D>(d: D) -> Result<AnyRegex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <Cow<str>>::deserialize(d)?;
+        AnyRegex::new(&s).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for AnyRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_str().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    use super::AnyRegex;
+
+    #[test]
+    fn test_fallback() {
+        let re: AnyRegex = from_str(r#""\\w+""#).unwrap();
+        assert!(matches!(re, AnyRegex::Text(_)));
+        assert!(re.is_match("été".as_bytes()));
+        assert!(!re.is_match(b"\xff"));
+        assert!(re.is_match(b"\xff abc \xfe"));
+
+        let re: AnyRegex = from_str(r#""^a.c$""#).unwrap();
+        assert!(re.is_match(b"abc"));
+        assert!(!re.is_match(b"a\xffc"));
+        assert!(!re.is_match(b"\xff\nabc"));
+
+        let re: AnyRegex = from_str(r#""(?-u)\\xFF.""#).unwrap();
+        assert!(matches!(re, AnyRegex::Bytes(_)));
+        assert!(re.is_match(b"\xff\xfe"));
+        assert_eq!(to_string(&re).unwrap(), r#""(?-u)\\xFF.""#);
+        let back: AnyRegex = from_str(&to_string(&re).unwrap()).unwrap();
+        assert!(matches!(back, AnyRegex::Bytes(_)));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = from_str::<AnyRegex>(r#""(""#).unwrap_err();
+        assert!(err.to_string().contains("unclosed group"));
+        assert!(AnyRegex::new("(?-u)\\xFF").is_ok());
+        assert!(AnyRegex::new("(?-u)\\xFF(").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Deserialization of ASCII-only patterns
+//!
+//! Patterns are compiled with Unicode mode turned off, which has the same
+//! effect as `RegexBuilder::unicode(false)`: `\w`, `\d`, `\s` and case
+//! insensitivity only cover ASCII. The mode is stored in the pattern as a
+//! leading `(?-u)` (added unless already there), so it survives
+//! serialization.
+//!
+//! Before compiling, the pattern is parsed with `regex-syntax` and rejected
+//! if it contains:
+//!
+//! * a Unicode class like `\pL` or `\p{Greek}`, also inside brackets;
+//! * a non-ASCII literal, whether written verbatim (`é`) or escaped
+//!   (`\xFF`, `\u{e9}`), also as a class range bound;
+//! * an inline flag turning Unicode mode back on, like `(?u)`.
+//!
+//! Works for both `Regex` and `bytes::Regex` fields. Note that `Regex`
+//! refuses patterns which can match invalid UTF-8 in this mode, e.g. `.` or
+//! `[^a]`, so use `bytes::Regex` for byte-oriented protocols.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::bytes::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Header {
+//!     #[serde(with = "serde_regex::ascii_only")]
+//!     name: Regex,
+//! }
+//!
+//! let header: Header = serde_json::from_str(r#"{"name": "\\w+: .*"}"#)
+//!     .unwrap();
+//! assert_eq!(header.name.as_str(), r"(?-u)\w+: .*");
+//! assert!(!header.name.is_match("é: x".as_bytes()));
+//! assert!(serde_json::from_str::<Header>(r#"{"name": "\\pL+"}"#).is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex_syntax::ast::{
+    Ast,
+    ClassSetItem,
+    Flag,
+    Flags,
+    FlagsItemKind,
+    GroupKind,
+    Span,
+    parse::Parser,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::{visit, visit_class};
+
+const PREFIX: &str = "(?-u)";
+
+mod sealed {
+    pub trait AsciiRegex: Sized {
+        fn compile(pattern: &str) -> Result<Self, regex::Error>;
+        fn pattern(&self) -> &str;
+    }
+
+    impl AsciiRegex for regex::Regex {
+        fn compile(pattern: &str) -> Result<Self, regex::Error> {
+            regex::Regex::new(pattern)
+        }
+        fn pattern(&self) -> &str {
+            self.as_str()
+        }
+    }
+
+    impl AsciiRegex for regex::bytes::Regex {
+        fn compile(pattern: &str) -> Result<Self, regex::Error> {
+            regex::bytes::Regex::new(pattern)
+        }
+        fn pattern(&self) -> &str {
+            self.as_str()
+        }
+    }
+}
+
+fn enables_unicode(flags: &Flags) -> bool {
+    let mut negated = false;
+    for item in &flags.items {
+        match item.kind {
+            FlagsItemKind::Negation => negated = true,
+            FlagsItemKind::Flag(Flag::Unicode) if !negated => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn check(pattern: &str) -> Result<(), String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut error = None;
+    let mut fail = |what: &str, span: &Span| {
+        error.get_or_insert_with(|| format!("{} {:?} is not allowed",
+            what, &pattern[span.start.offset..span.end.offset]));
+    };
+    visit(&ast, &mut |node| match node {
+        Ast::ClassUnicode(class) => fail("Unicode class", &class.span),
+        Ast::Literal(lit) if !lit.c.is_ascii() => {
+            fail("non-ASCII literal", &lit.span);
+        }
+        Ast::Flags(set) if enables_unicode(&set.flags) => {
+            fail("Unicode flag", &set.span);
+        }
+        Ast::Group(group) => {
+            if let GroupKind::NonCapturing(ref flags) = group.kind {
+                if enables_unicode(flags) {
+                    fail("Unicode flag", &flags.span);
+                }
+            }
+        }
+        Ast::ClassBracketed(class) => visit_class(&class.kind, &mut |item| {
+            match item {
+                ClassSetItem::Unicode(class) => {
+                    fail("Unicode class", &class.span);
+                }
+                ClassSetItem::Literal(lit) if !lit.c.is_ascii() => {
+                    fail("non-ASCII literal", &lit.span);
+                }
+                ClassSetItem::Range(range)
+                    if !range.start.c.is_ascii() || !range.end.c.is_ascii()
+                => {
+                    fail("non-ASCII range", &range.span);
+                }
+                _ => {}
+            }
+        }),
+        _ => {}
+    });
+    error.map_or(Ok(()), Err)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D, R>(d: D) -> Result<R, D::Error>
+where
+    D: Deserializer<'de>,
+    R: sealed::AsciiRegex,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    check(&s).map_err(D::Error::custom)?;
+    if s.starts_with(PREFIX) {
+        R::compile(&s)
+    } else {
+        R::compile(&format!("{}{}", PREFIX, s))
+    }.map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<R, S>(value: &R, serializer: S) -> Result<S::Ok, S::Error>
+where
+    R: sealed::AsciiRegex,
+    S: Serializer,
+{
+    let pattern = value.pattern();
+    if pattern.starts_with(PREFIX) {
+        serializer.serialize_str(pattern)
+    } else {
+        serializer.collect_str(&format_args!("{}{}", PREFIX, pattern))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::{Regex, bytes};
+    use serde_json::{Value, to_value};
+
+    use super::{check, deserialize, serialize};
+
+    #[test]
+    fn test_check() {
+        assert!(check(r"[a-z]+\d\w\x7F").is_ok());
+        assert!(check(r"(?-u:\w)(?i-u)a").is_ok());
+        assert_eq!(check(r"a\pLb").unwrap_err(),
+            r#"Unicode class "\\pL" is not allowed"#);
+        assert_eq!(check(r"[a\p{Greek}]").unwrap_err(),
+            r#"Unicode class "\\p{Greek}" is not allowed"#);
+        assert_eq!(check("café").unwrap_err(),
+            r#"non-ASCII literal "é" is not allowed"#);
+        assert!(check(r"\xFF").is_err());
+        assert!(check(r"[a-\x{100}]").is_err());
+        assert!(check(r"(?u)\w").is_err());
+        assert!(check(r"(?iu:\w)").is_err());
+    }
+
+    #[test]
+    fn test_compile() {
+        let re: Regex = deserialize(Value::from(r"\w+")).unwrap();
+        assert_eq!(re.as_str(), r"(?-u)\w+");
+        assert!(!re.is_match("é"));
+        let again: Regex = deserialize(Value::from(re.as_str())).unwrap();
+        assert_eq!(again.as_str(), re.as_str());
+
+        let re: bytes::Regex = deserialize(Value::from("a.c")).unwrap();
+        assert!(re.is_match(b"a\xffc"));
+        assert!(deserialize::<_, Regex>(Value::from("a.c")).is_err());
+
+        let plain = bytes::Regex::new("x").unwrap();
+        assert_eq!(serialize(&plain, serde_json::value::Serializer).unwrap(),
+            Value::from("(?-u)x"));
+        assert_eq!(to_value(crate::Serde(&plain)).unwrap(), Value::from("x"));
+    }
+}
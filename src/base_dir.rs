@@ -0,0 +1,28 @@
+//! Base directory for relative paths of modules reading files
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+static BASE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory relative file paths are resolved against
+///
+/// The directory is shared by all modules of this crate that read files.
+/// Replaces the previous base dir, if any. See module docs for details.
+pub fn set_base_dir(dir: impl AsRef<Path>) {
+    *BASE_DIR.write().unwrap_or_else(|e| e.into_inner()) =
+        Some(dir.as_ref().to_path_buf());
+}
+
+/// Resolves relative file paths against the current directory again
+pub fn clear_base_dir() {
+    *BASE_DIR.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+pub(crate) fn resolve(path: &Path) -> PathBuf {
+    match &*BASE_DIR.read().unwrap_or_else(|e| e.into_inner()) {
+        Some(base) => base.join(path),
+        None => path.to_path_buf(),
+    }
+}
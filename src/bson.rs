@@ -0,0 +1,142 @@
+//! (De)serialization as the native BSON regular expression type
+//!
+//! Enabled by the `bson` feature. Instead of a plain string the regex is
+//! stored as a BSON regex with separate pattern and options, which is how
+//! MongoDB documents store regular expressions. This is a separate module
+//! rather than a change to the default format, because features are
+//! additive and enabling it must not change JSON output of other crates.
+//!
+//! Options map to inline flags of the compiled pattern:
+//!
+//! | BSON option | inline flag | `RegexBuilder` method    |
+//! |-------------|-------------|--------------------------|
+//! | `i`         | `(?i)`      | `case_insensitive`       |
+//! | `m`         | `(?m)`      | `multi_line`             |
+//! | `s`         | `(?s)`      | `dot_matches_new_line`   |
+//! | `x`         | `(?x)`      | `ignore_whitespace`      |
+//! | `u`         | none        | always on in `regex`     |
+//!
+//! The `l` (locale) option and unknown options are errors. On serialization
+//! a leading group of these flags like `(?im)` is moved from the pattern to
+//! options. BSON requires options to be sorted, so flags are reordered and
+//! deduplicated: `(?si)` reads back as `(?is)`, which is an equivalent
+//! pattern, but not the same string.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::bson")]
+//!     pattern: Regex,
+//! }
+//!
+//! let doc = bson::doc! {
+//!     "pattern": bson::Regex {
+//!         pattern: "^ab+".into(),
+//!         options: "i".into(),
+//!     },
+//! };
+//! let rule: Rule = bson::from_document(doc).unwrap();
+//! assert_eq!(rule.pattern.as_str(), "(?i)^ab+");
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::syntax;
+
+const FLAGS: &[char] = &['i', 'm', 's', 'x'];
+
+fn split_flags(pattern: &str) -> (String, &str) {
+    match syntax::split_flags(pattern, FLAGS) {
+        Some((flags, rest)) => {
+            let mut options = flags.chars().collect::<Vec<_>>();
+            options.sort_unstable();
+            options.dedup();
+            (options.into_iter().collect(), rest)
+        }
+        None => (String::new(), pattern),
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bson = ::bson::Regex::deserialize(d)?;
+    let mut flags = Vec::new();
+    for option in bson.options.chars() {
+        match option {
+            c if FLAGS.contains(&c) => flags.push(c),
+            'u' => {}
+            c => {
+                return Err(D::Error::custom(format_args!(
+                    "unsupported regex option {:?}", c)));
+            }
+        }
+    }
+    Regex::new(&syntax::prefix_flags(FLAGS, &flags, &bson.pattern))
+        .map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let (options, pattern) = split_flags(value.as_str());
+    ::bson::Regex { pattern: pattern.to_string(), options }
+        .serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use ::bson::{Bson, doc, from_document, to_document};
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::bson")]
+        re: Regex,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rule = Rule { re: Regex::new("(?si)a.b").unwrap() };
+        let doc = to_document(&rule).unwrap();
+        match doc.get("re") {
+            Some(Bson::RegularExpression(re)) => {
+                assert_eq!((&re.pattern[..], &re.options[..]), ("a.b", "is"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let back: Rule = from_document(doc).unwrap();
+        assert_eq!(back.re.as_str(), "(?is)a.b");
+        assert!(back.re.is_match("A\nB"));
+    }
+
+    #[test]
+    fn test_options() {
+        let load = |options: &str| from_document::<Rule>(doc! {
+            "re": ::bson::Regex { pattern: "x".into(), options: options.into() },
+        }).map(|rule| rule.re.as_str().to_string());
+        assert_eq!(load("").unwrap(), "x");
+        assert_eq!(load("mu").unwrap(), "(?m)x");
+        assert_eq!(load("u").unwrap(), "x");
+        assert_eq!(load("sis").unwrap(), "(?is)x");
+        assert!(load("l").is_err());
+    }
+
+    #[test]
+    fn test_plain_pattern() {
+        let doc = to_document(&Rule { re: Regex::new("(?U)a+").unwrap() })
+            .unwrap();
+        let back: Rule = from_document(doc).unwrap();
+        assert_eq!(back.re.as_str(), "(?U)a+");
+    }
+}
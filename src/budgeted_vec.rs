@@ -0,0 +1,140 @@
+//! Deserialization of `Vec<Regex>` with a total size budget
+//!
+//! Per-pattern limits like [`size_guarded`](crate::size_guarded) don't
+//! bound the memory used by a whole rule set: a thousand patterns just
+//! below the limit are accepted. This module sums the estimated size of
+//! all elements and fails once the total exceeds the budget, naming the
+//! index of the element that crossed it. Each element is checked before
+//! it's compiled, so the patterns after the budget is exhausted are never
+//! compiled at all.
+//!
+//! The estimate is the same as in `size_guarded`: a count of syntax tree
+//! nodes, with counted repetitions multiplied by their count. It's a proxy
+//! for compiled size rather than a number of bytes; in particular Unicode
+//! classes like `\w` count as a single node.
+//!
+//! The default budget is [`DEFAULT_BUDGET`]. Use `deserialize_budget` with
+//! an explicit budget to override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rules {
+//!     #[serde(
+//!         serialize_with = "serde_regex::budgeted_vec::serialize",
+//!         deserialize_with = "serde_regex::budgeted_vec::deserialize_budget::<_, 200>",
+//!     )]
+//!     patterns: Vec<Regex>,
+//! }
+//!
+//! assert!(serde_json::from_str::<Rules>(r#"{"patterns": ["a{50}", "b{50}"]}"#)
+//!     .is_ok());
+//! let err = serde_json::from_str::<Rules>(r#"{
+//!     "patterns": ["a{50}", "b{50}", "c{50}", "d{50}"]
+//! }"#).err().unwrap();
+//! assert!(err.to_string().starts_with("pattern at index 3 exceeds"));
+//! ```
+use std::{borrow::Cow, fmt};
+
+use regex::Regex;
+use regex_syntax::ast::parse::Parser;
+use serde::{
+    Deserializer,
+    Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+use crate::size_guarded::size;
+
+/// Total estimated size allowed by default
+pub const DEFAULT_BUDGET: usize = 100_000;
+
+struct BudgetedSeqVisitor<const BUDGET: usize>;
+
+impl<'a, const BUDGET: usize> Visitor<'a> for BudgetedSeqVisitor<BUDGET> {
+    type Value = Vec<Regex>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter,
+            "sequence of patterns with total estimated size at most {}",
+            BUDGET)
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'a>,
+    {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        let mut total = 0usize;
+        while let Some(s) = seq.next_element::<Cow<str>>()? {
+            let ast = Parser::new().parse(&s).map_err(A::Error::custom)?;
+            total = total.saturating_add(size(&ast));
+            if total > BUDGET {
+                return Err(A::Error::custom(format_args!(
+                    "pattern at index {} exceeds total size budget: \
+                     estimated size {}, at most {} allowed",
+                    vec.len(), total, BUDGET)));
+            }
+            vec.push(Regex::new(&s).map_err(A::Error::custom)?);
+        }
+        Ok(vec)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_budget::<D, DEFAULT_BUDGET>(d)
+}
+
+/// Deserialize function allowing at most `BUDGET` total estimated size
+pub fn deserialize_budget<'de, D, const BUDGET: usize>(d: D)
+    -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_seq(BudgetedSeqVisitor::<BUDGET>)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Vec<Regex>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::deserialize_budget;
+
+    fn load<const BUDGET: usize>(json: &str) -> Result<Vec<Regex>, String> {
+        let value = serde_json::from_str::<Value>(json).unwrap();
+        deserialize_budget::<_, BUDGET>(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_budget() {
+        assert_eq!(load::<8>(r#"["abc", "def"]"#).unwrap().len(), 2);
+        assert_eq!(load::<7>(r#"["abc", "def"]"#).unwrap_err(),
+            "pattern at index 1 exceeds total size budget: \
+             estimated size 8, at most 7 allowed");
+        assert!(load::<0>("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_not_compiled() {
+        // the invalid pattern after the budget is exhausted isn't compiled
+        assert!(load::<3>(r#"["(ab){5}", "a{5000000}"]"#).unwrap_err()
+            .starts_with("pattern at index 0"));
+        assert!(load::<1000>(r#"["a", "("]"#).unwrap_err()
+            .contains("unclosed group"));
+    }
+}
@@ -0,0 +1,184 @@
+//! (De)serialization of `bytes::Regex` patterns as byte arrays
+//!
+//! Enabled by the `serde_bytes` feature. The pattern is (de)serialized with
+//! `serde_bytes`, so it may contain bytes which are not valid UTF-8. Prefer
+//! this module over the default string form only if patterns are genuinely
+//! binary, as byte arrays are much less readable in text formats.
+//!
+//! Valid UTF-8 input is compiled as is. Otherwise each invalid byte is
+//! replaced by a `(?-u:\xNN)` escape, which matches the raw byte, while
+//! the rest of the pattern keeps Unicode mode (so `\w` or `.` mean the same
+//! as in any other pattern). An invalid byte preceded by a backslash is
+//! matched literally too. Invalid bytes inside of a bracketed character
+//! class are an error. Such patterns are serialized with the escapes, so
+//! the serialized bytes are not equal to the original input but compile to
+//! the same matcher.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::bytes::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Magic {
+//!     #[serde(with = "serde_regex::byte_pattern")]
+//!     pattern: Regex,
+//! }
+//!
+//! let magic: Magic = serde_json::from_str(r#"{"pattern": [97, 255]}"#)
+//!     .unwrap();
+//! assert!(magic.pattern.is_match(b"a\xff"));
+//! ```
+use std::fmt::Write;
+use std::str;
+
+use regex::bytes::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+use serde_bytes::{ByteBuf, Bytes};
+
+/// Tracks whether the pattern text written so far ends inside of a class
+/// or right after a backslash
+#[derive(Default)]
+struct Scanner {
+    class_depth: usize,
+    class_start: bool,
+    escape: bool,
+}
+
+impl Scanner {
+    fn scan(&mut self, text: &str) {
+        for c in text.chars() {
+            if self.escape {
+                self.escape = false;
+            } else if c == '\\' {
+                self.escape = true;
+            } else if c == '[' {
+                self.class_depth += 1;
+                self.class_start = true;
+                continue;
+            } else if c == '^' && self.class_start {
+                continue;
+            } else if c == ']' && self.class_depth > 0 && !self.class_start {
+                self.class_depth -= 1;
+            }
+            self.class_start = false;
+        }
+    }
+}
+
+fn escape_invalid(mut bytes: &[u8]) -> Result<String, &'static str> {
+    let mut result = String::with_capacity(bytes.len() + 16);
+    let mut scanner = Scanner::default();
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(tail) => {
+                result.push_str(tail);
+                return Ok(result);
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                let valid = str::from_utf8(valid).expect("valid prefix");
+                result.push_str(valid);
+                scanner.scan(valid);
+                if scanner.class_depth > 0 {
+                    return Err("invalid UTF-8 inside of a character class \
+                                is not supported");
+                }
+                if scanner.escape {
+                    // the backslash escapes the first invalid byte
+                    result.pop();
+                    scanner.escape = false;
+                }
+                let bad = e.error_len().unwrap_or(rest.len());
+                for byte in &rest[..bad] {
+                    write!(result, "(?-u:\\x{:02X})", byte).unwrap();
+                }
+                bytes = &rest[bad..];
+            }
+        }
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let bytes = ByteBuf::deserialize(d)?;
+    let result = match str::from_utf8(&bytes) {
+        Ok(pattern) => Regex::new(pattern),
+        Err(_) => {
+            let pattern = escape_invalid(&bytes).map_err(D::Error::custom)?;
+            Regex::new(&pattern)
+        }
+    };
+    result.map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(Bytes::new(value.as_str().as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::bytes::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Magic {
+        #[serde(with = "crate::byte_pattern")]
+        pattern: Regex,
+    }
+
+    #[test]
+    fn test_utf8() {
+        let m: Magic = from_str(r#"{"pattern":[97,43]}"#).unwrap();
+        assert_eq!(m.pattern.as_str(), "a+");
+        assert_eq!(to_string(&m).unwrap(), r#"{"pattern":[97,43]}"#);
+    }
+
+    #[test]
+    fn test_binary() {
+        let m: Magic = from_str(r#"{"pattern":[97,255,254,43]}"#).unwrap();
+        assert_eq!(m.pattern.as_str(), r"a(?-u:\xFF)(?-u:\xFE)+");
+        assert!(m.pattern.is_match(b"xa\xff\xfe\xfe"));
+        assert!(!m.pattern.is_match("aÿþ".as_bytes()));
+        let json = to_string(&m).unwrap();
+        let m2: Magic = from_str(&json).unwrap();
+        assert_eq!(m2.pattern.as_str(), m.pattern.as_str());
+    }
+
+    #[test]
+    fn test_unicode_kept() {
+        // `\w.` followed by 0xFF
+        let m: Magic = from_str(r#"{"pattern":[92,119,46,255]}"#).unwrap();
+        assert_eq!(m.pattern.as_str(), r"\w.(?-u:\xFF)");
+        // `éж` in UTF-8 followed by 0xFF
+        assert!(m.pattern.is_match(b"\xc3\xa9\xd0\xb6\xff"));
+        assert!(!m.pattern.is_match(b"\xe9\xe9\xff"));
+    }
+
+    #[test]
+    fn test_backslash() {
+        // `a\` followed by 0xFF and `\\` followed by 0xFE
+        let m: Magic = from_str(r#"{"pattern":[97,92,255,92,92,254]}"#)
+            .unwrap();
+        assert_eq!(m.pattern.as_str(), r"a(?-u:\xFF)\\(?-u:\xFE)");
+        assert!(m.pattern.is_match(b"a\xff\\\xfe"));
+    }
+
+    #[test]
+    fn test_class() {
+        // `[a` followed by 0xFF and `]`
+        assert!(from_str::<Magic>(r#"{"pattern":[91,97,255,93]}"#).is_err());
+        // `[]]` followed by 0xFF
+        let m: Magic = from_str(r#"{"pattern":[91,93,93,255]}"#).unwrap();
+        assert!(m.pattern.is_match(b"]\xff"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Deserialization which normalizes bracketed character classes
+//!
+//! Literals and ranges of every bracketed class are sorted and merged, so
+//! `[cba]`, `[abc]` and `[a-bc]` are all stored as `[a-c]`. This keeps
+//! stored patterns (and their hashes) stable across authoring variations.
+//! Only classes are affected, the rest of the pattern is re-printed from
+//! the `regex-syntax` AST as is. Other class members, like `\d` or nested
+//! classes, are kept in original order in front of the merged ranges.
+//!
+//! Serialization emits the normalized pattern.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::canonical_classes")]
+//!     pattern: Regex,
+//! }
+//!
+//! let config: Config = serde_json::from_str(
+//!     r#"{"pattern": "x[cba]+"}"#).unwrap();
+//! assert_eq!(config.pattern.as_str(), "x[a-c]+");
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    Ast,
+    ClassSet,
+    ClassSetItem,
+    ClassSetRange,
+    Literal,
+    LiteralKind,
+    parse::Parser,
+    print::Printer,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit_mut;
+
+fn canonicalize_set(set: &mut ClassSet) {
+    match set {
+        ClassSet::Item(item) => canonicalize_item(item),
+        ClassSet::BinaryOp(op) => {
+            canonicalize_set(&mut op.lhs);
+            canonicalize_set(&mut op.rhs);
+        }
+    }
+}
+
+/// Escapes characters that may change class meaning once literals are
+/// reordered (e.g. a `^` moved to the front or a `-` between two literals)
+fn escape_literal(mut lit: Literal) -> Literal {
+    if lit.kind == LiteralKind::Verbatim &&
+        matches!(lit.c, '^' | ']' | '-' | '[' | '&' | '~' | '\\')
+    {
+        lit.kind = LiteralKind::Meta;
+    }
+    lit
+}
+
+fn canonicalize_item(item: &mut ClassSetItem) {
+    let union = match item {
+        ClassSetItem::Bracketed(class) => {
+            return canonicalize_set(&mut class.kind);
+        }
+        ClassSetItem::Union(union) => union,
+        _ => return,
+    };
+    let mut ranges: Vec<(Literal, Literal)> = Vec::new();
+    let mut items = Vec::new();
+    for mut item in union.items.drain(..) {
+        match item {
+            ClassSetItem::Literal(lit) => ranges.push((lit.clone(), lit)),
+            ClassSetItem::Range(range) => {
+                ranges.push((range.start, range.end));
+            }
+            _ => {
+                canonicalize_item(&mut item);
+                items.push(item);
+            }
+        }
+    }
+    ranges.sort_by_key(|(start, end)| (start.c, end.c));
+    let mut merged: Vec<(Literal, Literal)> = Vec::new();
+    for (start, end) in ranges {
+        if let Some(last) = merged.last_mut() {
+            if start.c as u32 <= last.1.c as u32 + 1 {
+                if end.c > last.1.c {
+                    last.1 = end;
+                }
+                continue;
+            }
+        }
+        merged.push((start, end));
+    }
+    let span = union.span;
+    items.extend(merged.into_iter().map(|(start, end)| {
+        let start = escape_literal(start);
+        let end = escape_literal(end);
+        if start.c == end.c {
+            ClassSetItem::Literal(start)
+        } else {
+            ClassSetItem::Range(ClassSetRange { span, start, end })
+        }
+    }));
+    union.items = items;
+}
+
+fn canonicalize(pattern: &str)
+    -> Result<String, Box<regex_syntax::ast::Error>>
+{
+    let mut ast = Parser::new().parse(pattern)?;
+    visit_mut(&mut ast, &mut |node| {
+        if let Ast::ClassBracketed(class) = node {
+            canonicalize_set(&mut class.kind);
+        }
+    });
+    let mut result = String::with_capacity(pattern.len());
+    Printer::new().print(&ast, &mut result).expect("writing to string");
+    Ok(result)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let canonical = canonicalize(&s).map_err(D::Error::custom)?;
+    Regex::new(&canonical).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use super::canonicalize;
+
+    fn assert_same_matches(pattern: &str, inputs: &[&str]) {
+        let original = Regex::new(pattern).unwrap();
+        let canonical = Regex::new(&canonicalize(pattern).unwrap()).unwrap();
+        for input in inputs {
+            assert_eq!(original.is_match(input), canonical.is_match(input),
+                "{:?} vs {:?} on {:?}", original, canonical, input);
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(canonicalize("[cba]").unwrap(), "[a-c]");
+        assert_eq!(canonicalize("[a-bc]").unwrap(), "[a-c]");
+        assert_eq!(canonicalize("[x0-9a-f5]").unwrap(), "[0-9a-fx]");
+        assert_eq!(canonicalize("[^zy]").unwrap(), "[^y-z]");
+        assert_eq!(canonicalize(r"[z\da]").unwrap(), r"[\daz]");
+        assert_eq!(canonicalize(r"[[ba]&&a]").unwrap(), r"[[a-b]&&a]");
+    }
+
+    #[test]
+    fn test_untouched() {
+        assert_eq!(canonicalize(r"cba(\d+)|\]").unwrap(), r"cba(\d+)|\]");
+        assert_eq!(canonicalize(r"[\]\-]").unwrap(), r"[\-\]]");
+    }
+
+    #[test]
+    fn test_special_literals() {
+        assert_eq!(canonicalize("[a^]").unwrap(), r"[\^a]");
+        assert_eq!(canonicalize("[]0]").unwrap(), r"[0\]]");
+        assert_eq!(canonicalize("[-+.]").unwrap(), r"[+\--.]");
+        let inputs = &["a", "^", "b", "]", "0", "-", "+", ".", ",",
+                       "[", "&", "~", "\\", "x"];
+        assert_same_matches("[a^]", inputs);
+        assert_same_matches("[]0]", inputs);
+        assert_same_matches("[-+.]", inputs);
+        assert_same_matches("[^-a]", inputs);
+        assert_same_matches(r"[\\~&\[]", inputs);
+        assert_same_matches(r"[\]-a]", inputs);
+    }
+}
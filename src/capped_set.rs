@@ -0,0 +1,71 @@
+//! Deserialization of `RegexSet` with a limit on number of patterns
+//!
+//! Works like [`capped_vec`](crate::capped_vec) but builds a `RegexSet`.
+//! The default limit is
+//! [`DEFAULT_MAX_PATTERNS`](crate::capped_vec::DEFAULT_MAX_PATTERNS).
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::RegexSet;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filters {
+//!     #[serde(
+//!         serialize_with = "serde_regex::capped_set::serialize",
+//!         deserialize_with = "serde_regex::capped_set::deserialize_max::<_, 100>",
+//!     )]
+//!     patterns: RegexSet,
+//! }
+//! ```
+use std::borrow::Cow;
+
+use regex::RegexSet;
+use serde::{Deserializer, Serialize, Serializer, de::Error};
+
+use crate::capped_vec::{CappedSeqVisitor, DEFAULT_MAX_PATTERNS};
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<RegexSet, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_max::<D, DEFAULT_MAX_PATTERNS>(d)
+}
+
+/// Deserialize function allowing at most `MAX` patterns
+pub fn deserialize_max<'de, D, const MAX: usize>(d: D)
+    -> Result<RegexSet, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let patterns = d.deserialize_seq(
+        CappedSeqVisitor::<Cow<str>, MAX>::new())?;
+    RegexSet::new(patterns).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &RegexSet, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    value.patterns().serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Deserializer;
+
+    use super::deserialize_max;
+
+    #[test]
+    fn test_limit() {
+        let set = deserialize_max::<_, 2>(
+            &mut Deserializer::from_str(r#"["a", "b"]"#)).unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(deserialize_max::<_, 2>(
+            &mut Deserializer::from_str(r#"["a", "b", "c"]"#)).is_err());
+    }
+}
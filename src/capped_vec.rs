@@ -0,0 +1,140 @@
+//! Deserialization of `Vec<Regex>` with a limit on number of patterns
+//!
+//! Untrusted input may contain millions of patterns. This module fails with
+//! an `invalid_length` error if the sequence is longer than the limit. The
+//! length reported by the format is checked before anything is compiled,
+//! and the limit is enforced while reading elements too.
+//!
+//! The default limit is [`DEFAULT_MAX_PATTERNS`]. Use `deserialize_max`
+//! with an explicit limit to override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filters {
+//!     #[serde(with = "serde_regex::capped_vec")]
+//!     include: Vec<Regex>,
+//!     #[serde(
+//!         serialize_with = "serde_regex::capped_vec::serialize",
+//!         deserialize_with = "serde_regex::capped_vec::deserialize_max::<_, 2>",
+//!     )]
+//!     exclude: Vec<Regex>,
+//! }
+//!
+//! assert!(serde_json::from_str::<Filters>(r#"{
+//!     "include": ["a", "b", "c"],
+//!     "exclude": ["x", "y", "z"]
+//! }"#).is_err());
+//! ```
+use std::{fmt, marker::PhantomData};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+use crate::Serde;
+
+/// Number of patterns allowed by default
+pub const DEFAULT_MAX_PATTERNS: usize = 1024;
+
+pub(crate) struct CappedSeqVisitor<T, const MAX: usize>(PhantomData<T>);
+
+impl<T, const MAX: usize> CappedSeqVisitor<T, MAX> {
+    pub(crate) fn new() -> Self {
+        CappedSeqVisitor(PhantomData)
+    }
+}
+
+impl<'a, T, const MAX: usize> Visitor<'a> for CappedSeqVisitor<T, MAX>
+where
+    T: Deserialize<'a>,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "sequence of at most {} patterns", MAX)
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'a>,
+    {
+        let mut vec = match seq.size_hint() {
+            Some(size) if size > MAX => {
+                return Err(A::Error::invalid_length(size, &self));
+            }
+            Some(size) => Vec::with_capacity(size),
+            None => Vec::new(),
+        };
+        while let Some(el) = seq.next_element()? {
+            if vec.len() == MAX {
+                return Err(A::Error::invalid_length(MAX + 1, &self));
+            }
+            vec.push(el);
+        }
+        Ok(vec)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_max::<D, DEFAULT_MAX_PATTERNS>(d)
+}
+
+/// Deserialize function allowing at most `MAX` patterns
+pub fn deserialize_max<'de, D, const MAX: usize>(d: D)
+    -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let vec = d.deserialize_seq(CappedSeqVisitor::<Serde<Regex>, MAX>::new())?;
+    Ok(vec.into_iter().map(Serde::into_inner).collect())
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Vec<Regex>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{Deserializer, from_str};
+
+    use super::deserialize_max;
+
+    fn load<const MAX: usize>(json: &str) -> Result<Vec<Regex>, String> {
+        deserialize_max::<_, MAX>(&mut Deserializer::from_str(json))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(load::<2>(r#"["a", "b"]"#).unwrap().len(), 2);
+        assert_eq!(load::<2>(r#"["a", "b", "c"]"#).unwrap_err(),
+            "invalid length 3, expected sequence of at most 2 patterns \
+             at line 1 column 15");
+        assert!(load::<0>(r#"[]"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let value = from_str::<serde_json::Value>(r#"["a", "b("]"#).unwrap();
+        // size hint is checked before invalid pattern is compiled
+        let err = deserialize_max::<_, 1>(value).unwrap_err();
+        assert_eq!(err.to_string(),
+            "invalid length 2, expected sequence of at most 1 patterns");
+    }
+}
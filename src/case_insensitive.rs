@@ -0,0 +1,158 @@
+use std::{
+    borrow::Cow,
+    ops::{Deref, DerefMut},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+const FLAG: &str = "(?i)";
+
+/// A regex whose case sensitivity is chosen by the type
+///
+/// With `ON = true` patterns are made case-insensitive by prepending an
+/// inline `(?i)` flag (unless the pattern already starts with it), with
+/// `ON = false` they are compiled as is. Because the flag is part of the
+/// stored pattern, serialized output has the same meaning whatever type is
+/// used to read it back.
+///
+/// ```rust
+/// use serde_regex::CaseInsensitive;
+///
+/// let re: CaseInsensitive<true> = serde_json::from_str(r#""abc""#).unwrap();
+/// assert!(re.is_match("ABC"));
+/// assert_eq!(serde_json::to_string(&re).unwrap(), r#""(?i)abc""#);
+/// ```
+///
+/// With the `serde_with` feature it can also be used as an adapter for
+/// plain `Regex` fields, e.g. `#[serde_as(as = "CaseInsensitive<true>")]`.
+#[derive(Debug, Clone)]
+pub struct CaseInsensitive<const ON: bool>(pub Regex);
+
+fn compile<const ON: bool>(pattern: &str) -> Result<Regex, regex::Error> {
+    if ON && !pattern.starts_with(FLAG) {
+        Regex::new(&format!("{}{}", FLAG, pattern))
+    } else {
+        Regex::new(pattern)
+    }
+}
+
+fn serialize_regex<S, const ON: bool>(regex: &Regex, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let pattern = regex.as_str();
+    if ON && !pattern.starts_with(FLAG) {
+        serializer.collect_str(&format_args!("{}{}", FLAG, pattern))
+    } else {
+        pattern.serialize(serializer)
+    }
+}
+
+fn deserialize_regex<'de, D, const ON: bool>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    compile::<ON>(&s).map_err(D::Error::custom)
+}
+
+impl<'de, const ON: bool> Deserialize<'de> for CaseInsensitive<ON> {
+    fn deserialize<D>(d: D) -> Result<CaseInsensitive<ON>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_regex::<D, ON>(d).map(CaseInsensitive)
+    }
+}
+
+impl<const ON: bool> Serialize for CaseInsensitive<ON> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_regex::<S, ON>(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde_with")]
+impl<const ON: bool> serde_with::SerializeAs<Regex> for CaseInsensitive<ON> {
+    fn serialize_as<S>(source: &Regex, serializer: S)
+        -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_regex::<S, ON>(source, serializer)
+    }
+}
+
+#[cfg(feature = "serde_with")]
+impl<'de, const ON: bool> serde_with::DeserializeAs<'de, Regex>
+    for CaseInsensitive<ON>
+{
+    fn deserialize_as<D>(d: D) -> Result<Regex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_regex::<D, ON>(d)
+    }
+}
+
+impl<const ON: bool> Deref for CaseInsensitive<ON> {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl<const ON: bool> DerefMut for CaseInsensitive<ON> {
+    fn deref_mut(&mut self) -> &mut Regex {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    use super::CaseInsensitive;
+
+    #[test]
+    fn test_on() {
+        let re: CaseInsensitive<true> = from_str(r#""a+""#).unwrap();
+        assert!(re.is_match("AA"));
+        let json = to_string(&re).unwrap();
+        assert_eq!(json, r#""(?i)a+""#);
+        let back: CaseInsensitive<false> = from_str(&json).unwrap();
+        assert!(back.is_match("AA"));
+        assert_eq!(to_string(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn test_off() {
+        let re: CaseInsensitive<false> = from_str(r#""a+""#).unwrap();
+        assert!(!re.is_match("AA"));
+        assert_eq!(to_string(&re).unwrap(), r#""a+""#);
+        let on = CaseInsensitive::<true>(re.0);
+        assert_eq!(to_string(&on).unwrap(), r#""(?i)a+""#);
+    }
+
+    #[cfg(feature = "serde_with")]
+    #[test]
+    fn test_serde_with() {
+        use regex::Regex;
+        use serde_derive::{Serialize, Deserialize};
+        use serde_with::As;
+
+        #[derive(Serialize, Deserialize)]
+        struct Config {
+            #[serde(with = "As::<CaseInsensitive<true>>")]
+            pattern: Regex,
+        }
+        let config: Config = from_str(r#"{"pattern":"a"}"#).unwrap();
+        assert!(config.pattern.is_match("A"));
+        assert_eq!(to_string(&config).unwrap(), r#"{"pattern":"(?i)a"}"#);
+    }
+}
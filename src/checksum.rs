@@ -0,0 +1,138 @@
+//! (De)serialization of patterns with a SHA-256 checksum
+//!
+//! Enabled by the `sha2` feature. The regex is serialized as a map with the
+//! pattern and its checksum:
+//!
+//! ```json
+//! { "pattern": "^a+$", "sha256": "<64 hex digits>" }
+//! ```
+//!
+//! The checksum is SHA-256 of the raw pattern bytes, i.e. of the UTF-8
+//! encoded string exactly as returned by `Regex::as_str()`, without any
+//! normalization, quoting or trailing newline. It's written as lowercase
+//! hex, but either case is accepted when reading.
+//!
+//! On deserialization the checksum is verified before the pattern is
+//! compiled, and a mismatch is an error. Both keys are required. Note that
+//! a checksum only detects accidental or naive modification: anyone who
+//! can change the pattern can update the checksum too, so pair this with a
+//! signature of the whole document if tampering is a concern.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::checksum")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule = Rule { pattern: Regex::new("abc").unwrap() };
+//! let json = serde_json::to_string(&rule).unwrap();
+//! assert!(json.contains(
+//!     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+//!
+//! let tampered = json.replace("abc", "abd");
+//! assert!(serde_json::from_str::<Rule>(&tampered).is_err());
+//! ```
+use std::fmt::Write;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+use sha2::{Digest, Sha256};
+
+struct Checked {
+    pattern: String,
+    sha256: String,
+}
+
+deserialize_struct!(Checked {
+    pattern: String,
+    sha256: String,
+});
+
+struct CheckedRef<'a> {
+    pattern: &'a str,
+    sha256: String,
+}
+
+serialize_struct!(CheckedRef<'_> as CheckedRef { pattern, sha256 });
+
+fn sha256_hex(pattern: &str) -> String {
+    let mut hex = String::with_capacity(64);
+    for byte in Sha256::digest(pattern.as_bytes()) {
+        write!(hex, "{:02x}", byte).expect("can write to string");
+    }
+    hex
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let checked = Checked::deserialize(d)?;
+    let actual = sha256_hex(&checked.pattern);
+    if !actual.eq_ignore_ascii_case(&checked.sha256) {
+        return Err(D::Error::custom(format_args!(
+            "sha256 mismatch for pattern {:?}: expected {}, got {}",
+            checked.pattern, checked.sha256, actual)));
+    }
+    Regex::new(&checked.pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    CheckedRef {
+        pattern: value.as_str(),
+        sha256: sha256_hex(value.as_str()),
+    }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{Value, json};
+
+    use super::{deserialize, serialize, sha256_hex};
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(sha256_hex(""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let regex = Regex::new(r"^\d+é$").unwrap();
+        let value = serialize(&regex, serde_json::value::Serializer).unwrap();
+        assert_eq!(value["pattern"], r"^\d+é$");
+        let back = deserialize(value.clone()).unwrap();
+        assert_eq!(back.as_str(), regex.as_str());
+
+        let upper = json!({
+            "pattern": value["pattern"],
+            "sha256": value["sha256"].as_str().unwrap().to_uppercase(),
+        });
+        assert!(deserialize(upper).is_ok());
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad = json!({"pattern": "a", "sha256": sha256_hex("b")});
+        assert!(deserialize(bad).unwrap_err().to_string()
+            .starts_with(r#"sha256 mismatch for pattern "a""#));
+        assert!(deserialize(json!({"pattern": "a"})).is_err());
+        assert!(deserialize(Value::from("a")).is_err());
+        // a valid checksum of an invalid pattern is still a compile error
+        let invalid = json!({"pattern": "(", "sha256": sha256_hex("(")});
+        assert!(deserialize(invalid).unwrap_err().to_string()
+            .contains("regex parse error"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Helpers for generating Rust source code embedding patterns
+//!
+//! This is not serde per se, but it's the same problem of writing a
+//! pattern out in a format which reads back into the same regex: here the
+//! format is a Rust string literal.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_regex::codegen::to_rust_literal;
+//!
+//! let regex = Regex::new(r##"^"\w+"#\d$"##).unwrap();
+//! let code = format!("let re = Regex::new({}).unwrap();",
+//!     to_rust_literal(&regex));
+//! assert_eq!(code, r###"let re = Regex::new(r##"^"\w+"#\d$"##).unwrap();"###);
+//! ```
+use regex::Regex;
+
+/// Returns a Rust string literal for the pattern, raw if possible
+///
+/// The pattern is written as a raw string, so backslashes are kept as is:
+/// `\d+` becomes `r"\d+"`. The number of `#` is the smallest one which
+/// doesn't conflict with the pattern: none if the pattern has no `"`,
+/// `r#"..."#` if it contains `"`, `r##"..."##` if it contains `"#`, and so
+/// on.
+///
+/// A raw string can't contain a carriage return (`\r` as an actual
+/// character, not the escape), so for such patterns a regular string
+/// literal with escapes is produced instead, e.g. `"a\r\\d"`. The result is
+/// always a valid literal for exactly the same pattern.
+pub fn to_rust_literal(regex: &Regex) -> String {
+    literal(regex.as_str())
+}
+
+fn literal(pattern: &str) -> String {
+    if pattern.contains('\r') {
+        return format!("{:?}", pattern);
+    }
+    let hashes = pattern.match_indices('"')
+        .map(|(idx, _)| {
+            pattern[idx + 1..].bytes().take_while(|&b| b == b'#').count() + 1
+        })
+        .max()
+        .unwrap_or(0);
+    let hashes = "#".repeat(hashes);
+    format!("r{}\"{}\"{}", hashes, pattern, hashes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::literal;
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal(r"\d+"), r#"r"\d+""#);
+        assert_eq!(literal(""), r#"r"""#);
+        assert_eq!(literal(r#"a"b"#), r##"r#"a"b"#"##);
+        assert_eq!(literal(r##"a"#b"##), r###"r##"a"#b"##"###);
+        assert_eq!(literal(r###"a"##b"#c""###), r####"r###"a"##b"#c""###"####);
+        assert_eq!(literal("#a#"), r##"r"#a#""##);
+        assert_eq!(literal("a\r\\d\"é"), r#""a\r\\d\"é""#);
+    }
+}
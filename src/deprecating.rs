@@ -0,0 +1,89 @@
+//! Deserialization which warns about deprecated constructs
+//!
+//! Enabled by the `log` feature. Patterns are compiled as usual, but if a
+//! pattern contains any of the substrings registered with
+//! [`set_deprecated`], a `log::warn!` message naming the construct is
+//! emitted. This is advisory only and never fails deserialization, so it
+//! can be used to drive gradual migration of many config files.
+//!
+//! The list of deprecated constructs is global and empty by default.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::deprecating")]
+//!     pattern: Regex,
+//! }
+//!
+//! serde_regex::deprecating::set_deprecated(vec!["(?s)", r"\C"]);
+//! // still loads, but logs a warning
+//! let config: Config = serde_json::from_str(r#"{"pattern": "(?s)a.b"}"#)
+//!     .unwrap();
+//! ```
+use std::{borrow::Cow, sync::RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+static DEPRECATED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Replaces the global list of deprecated substrings
+pub fn set_deprecated<I, S>(constructs: I)
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let constructs = constructs.into_iter().map(Into::into).collect();
+    *DEPRECATED.write().unwrap_or_else(|e| e.into_inner()) = constructs;
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    let deprecated = DEPRECATED.read().unwrap_or_else(|e| e.into_inner());
+    for construct in deprecated.iter() {
+        if s.contains(&construct[..]) {
+            log::warn!("pattern {:?} uses deprecated construct {:?}",
+                s, construct);
+        }
+    }
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use crate::test_log::capture;
+    use super::{deserialize, set_deprecated};
+
+    #[test]
+    fn test_warn() {
+        set_deprecated(vec!["(?s)"]);
+        let messages = capture(|| {
+            deserialize(Value::from("(?s)a.b")).unwrap();
+            deserialize(Value::from("a.b")).unwrap();
+        });
+        set_deprecated(Vec::<String>::new());
+        assert_eq!(messages, vec![
+            r#"WARN: pattern "(?s)a.b" uses deprecated construct "(?s)""#,
+        ]);
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+
+use regex_automata::{
+    Input,
+    dfa::{Automaton, dense},
+};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+/// A precompiled DFA which is (de)serialized as an automaton
+///
+/// Requires the `regex-automata` feature. Unlike `Serde<Regex>`, which
+/// stores the pattern and compiles it on every load, this type stores a
+/// fully built dense DFA from the `regex-automata` crate. Deserialization
+/// only validates and copies the automaton, so there is no compilation at
+/// runtime, which helps startup-critical programs and lets a build step
+/// produce matchers embedded into the binary.
+///
+/// It's serialized as a map with the pattern (kept for humans, it isn't
+/// used for matching) and the DFA in the little-endian binary format of
+/// `regex-automata`, written with `serialize_bytes`:
+///
+/// ```json
+/// { "pattern": "a+b", "dfa": [114, 117, 115, 116, ...] }
+/// ```
+///
+/// The tradeoff is size: a dense DFA takes at least a kilobyte even for a
+/// trivial pattern and grows quickly with Unicode classes and counted
+/// repetition (`\w{10}` is already hundreds of kilobytes), compared to a
+/// few bytes for the pattern. Use a binary format like `bincode` or CBOR
+/// rather than JSON, which writes every byte as a number. The DFA only
+/// reports whether and where a match ends, see [`is_match`] and
+/// [`find_end`]; it has no capture groups. Only little-endian targets can
+/// load the DFA, and a DFA written by an incompatible version of
+/// `regex-automata` is a deserialization error.
+///
+/// ```rust
+/// use serde_regex::SerializableDfa;
+///
+/// let dfa = SerializableDfa::new(r"[0-9]+-[0-9]+").unwrap();
+/// let stored = serde_json::to_vec(&dfa).unwrap();
+///
+/// let loaded: SerializableDfa = serde_json::from_slice(&stored).unwrap();
+/// assert_eq!(loaded.as_str(), "[0-9]+-[0-9]+");
+/// assert!(loaded.is_match(b"pages 10-12"));
+/// assert!(!loaded.is_match(b"page 10"));
+/// ```
+///
+/// [`is_match`]: SerializableDfa::is_match
+/// [`find_end`]: SerializableDfa::find_end
+#[derive(Clone)]
+pub struct SerializableDfa {
+    pattern: String,
+    dfa: dense::DFA<Vec<u32>>,
+}
+
+impl SerializableDfa {
+    /// Builds a DFA for the pattern with default settings
+    pub fn new(pattern: &str)
+        -> Result<SerializableDfa, Box<dense::BuildError>>
+    {
+        let dfa = dense::DFA::new(pattern).map_err(Box::new)?;
+        Ok(SerializableDfa { pattern: pattern.to_string(), dfa })
+    }
+
+    /// Returns the pattern the DFA was built from
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the underlying DFA, e.g. for searches with custom settings
+    pub fn dfa(&self) -> &dense::DFA<Vec<u32>> {
+        &self.dfa
+    }
+
+    /// Returns true if the DFA matches anywhere in the haystack
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        self.find_end(haystack).is_some()
+    }
+
+    /// Returns the end offset of the leftmost-first match, if any
+    pub fn find_end(&self, haystack: &[u8]) -> Option<usize> {
+        // a DFA built without quit bytes never fails the search
+        self.dfa.try_search_fwd(&Input::new(haystack)).ok()
+            .flatten()
+            .map(|half| half.offset())
+    }
+}
+
+impl fmt::Debug for SerializableDfa {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SerializableDfa")
+            .field("pattern", &self.pattern)
+            .field("memory_usage", &self.dfa.memory_usage())
+            .finish()
+    }
+}
+
+struct BytesRef<'a>(&'a [u8]);
+
+impl Serialize for BytesRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+struct ByteBuf(Vec<u8>);
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("serialized DFA bytes")
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<ByteBuf, E>
+    where
+        E: Error,
+    {
+        Ok(ByteBuf(v.to_vec()))
+    }
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<ByteBuf, E>
+    where
+        E: Error,
+    {
+        Ok(ByteBuf(v))
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<ByteBuf, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(ByteBuf(bytes))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(d: D) -> Result<ByteBuf, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+struct StoredRef<'a> {
+    pattern: &'a str,
+    dfa: BytesRef<'a>,
+}
+
+serialize_struct!(StoredRef<'_> as StoredRef { pattern, dfa });
+
+struct Stored {
+    pattern: String,
+    dfa: ByteBuf,
+}
+
+deserialize_struct!(Stored {
+    pattern: String,
+    dfa: ByteBuf,
+});
+
+/// Loads a DFA from bytes which may be arbitrarily aligned
+fn load(bytes: &[u8]) -> Result<dense::DFA<Vec<u32>>, String> {
+    // `from_bytes` needs the data aligned to `u32`, so copy it to an
+    // aligned offset of a slightly larger buffer
+    let mut buffer = vec![0u8; bytes.len() + 3];
+    let offset = buffer.as_ptr().align_offset(4);
+    let aligned = &mut buffer[offset..offset + bytes.len()];
+    aligned.copy_from_slice(bytes);
+    let (dfa, _) = dense::DFA::from_bytes(aligned)
+        .map_err(|e| format!("invalid serialized DFA: {}", e))?;
+    Ok(dfa.to_owned())
+}
+
+impl<'de> Deserialize<'de> for SerializableDfa {
+    fn deserialize<D>(d: D) -> Result<SerializableDfa, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let stored = Stored::deserialize(d)?;
+        let dfa = load(&stored.dfa.0).map_err(D::Error::custom)?;
+        Ok(SerializableDfa { pattern: stored.pattern, dfa })
+    }
+}
+
+impl Serialize for SerializableDfa {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (bytes, padding) = self.dfa.to_bytes_little_endian();
+        StoredRef {
+            pattern: &self.pattern,
+            dfa: BytesRef(&bytes[padding..]),
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_slice, from_value, json, to_value, to_vec};
+
+    use super::{SerializableDfa, load};
+
+    #[test]
+    fn test_round_trip() {
+        let dfa = SerializableDfa::new(r"(?i)error: \d+").unwrap();
+        let loaded: SerializableDfa = from_slice(&to_vec(&dfa).unwrap())
+            .unwrap();
+        assert_eq!(loaded.as_str(), dfa.as_str());
+        assert!(loaded.is_match(b"x ERROR: 42"));
+        assert!(!loaded.is_match(b"error: x"));
+        assert_eq!(loaded.find_end(b"error: 12 error: 3"), Some(9));
+    }
+
+    #[test]
+    fn test_unaligned() {
+        let dfa = SerializableDfa::new("a+").unwrap();
+        let (bytes, padding) = dfa.dfa().to_bytes_little_endian();
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes[padding..]);
+        assert!(load(&shifted[1..]).unwrap().memory_usage() > 0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let value = to_value(SerializableDfa::new("a").unwrap()).unwrap();
+        let mut broken = value.clone();
+        broken["dfa"][0] = json!(0);
+        assert!(from_value::<SerializableDfa>(broken).unwrap_err().to_string()
+            .starts_with("invalid serialized DFA"));
+        assert!(from_value::<SerializableDfa>(json!({"pattern": "a"}))
+            .is_err());
+        assert!(from_value::<SerializableDfa>(json!("a")).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Comparison of two versions of a pattern list
+//!
+//! Helpers for "what changed" reports between two versions of a config,
+//! e.g. a `RegexSet` or a `Vec<Regex>` loaded with `serde_regex` from an
+//! old and a new file. Comparison is by pattern string: patterns which
+//! differ only in spelling, like `a+` and `aa*`, are reported as changed
+//! even though they match the same text.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::{Regex, RegexSet};
+//! use serde_regex::diff::{set_diff, vec_diff};
+//!
+//! let old = RegexSet::new(["^a", "^b", "^c"]).unwrap();
+//! let new = RegexSet::new(["^c", "^d", "^a"]).unwrap();
+//! let (added, removed) = set_diff(&old, &new);
+//! assert_eq!(added, ["^d"]);
+//! assert_eq!(removed, ["^b"]);
+//!
+//! // for a list, order matters: "^a" moved to the end
+//! let old = ["^a", "^b", "^c"].map(|p| Regex::new(p).unwrap());
+//! let new = ["^b", "^c", "^a"].map(|p| Regex::new(p).unwrap());
+//! let (added, removed) = vec_diff(&old, &new);
+//! assert_eq!(added, [(2, "^a".to_string())]);
+//! assert_eq!(removed, [(0, "^a".to_string())]);
+//! ```
+use std::collections::HashSet;
+
+use regex::{Regex, RegexSet};
+
+/// Patterns with their indices in a list, see [`vec_diff`]
+pub type IndexedPatterns = Vec<(usize, String)>;
+
+/// Returns patterns added in `new` and removed from `old`
+///
+/// Sets are compared as unordered collections of unique patterns. Both
+/// lists keep the order of the set they come from and contain every
+/// pattern once.
+pub fn set_diff(old: &RegexSet, new: &RegexSet) -> (Vec<String>, Vec<String>) {
+    let old_patterns = old.patterns().iter().collect::<HashSet<_>>();
+    let new_patterns = new.patterns().iter().collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    let added = new.patterns().iter()
+        .filter(|p| !old_patterns.contains(p) && seen.insert(*p))
+        .cloned()
+        .collect();
+    let removed = old.patterns().iter()
+        .filter(|p| !new_patterns.contains(p) && seen.insert(*p))
+        .cloned()
+        .collect();
+    (added, removed)
+}
+
+/// Returns an order-aware diff of two lists of regexes
+///
+/// The result is `(added, removed)`, where `added` has patterns with their
+/// indices in `new` and `removed` has patterns with their indices in `old`.
+/// The patterns not listed form the longest common subsequence of both
+/// lists, so a pattern which moved relative to others is reported as both
+/// removed and added, as in a line-based diff. Takes time and memory
+/// proportional to the product of list lengths.
+pub fn vec_diff(old: &[Regex], new: &[Regex])
+    -> (IndexedPatterns, IndexedPatterns)
+{
+    // common[i][j] is the length of the common subsequence of old[i..]
+    // and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i].as_str() == new[j].as_str() {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len()
+            && old[i].as_str() == new[j].as_str()
+        {
+            i += 1;
+            j += 1;
+        } else if j < new.len()
+            && (i == old.len() || common[i][j + 1] >= common[i + 1][j])
+        {
+            added.push((j, new[j].as_str().to_string()));
+            j += 1;
+        } else {
+            removed.push((i, old[i].as_str().to_string()));
+            i += 1;
+        }
+    }
+    (added, removed)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::{Regex, RegexSet};
+
+    use super::{set_diff, vec_diff};
+
+    fn regexes(patterns: &[&str]) -> Vec<Regex> {
+        patterns.iter().map(|p| Regex::new(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_set_diff() {
+        let old = RegexSet::new(["a", "b", "b", "c"]).unwrap();
+        let new = RegexSet::new(["d", "c", "d", "a+"]).unwrap();
+        let (added, removed) = set_diff(&old, &new);
+        assert_eq!(added, ["d", "a+"]);
+        assert_eq!(removed, ["a", "b"]);
+        let (added, removed) = set_diff(&old, &old);
+        assert!(added.is_empty() && removed.is_empty());
+        let (added, removed) = set_diff(&RegexSet::empty(), &old);
+        assert_eq!((added.len(), removed.len()), (3, 0));
+    }
+
+    #[test]
+    fn test_vec_diff() {
+        let same = regexes(&["a", "b"]);
+        assert_eq!(vec_diff(&same, &same), (vec![], vec![]));
+        let (added, removed) = vec_diff(&regexes(&["a", "b", "c"]),
+                                        &regexes(&["a", "x", "c", "d"]));
+        assert_eq!(added, [(1, "x".into()), (3, "d".into())]);
+        assert_eq!(removed, [(1, "b".into())]);
+        let (added, removed) = vec_diff(&regexes(&["a", "b"]),
+                                        &regexes(&["b", "a"]));
+        assert_eq!((added.len(), removed.len()), (1, 1));
+        let (added, removed) = vec_diff(&[], &regexes(&["a", "a"]));
+        assert_eq!(added, [(0, "a".into()), (1, "a".into())]);
+        assert!(removed.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! (De)serialization of optional patterns with a "disabled" sentinel
+//!
+//! In configs with many switchable rules, a field can be kept in place but
+//! turned off without a separate `enabled` flag. This module deserializes
+//! an `Option<Regex>`, where any of the following means `None`:
+//!
+//! * `false`;
+//! * `null` (or any other "none" value of the format);
+//! * the string `"disabled"` ([`SENTINEL`]), for formats which only have
+//!   strings, like environment variables.
+//!
+//! Any other string is compiled into `Some(regex)`, while `true` and other
+//! types are an error. `None` is serialized as `false`.
+//!
+//! A pattern matching the literal text `disabled` can't be written as is,
+//! use e.g. `(?:disabled)` instead. Serialization does the same, so
+//! `Some(Regex::new("disabled")?)` is written as `"(?:disabled)"`.
+//!
+//! Since the sentinels are told apart by type, this needs a self-describing
+//! format (JSON, YAML, TOML and the like), the same as `deserialize_any`.
+//!
+//! # Missing fields
+//!
+//! As with other `with` modules, a missing field is an error unless the
+//! field has `#[serde(default)]`, in which case it's `None`, i.e. a missing
+//! field means disabled. Use this module without `default` if every rule
+//! should be listed explicitly, either as a pattern or as `false`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rules {
+//!     #[serde(with = "serde_regex::disabled")]
+//!     spam: Option<Regex>,
+//!     #[serde(with = "serde_regex::disabled", default)]
+//!     ads: Option<Regex>,
+//! }
+//!
+//! let rules: Rules = serde_json::from_str(r#"{"spam": false}"#).unwrap();
+//! assert!(rules.spam.is_none());
+//! assert!(rules.ads.is_none());
+//! let rules: Rules = serde_json::from_str(r#"{
+//!     "spam": "(?i)viagra",
+//!     "ads": "disabled"
+//! }"#).unwrap();
+//! assert!(rules.spam.unwrap().is_match("VIAGRA"));
+//! assert!(rules.ads.is_none());
+//! assert!(serde_json::from_str::<Rules>("{}").is_err());
+//! ```
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserializer, Serializer, de::{Error, Unexpected, Visitor}};
+
+use crate::Serde;
+
+/// String accepted as `None`
+pub const SENTINEL: &str = "disabled";
+
+struct DisabledVisitor;
+
+impl<'de> Visitor<'de> for DisabledVisitor {
+    type Value = Option<Regex>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter,
+            "valid regular expression, false, null or {:?}", SENTINEL)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Option<Regex>, E>
+    where
+        E: Error,
+    {
+        if value == SENTINEL {
+            return Ok(None);
+        }
+        Regex::new(value).map(Some).map_err(E::custom)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Option<Regex>, E>
+    where
+        E: Error,
+    {
+        if value {
+            return Err(E::invalid_value(Unexpected::Bool(value), &self));
+        }
+        Ok(None)
+    }
+
+    fn visit_none<E>(self) -> Result<Option<Regex>, E>
+    where
+        E: Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Option<Regex>, E>
+    where
+        E: Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, d: D) -> Result<Option<Regex>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(self)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(DisabledVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Option<Regex>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(regex) if regex.as_str() == SENTINEL => {
+            serializer.serialize_some(&format!("(?:{})", SENTINEL))
+        }
+        Some(regex) => serializer.serialize_some(&Serde(regex)),
+        None => serializer.serialize_bool(false),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{Value, json};
+
+    use super::deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::disabled")]
+        re: Option<Regex>,
+    }
+
+    #[test]
+    fn test_sentinels() {
+        for value in &[json!(false), json!(null), json!("disabled")] {
+            assert!(deserialize(value.clone()).unwrap().is_none());
+        }
+        assert_eq!(deserialize(Value::from("a+")).unwrap().unwrap().as_str(),
+            "a+");
+        assert_eq!(deserialize(json!(true)).unwrap_err().to_string(),
+            "invalid value: boolean `true`, expected valid regular \
+             expression, false, null or \"disabled\"");
+        assert!(deserialize(json!(1)).is_err());
+        assert!(deserialize(Value::from("(")).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rule = Rule { re: None };
+        assert_eq!(serde_json::to_value(&rule).unwrap(), json!({"re": false}));
+        let rule: Rule = serde_yaml::from_str("re: ~").unwrap();
+        assert!(rule.re.is_none());
+        let rule: Rule = serde_yaml::from_str("re: '^a'").unwrap();
+        assert_eq!(serde_yaml::to_string(&rule).unwrap(), "re: ^a\n");
+    }
+
+    #[test]
+    fn test_sentinel_pattern() {
+        let rule = Rule { re: Some(Regex::new("disabled").unwrap()) };
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value, json!({"re": "(?:disabled)"}));
+        let rule: Rule = serde_json::from_value(value).unwrap();
+        assert!(rule.re.unwrap().is_match("disabled"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Deserialization mapping an empty string to an always-matching regex
+//!
+//! An empty string is compiled to [`ALWAYS`], i.e. `(?s:.*)`, which
+//! matches any input, including an empty one, and whose leftmost match
+//! spans the whole input. Any other string is compiled as usual. An empty
+//! pattern matches everything too, but only with an empty match at the
+//! start, so the difference shows when using `find` or `captures`.
+//!
+//! On serialization a regex with exactly the [`ALWAYS`] pattern is written
+//! back as an empty string, so values round-trip unchanged. A regex with
+//! an actual empty pattern is written as the equivalent `(?:)` instead, so
+//! it isn't read back as [`ALWAYS`].
+//!
+//! See also [`empty_as_never`](crate::empty_as_never).
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::empty_as_always")]
+//!     include: Regex,
+//! }
+//!
+//! let filter: Filter = serde_json::from_str(r#"{"include": ""}"#).unwrap();
+//! assert_eq!(filter.include.find("a\nb").unwrap().as_str(), "a\nb");
+//! assert_eq!(serde_json::to_string(&filter).unwrap(), r#"{"include":""}"#);
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Pattern an empty string is compiled to
+pub const ALWAYS: &str = "(?s:.*)";
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let pattern = if s.is_empty() { ALWAYS } else { &s };
+    Regex::new(pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.as_str() == ALWAYS {
+        serializer.serialize_str("")
+    } else if value.as_str().is_empty() {
+        serializer.serialize_str("(?:)")
+    } else {
+        crate::serialize(value, serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize};
+
+    #[test]
+    fn test_empty() {
+        let always = deserialize(Value::from("")).unwrap();
+        assert!(always.is_match(""));
+        assert_eq!(always.find("x\ny").unwrap().as_str(), "x\ny");
+        assert_eq!(serialize(&always, serde_json::value::Serializer).unwrap(),
+            "");
+        let other = deserialize(Value::from("a")).unwrap();
+        assert!(!other.is_match("b"));
+    }
+
+    #[test]
+    fn test_empty_pattern() {
+        let empty = Regex::new("").unwrap();
+        let value = serialize(&empty, serde_json::value::Serializer).unwrap();
+        assert_eq!(value, "(?:)");
+        let back = deserialize(value).unwrap();
+        assert_eq!(back.find("x\ny").unwrap().range(), 0..0);
+    }
+}
@@ -0,0 +1,93 @@
+//! Deserialization mapping an empty string to a never-matching regex
+//!
+//! By default an empty string is a valid pattern which matches everywhere,
+//! which is rarely what an empty config value like `exclude = ""` means.
+//! With this module an empty string compiles to [`NEVER`], an empty
+//! character class which doesn't match any input, including an empty one.
+//! (Note that the commonly suggested `$^` does match an empty string.) Any
+//! other string is compiled as usual.
+//!
+//! On serialization a regex with exactly the [`NEVER`] pattern is written
+//! back as an empty string, so values round-trip unchanged. A regex with
+//! an actual empty pattern is written as the equivalent `(?:)` instead, so
+//! it isn't read back as [`NEVER`].
+//!
+//! See also [`empty_as_always`](crate::empty_as_always).
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::empty_as_never")]
+//!     exclude: Regex,
+//! }
+//!
+//! let filter: Filter = serde_json::from_str(r#"{"exclude": ""}"#).unwrap();
+//! assert!(!filter.exclude.is_match(""));
+//! assert!(!filter.exclude.is_match("anything"));
+//! assert_eq!(serde_json::to_string(&filter).unwrap(), r#"{"exclude":""}"#);
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Pattern an empty string is compiled to
+pub const NEVER: &str = r"[^\x00-\x{10FFFF}]";
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let pattern = if s.is_empty() { NEVER } else { &s };
+    Regex::new(pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.as_str() == NEVER {
+        serializer.serialize_str("")
+    } else if value.as_str().is_empty() {
+        serializer.serialize_str("(?:)")
+    } else {
+        crate::serialize(value, serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize};
+
+    #[test]
+    fn test_empty() {
+        let never = deserialize(Value::from("")).unwrap();
+        assert!(!never.is_match(""));
+        assert!(!never.is_match("\n\u{10FFFF}"));
+        assert_eq!(serialize(&never, serde_json::value::Serializer).unwrap(),
+            "");
+        let other = deserialize(Value::from("a")).unwrap();
+        assert!(other.is_match("a"));
+    }
+
+    #[test]
+    fn test_empty_pattern() {
+        let empty = Regex::new("").unwrap();
+        let value = serialize(&empty, serde_json::value::Serializer).unwrap();
+        assert_eq!(value, "(?:)");
+        let back = deserialize(value).unwrap();
+        assert!(back.is_match(""));
+        assert!(back.is_match("anything"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Deserialization of bytes patterns with a declared encoding
+//!
+//! Input is a map with the pattern and an optional encoding:
+//!
+//! ```json
+//! { "encoding": "latin1", "pattern": "café|naïve" }
+//! ```
+//!
+//! The encoding describes which bytes the non-ASCII characters of the
+//! pattern stand for. Supported encodings are:
+//!
+//! * `utf8` (the default, also spelled `utf-8`): the pattern is compiled
+//!   as is, so `é` matches its two-byte UTF-8 encoding, exactly like
+//!   deserializing `bytes::Regex` with `serde_regex`;
+//! * `latin1` (also `iso-8859-1`): each character from U+0080 to U+00FF is
+//!   replaced with the single byte of the same value, written as a `\xNN`
+//!   escape, and Unicode mode is turned off with a leading `(?-u)`, so that
+//!   `\w`, `\d`, `.` and escapes like `\xE9` match single bytes. Characters
+//!   above U+00FF can't be encoded and are an error.
+//!
+//! Any other encoding is an error. Only this module's deserializer applies
+//! the encoding: the resulting `bytes::Regex` stores the converted pattern,
+//! which is ASCII for `latin1`, so serialization writes it without
+//! `encoding` and it is read back using the default.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::bytes::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::encoded")]
+//!     word: Regex,
+//! }
+//!
+//! let filter: Filter = serde_json::from_str(r#"{"word": {
+//!     "encoding": "latin1",
+//!     "pattern": "café"
+//! }}"#).unwrap();
+//! assert!(filter.word.is_match(b"un caf\xe9"));
+//! assert!(!filter.word.is_match("un café".as_bytes()));
+//! assert_eq!(serde_json::to_string(&filter).unwrap(),
+//!     r#"{"word":{"pattern":"(?-u)caf\\xE9"}}"#);
+//! ```
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use regex::bytes::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+const NO_UNICODE: &str = "(?-u)";
+
+#[derive(Clone, Copy, Default)]
+enum Encoding {
+    #[default]
+    Utf8,
+    Latin1,
+}
+
+const ENCODINGS: &[&str] = &["utf8", "latin1"];
+
+impl<'de> Deserialize<'de> for Encoding {
+    fn deserialize<D>(d: D) -> Result<Encoding, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match &<Cow<str>>::deserialize(d)?[..] {
+            "utf8" | "utf-8" => Ok(Encoding::Utf8),
+            "latin1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            other => Err(D::Error::unknown_variant(other, ENCODINGS)),
+        }
+    }
+}
+
+struct Encoded {
+    encoding: Encoding,
+    pattern: String,
+}
+
+deserialize_struct!(Encoded {
+    encoding: Encoding = Encoding::default(),
+    pattern: String,
+});
+
+struct PatternRef<'a> {
+    pattern: &'a str,
+}
+
+serialize_struct!(PatternRef<'_> as PatternRef { pattern });
+
+fn latin1(pattern: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(pattern.len() + NO_UNICODE.len());
+    if !pattern.starts_with(NO_UNICODE) {
+        result.push_str(NO_UNICODE);
+    }
+    for c in pattern.chars() {
+        match c as u32 {
+            0..=0x7F => result.push(c),
+            code @ 0x80..=0xFF => write!(result, "\\x{:02X}", code).unwrap(),
+            _ => {
+                return Err(format!(
+                    "character {:?} can't be encoded in latin1", c));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn encode(encoding: Encoding, pattern: &str) -> Result<Cow<'_, str>, String> {
+    match encoding {
+        Encoding::Utf8 => Ok(Cow::Borrowed(pattern)),
+        Encoding::Latin1 => latin1(pattern).map(Cow::Owned),
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let input = Encoded::deserialize(d)?;
+    let pattern = encode(input.encoding, &input.pattern)
+        .map_err(D::Error::custom)?;
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    PatternRef { pattern: value.as_str() }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::bytes::Regex;
+    use serde_json::{json, Value};
+
+    use super::{deserialize, latin1, serialize};
+
+    fn load(value: Value) -> Result<Regex, String> {
+        deserialize(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_latin1() {
+        assert_eq!(latin1("caf\u{e9}").unwrap(), r"(?-u)caf\xE9");
+        assert_eq!(latin1("(?-u)[\u{80}-\u{ff}]").unwrap(),
+            r"(?-u)[\x80-\xFF]");
+        assert_eq!(latin1("\u{100}").unwrap_err(),
+            r#"character 'Ā' can't be encoded in latin1"#);
+    }
+
+    #[test]
+    fn test_encodings() {
+        let re = load(json!({"pattern": "\u{e9}"})).unwrap();
+        assert!(re.is_match("\u{e9}".as_bytes()));
+        assert!(!re.is_match(b"\xe9"));
+        let re = load(json!({"encoding": "utf-8", "pattern": "\u{e9}"}))
+            .unwrap();
+        assert!(re.is_match("\u{e9}".as_bytes()));
+
+        let re = load(json!({"encoding": "latin1", "pattern": "^\u{e9}.$"}))
+            .unwrap();
+        assert!(re.is_match(b"\xe9\xff"));
+        assert!(!re.is_match("\u{e9}".as_bytes()));
+        let re = load(json!({
+            "encoding": "iso-8859-1",
+            "pattern": "[\u{e0}-\u{ff}]",
+        })).unwrap();
+        assert!(re.is_match(b"\xe9"));
+
+        assert!(load(json!({"encoding": "utf16", "pattern": "a"})).unwrap_err()
+            .starts_with("unknown variant `utf16`"));
+        assert!(load(json!({"encoding": "latin1", "pattern": "\u{2603}"}))
+            .is_err());
+        assert!(load(json!({"encoding": "latin1", "pattern": "("})).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let re = load(json!({"encoding": "latin1", "pattern": "\u{e9}+"}))
+            .unwrap();
+        let value = serialize(&re, serde_json::value::Serializer).unwrap();
+        assert_eq!(value, json!({"pattern": r"(?-u)\xE9+"}));
+        assert_eq!(load(value).unwrap().as_str(), re.as_str());
+    }
+}
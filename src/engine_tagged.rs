@@ -0,0 +1,119 @@
+//! (De)serialization of patterns tagged with a regex engine
+//!
+//! Input is a map with an engine identifier and the pattern:
+//!
+//! ```json
+//! { "engine": "rust-regex-1", "pattern": "^\\w+$" }
+//! ```
+//!
+//! Regex dialects differ in subtle ways (PCRE has lookaround and
+//! backreferences, RE2 has different escapes, etc.), so the tag makes sure
+//! a pattern written for another engine fails loudly instead of being
+//! silently interpreted differently. The only accepted identifier is
+//! [`ENGINE`], i.e. `rust-regex-1`, meaning the syntax of the `regex`
+//! crate version 1.x. Any other value, as well as a missing tag, is an
+//! error.
+//!
+//! Serialization emits the same tagged form.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Debug, Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::engine_tagged")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": {
+//!     "engine": "rust-regex-1",
+//!     "pattern": "^a+$"
+//! }}"#).unwrap();
+//! assert!(rule.pattern.is_match("aaa"));
+//!
+//! let err = serde_json::from_str::<Rule>(r#"{"pattern": {
+//!     "engine": "pcre2",
+//!     "pattern": "(?<=a)b"
+//! }}"#).unwrap_err();
+//! assert!(err.to_string().contains("unsupported regex engine"));
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::Serde;
+
+/// Engine identifier accepted and emitted by this module
+pub const ENGINE: &str = "rust-regex-1";
+
+struct Tagged {
+    engine: String,
+    pattern: String,
+}
+
+deserialize_struct!(Tagged {
+    engine: String,
+    pattern: String,
+});
+
+struct TaggedRef<'a> {
+    engine: &'static str,
+    pattern: Serde<&'a Regex>,
+}
+
+serialize_struct!(TaggedRef<'_> as TaggedRef { engine, pattern });
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tagged = Tagged::deserialize(d)?;
+    if tagged.engine != ENGINE {
+        return Err(D::Error::custom(format_args!(
+            "unsupported regex engine {:?}, expected {:?}",
+            tagged.engine, ENGINE)));
+    }
+    Regex::new(&tagged.pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    TaggedRef { engine: ENGINE, pattern: Serde(value) }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::engine_tagged")]
+        re: Regex,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = r#"{"re":{"engine":"rust-regex-1","pattern":"a\\d+"}}"#;
+        let rule: Rule = from_str(json).unwrap();
+        assert!(rule.re.is_match("a12"));
+        assert_eq!(to_string(&rule).unwrap(), json);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = from_str::<Rule>(
+            r#"{"re":{"engine":"re2","pattern":"a"}}"#).unwrap_err();
+        assert_eq!(err.to_string(),
+            r#"unsupported regex engine "re2", expected "rust-regex-1" at line 1 column 37"#);
+        assert!(from_str::<Rule>(r#"{"re":{"pattern":"a"}}"#).is_err());
+        assert!(from_str::<Rule>(r#"{"re":"a"}"#).is_err());
+    }
+}
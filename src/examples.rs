@@ -0,0 +1,142 @@
+//! Deserialization of patterns checked against example inputs
+//!
+//! Input is a map with the pattern and two optional lists of examples:
+//!
+//! ```json
+//! {
+//!     "pattern": "^[a-z]+\\.example\\.com$",
+//!     "must_match": ["www.example.com"],
+//!     "must_not_match": ["example.com", "www.example.com.evil"]
+//! }
+//! ```
+//!
+//! After compiling the pattern, every `must_match` example is checked to
+//! match and every `must_not_match` example is checked not to match. The
+//! first violating example is reported as an error, so that a typo or an
+//! overly broad pattern is caught when the config is loaded, rather than
+//! when it misbehaves in production. Examples are validated only at load
+//! time: they are not kept in the compiled regex, and serialization writes
+//! just `{"pattern": ...}`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Route {
+//!     #[serde(with = "serde_regex::examples")]
+//!     host: Regex,
+//! }
+//!
+//! let route: Route = serde_json::from_str(r#"{"host": {
+//!     "pattern": "^[a-z]+\\.example\\.com$",
+//!     "must_match": ["www.example.com"],
+//!     "must_not_match": ["example.com"]
+//! }}"#).unwrap();
+//! assert!(route.host.is_match("api.example.com"));
+//!
+//! let err = serde_json::from_str::<Route>(r#"{"host": {
+//!     "pattern": "example\\.com",
+//!     "must_not_match": ["www.example.com.evil"]
+//! }}"#).err().unwrap();
+//! assert!(err.to_string().contains("www.example.com.evil"));
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+struct WithExamples {
+    pattern: String,
+    must_match: Vec<String>,
+    must_not_match: Vec<String>,
+}
+
+deserialize_struct!(WithExamples {
+    pattern: String,
+    must_match: Vec<String> = Vec::new(),
+    must_not_match: Vec<String> = Vec::new(),
+});
+
+struct PatternRef<'a> {
+    pattern: &'a str,
+}
+
+serialize_struct!(PatternRef<'_> as PatternRef { pattern });
+
+fn check(regex: &Regex, must_match: &[String], must_not_match: &[String])
+    -> Result<(), String>
+{
+    if let Some(example) = must_match.iter().find(|e| !regex.is_match(e)) {
+        return Err(format!("pattern {:?} does not match example {:?}",
+            regex.as_str(), example));
+    }
+    if let Some(example) = must_not_match.iter().find(|e| regex.is_match(e)) {
+        return Err(format!("pattern {:?} matches excluded example {:?}",
+            regex.as_str(), example));
+    }
+    Ok(())
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let input = WithExamples::deserialize(d)?;
+    let regex = Regex::new(&input.pattern).map_err(D::Error::custom)?;
+    check(&regex, &input.must_match, &input.must_not_match)
+        .map_err(D::Error::custom)?;
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    PatternRef { pattern: value.as_str() }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    use super::{deserialize, serialize};
+
+    fn load(value: Value) -> Result<String, String> {
+        deserialize(value)
+            .map(|re| re.as_str().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(load(json!({"pattern": "a+"})).unwrap(), "a+");
+        assert_eq!(load(json!({
+            "pattern": "^a+$",
+            "must_match": ["a", "aaa"],
+            "must_not_match": ["", "ab"],
+        })).unwrap(), "^a+$");
+        assert_eq!(load(json!({
+            "pattern": "a+",
+            "must_not_match": ["b", "ba", "ca"],
+        })).unwrap_err(), r#"pattern "a+" matches excluded example "ba""#);
+        assert_eq!(load(json!({
+            "pattern": "^a+$",
+            "must_match": ["a", "b"],
+            "must_not_match": ["c"],
+        })).unwrap_err(), r#"pattern "^a+$" does not match example "b""#);
+        assert!(load(json!({"pattern": "("})).is_err());
+        assert!(load(json!({"pattern": "a", "examples": []})).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let re = Regex::new("^a+$").unwrap();
+        let value = serialize(&re, serde_json::value::Serializer).unwrap();
+        assert_eq!(value, json!({"pattern": "^a+$"}));
+        assert_eq!(load(value).unwrap(), "^a+$");
+    }
+}
@@ -0,0 +1,251 @@
+//! Deserialization which rewrites `.` into an explicit character class
+//!
+//! Whether `.` matches a newline depends on the engine and its flags, so
+//! a pattern exported to other engines may silently change meaning. This
+//! module rewrites every `.` outside of a bracketed class into a class
+//! that spells out what it matches:
+//!
+//! | `.` in effect                  | rewritten to |
+//! |--------------------------------|--------------|
+//! | default, [`DotPolicy::ExcludeNewline`] | `[^\n]` |
+//! | default, [`DotPolicy::DotAll`] | `[\s\S]`     |
+//! | `(?s)`                         | `[\s\S]`     |
+//! | `(?-s)`                        | `[^\n]`      |
+//! | `(?R)` without `(?s)`          | `[^\r\n]`    |
+//!
+//! The policy applies only to dots not covered by an `s` flag: explicit
+//! `(?s)` and `(?-s)`, both as a group like `(?s)` and as a scoped group
+//! like `(?s:...)`, are respected. So with the default policy the rewritten
+//! pattern matches exactly the same strings, only its text changes, while
+//! [`DotPolicy::DotAll`] makes bare dots match newlines too. The flags are
+//! left in place.
+//!
+//! The compiled regex contains the rewritten pattern, so serialization
+//! writes the rewritten, portable form.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Export {
+//!     #[serde(with = "serde_regex::explicit_dot")]
+//!     line: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::explicit_dot::serialize",
+//!         deserialize_with = "serde_regex::explicit_dot::deserialize_dot_all",
+//!     )]
+//!     block: Regex,
+//! }
+//!
+//! let export: Export = serde_json::from_str(r#"{
+//!     "line": "a.b[.]",
+//!     "block": "<.*>"
+//! }"#).unwrap();
+//! assert_eq!(export.line.as_str(), r"a[^\n]b[.]");
+//! assert_eq!(export.block.as_str(), r"<[\s\S]*>");
+//! assert!(export.block.is_match("<a\nb>"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    self,
+    Ast,
+    Flag,
+    Flags,
+    FlagsItemKind,
+    GroupKind,
+    Span,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// What a `.` not covered by an `s` flag is rewritten to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DotPolicy {
+    /// Keep the default meaning, rewrite to `[^\n]` (`[^\r\n]` in CRLF mode)
+    #[default]
+    ExcludeNewline,
+    /// Match newlines too, rewrite to `[\s\S]`
+    DotAll,
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    dot_all: Option<bool>,
+    crlf: bool,
+}
+
+impl State {
+    fn apply(&mut self, flags: &Flags) {
+        let mut enable = true;
+        for item in &flags.items {
+            match item.kind {
+                FlagsItemKind::Negation => enable = false,
+                FlagsItemKind::Flag(Flag::DotMatchesNewLine) => {
+                    self.dot_all = Some(enable);
+                }
+                FlagsItemKind::Flag(Flag::CRLF) => self.crlf = enable,
+                _ => {}
+            }
+        }
+    }
+    fn replacement(&self, policy: DotPolicy) -> &'static str {
+        let dot_all = self.dot_all
+            .unwrap_or(policy == DotPolicy::DotAll);
+        match (dot_all, self.crlf) {
+            (true, _) => r"[\s\S]",
+            (false, false) => r"[^\n]",
+            (false, true) => r"[^\r\n]",
+        }
+    }
+}
+
+/// Collects spans of dots with their replacements, in order
+///
+/// Flags set by a group like `(?s)` apply to the rest of the enclosing
+/// group, so `state` is shared between siblings and copied for groups.
+fn collect(ast: &Ast, state: &mut State, policy: DotPolicy,
+    dots: &mut Vec<(Span, &'static str)>)
+{
+    match ast {
+        Ast::Dot(span) => dots.push((**span, state.replacement(policy))),
+        Ast::Flags(set) => state.apply(&set.flags),
+        Ast::Group(group) => {
+            let mut inner = *state;
+            if let GroupKind::NonCapturing(ref flags) = group.kind {
+                inner.apply(flags);
+            }
+            collect(&group.ast, &mut inner, policy, dots);
+        }
+        Ast::Repetition(rep) => collect(&rep.ast, state, policy, dots),
+        Ast::Alternation(alt) => {
+            for ast in alt.asts.iter() {
+                collect(ast, state, policy, dots);
+            }
+        }
+        Ast::Concat(concat) => {
+            for ast in concat.asts.iter() {
+                collect(ast, state, policy, dots);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Rewrites every `.` of the pattern into an explicit class
+///
+/// Returns `None` if the pattern can't be parsed.
+pub fn rewrite(pattern: &str, policy: DotPolicy) -> Option<Cow<'_, str>> {
+    let ast = ast::parse::Parser::new().parse(pattern).ok()?;
+    let mut dots = Vec::new();
+    let mut state = State { dot_all: None, crlf: false };
+    collect(&ast, &mut state, policy, &mut dots);
+    if dots.is_empty() {
+        return Some(Cow::Borrowed(pattern));
+    }
+    let mut result = String::with_capacity(pattern.len() + dots.len() * 5);
+    let mut pos = 0;
+    for (span, replacement) in dots {
+        result.push_str(&pattern[pos..span.start.offset]);
+        result.push_str(replacement);
+        pos = span.end.offset;
+    }
+    result.push_str(&pattern[pos..]);
+    Some(Cow::Owned(result))
+}
+
+fn compile<'de, D>(d: D, policy: DotPolicy) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    match rewrite(&s, policy) {
+        Some(pattern) => Regex::new(&pattern),
+        // report the error of the original pattern
+        None => Regex::new(&s),
+    }.map_err(D::Error::custom)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, DotPolicy::ExcludeNewline)
+}
+
+/// Deserialize function making bare dots match newlines
+pub fn deserialize_dot_all<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, DotPolicy::DotAll)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{DotPolicy, deserialize, rewrite};
+
+    fn exclude(pattern: &str) -> String {
+        rewrite(pattern, DotPolicy::ExcludeNewline).unwrap().into_owned()
+    }
+
+    fn dot_all(pattern: &str) -> String {
+        rewrite(pattern, DotPolicy::DotAll).unwrap().into_owned()
+    }
+
+    #[test]
+    fn test_rewrite() {
+        assert_eq!(exclude("abc"), "abc");
+        assert_eq!(exclude(r"a.\.[.]"), r"a[^\n]\.[.]");
+        assert_eq!(exclude("(.)|.+"), r"([^\n])|[^\n]+");
+        assert_eq!(dot_all("(.)|.+"), r"([\s\S])|[\s\S]+");
+        assert_eq!(exclude("(?R).(?s)."), r"(?R)[^\r\n](?s)[\s\S]");
+    }
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(exclude("(?s:.).(?s).(?-s)."),
+            r"(?s:[\s\S])[^\n](?s)[\s\S](?-s)[^\n]");
+        assert_eq!(dot_all("(?-s:.).(?-s).(?m-s)."),
+            r"(?-s:[^\n])[\s\S](?-s)[^\n](?m-s)[^\n]");
+        // flags in a group don't leak out of it
+        assert_eq!(exclude("((?s).)."), r"((?s)[\s\S])[^\n]");
+        // but they apply to later alternatives of the same group
+        assert_eq!(exclude("a(?s)|."), r"a(?s)|[\s\S]");
+    }
+
+    #[test]
+    fn test_same_matches() {
+        for pattern in &["a.b", "(?s)a.b", "(?R)a.b", "(?s:a.)b.", "(?x) a . b"] {
+            let original = Regex::new(pattern).unwrap();
+            let rewritten = Regex::new(&exclude(pattern)).unwrap();
+            for text in &["axb", "a\nb", "a\rb", "a\nb\n", "a\r\nbc"] {
+                assert_eq!(original.is_match(text), rewritten.is_match(text),
+                    "{:?} on {:?}", pattern, text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert_eq!(deserialize(Value::from("a.")).unwrap().as_str(),
+            r"a[^\n]");
+        assert!(deserialize(Value::from("(.")).unwrap_err().to_string()
+            .contains("unclosed group"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Deserialization rejecting forbidden combinations of inline flags
+//!
+//! Organizational policy may forbid using certain flags together, e.g.
+//! case-insensitive matching `i` with swapped greediness `U`, even though
+//! each of them is fine on its own. A [`FlagPolicy`] is built from such
+//! combinations, each given as a bit mask of the constants from
+//! [`flag_whitelist`](crate::flag_whitelist). A pattern is rejected if it
+//! enables all flags of any forbidden combination, with an error naming
+//! the combination.
+//!
+//! Flags are collected from the whole pattern, with global flags like
+//! `(?i)` and scoped ones like `(?i:...)` treated alike, wherever they are.
+//! So `(?i:a)(?U:b+)` uses both `i` and `U`, although they never apply to
+//! the same part of the pattern. Only enabled flags count: `(?-i)` doesn't
+//! use `i`. Note that settings of `RegexBuilder` aren't seen by the policy,
+//! only flags written in the pattern are.
+//!
+//! There is no default policy, so the module has no `deserialize`
+//! function: use the policy as a `DeserializeSeed` in a `deserialize_with`
+//! function instead:
+//!
+//! ```rust
+//! use std::sync::LazyLock;
+//!
+//! use regex::Regex;
+//! use serde::de::{DeserializeSeed, Deserializer};
+//! use serde_derive::{Serialize, Deserialize};
+//! use serde_regex::flag_policy::FlagPolicy;
+//! use serde_regex::flag_whitelist::{
+//!     CASE_INSENSITIVE, DOT_MATCHES_NEW_LINE, MULTI_LINE, SWAP_GREED,
+//! };
+//!
+//! static POLICY: LazyLock<FlagPolicy> = LazyLock::new(|| FlagPolicy::new()
+//!     .forbid(CASE_INSENSITIVE | SWAP_GREED)
+//!     .forbid(MULTI_LINE | DOT_MATCHES_NEW_LINE));
+//!
+//! fn checked<'de, D: Deserializer<'de>>(d: D) -> Result<Regex, D::Error> {
+//!     (&*POLICY).deserialize(d)
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(
+//!         serialize_with = "serde_regex::flag_policy::serialize",
+//!         deserialize_with = "checked",
+//!     )]
+//!     pattern: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Rule>(r#"{"pattern": "(?is)a.b"}"#)
+//!     .is_ok());
+//! let err = serde_json::from_str::<Rule>(r#"{"pattern": "(?i)a(?U:b+)"}"#)
+//!     .err().unwrap();
+//! assert!(err.to_string().starts_with("flags i, U can't be used together"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{Ast, Flags, FlagsItemKind, GroupKind, parse::Parser};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+    de::{DeserializeSeed, Error},
+};
+
+use crate::flag_whitelist::{ALL_FLAGS, bit_and_name};
+use crate::syntax::visit;
+
+/// Builder of forbidden flag combinations
+///
+/// See module docs for how flags of a pattern are evaluated.
+#[derive(Debug, Clone, Default)]
+pub struct FlagPolicy {
+    forbidden: Vec<u32>,
+}
+
+fn enabled(flags: &Flags) -> u32 {
+    let mut bits = 0;
+    for item in &flags.items {
+        match item.kind {
+            FlagsItemKind::Negation => break,
+            FlagsItemKind::Flag(flag) => bits |= bit_and_name(flag).0,
+        }
+    }
+    bits
+}
+
+fn names(mask: u32) -> String {
+    ALL_FLAGS.iter()
+        .map(|&flag| bit_and_name(flag))
+        .filter(|(bit, _)| mask & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl FlagPolicy {
+    /// Creates a policy which allows any flags
+    pub fn new() -> FlagPolicy {
+        FlagPolicy::default()
+    }
+
+    /// Forbids using all flags of the `combination` mask in one pattern
+    pub fn forbid(mut self, combination: u32) -> FlagPolicy {
+        self.forbidden.push(combination);
+        self
+    }
+
+    /// Checks that the pattern doesn't use any forbidden combination
+    pub fn check(&self, pattern: &str) -> Result<(), String> {
+        let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+        let mut used = 0;
+        visit(&ast, &mut |node| match node {
+            Ast::Flags(set) => used |= enabled(&set.flags),
+            Ast::Group(group) => {
+                if let GroupKind::NonCapturing(ref flags) = group.kind {
+                    used |= enabled(flags);
+                }
+            }
+            _ => {}
+        });
+        match self.forbidden.iter().find(|&&mask| used & mask == mask) {
+            Some(&mask) => Err(format!(
+                "flags {} can't be used together in {:?}", names(mask), pattern)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &FlagPolicy {
+    type Value = Regex;
+
+    fn deserialize<D>(self, d: D) -> Result<Regex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <Cow<str>>::deserialize(d)?;
+        self.check(&s).map_err(D::Error::custom)?;
+        Regex::new(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde::de::DeserializeSeed;
+    use serde_json::Value;
+
+    use crate::flag_whitelist::{
+        CASE_INSENSITIVE, CRLF, IGNORE_WHITESPACE, MULTI_LINE, SWAP_GREED,
+    };
+    use super::FlagPolicy;
+
+    #[test]
+    fn test_check() {
+        let policy = FlagPolicy::new()
+            .forbid(CASE_INSENSITIVE | SWAP_GREED)
+            .forbid(MULTI_LINE | CRLF | IGNORE_WHITESPACE);
+        for pattern in &["(?i)a", "(?U)a+", "(?i-U)a", "(?mR)^a$", "(?-i)(?U)a"]
+        {
+            assert_eq!(policy.check(pattern), Ok(()), "{:?}", pattern);
+        }
+        assert_eq!(policy.check("(?Ui)a").unwrap_err(),
+            r#"flags i, U can't be used together in "(?Ui)a""#);
+        assert!(policy.check("(?i:a)(?U:b+)").is_err());
+        assert!(policy.check("(?x)(?:(?mR:^a))").unwrap_err()
+            .starts_with("flags m, R, x can't"));
+        assert!(policy.check("(").is_err());
+        assert!(FlagPolicy::new().check("(?imsxURu)a").is_ok());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let policy = FlagPolicy::new().forbid(CASE_INSENSITIVE);
+        let re = (&policy).deserialize(Value::from("(?m)^a")).unwrap();
+        assert_eq!(re.as_str(), "(?m)^a");
+        assert!((&policy).deserialize(Value::from("(?i)a")).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Deserialization allowing only specific inline flags
+//!
+//! Untrusted patterns can change matching in surprising ways with inline
+//! flags, e.g. `(?s)` makes `.` match newlines in the whole pattern. This
+//! module parses the pattern with `regex-syntax` and rejects it if it uses
+//! a flag which is not in the whitelist, with an error naming the flag.
+//!
+//! Both forms of inline flags are detected, wherever they are in the
+//! pattern, including nested groups:
+//!
+//! * global ones like `(?s)`, which apply until the end of the enclosing
+//!   group (or the whole pattern at the top level);
+//! * scoped ones like `(?s:...)`, which apply only inside the group.
+//!
+//! A flag is rejected whether it's enabled or disabled, so `(?-u)` needs
+//! [`UNICODE`] in the whitelist too.
+//!
+//! The whitelist is a bit mask of the constants in this module. The
+//! default is [`DEFAULT_ALLOWED`], i.e. `i` and `m`. Use `deserialize_allow`
+//! to override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//! use serde_regex::flag_whitelist::{CASE_INSENSITIVE, IGNORE_WHITESPACE};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::flag_whitelist")]
+//!     pattern: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::flag_whitelist::serialize",
+//!         deserialize_with = "serde_regex::flag_whitelist::deserialize_allow::\
+//!             <_, { CASE_INSENSITIVE | IGNORE_WHITESPACE }>",
+//!     )]
+//!     verbose: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{
+//!     "pattern": "(?i)^a(?m:$)",
+//!     "verbose": "(?x) a b"
+//! }"#).is_ok());
+//! assert!(serde_json::from_str::<Config>(r#"{
+//!     "pattern": "a(?s:.)b",
+//!     "verbose": "a b"
+//! }"#).is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    Ast,
+    Flag,
+    Flags,
+    FlagsItemKind,
+    GroupKind,
+    parse::Parser,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit;
+
+/// The `i` flag
+pub const CASE_INSENSITIVE: u32 = 1 << 0;
+/// The `m` flag
+pub const MULTI_LINE: u32 = 1 << 1;
+/// The `s` flag
+pub const DOT_MATCHES_NEW_LINE: u32 = 1 << 2;
+/// The `U` flag
+pub const SWAP_GREED: u32 = 1 << 3;
+/// The `u` flag
+pub const UNICODE: u32 = 1 << 4;
+/// The `R` flag
+pub const CRLF: u32 = 1 << 5;
+/// The `x` flag
+pub const IGNORE_WHITESPACE: u32 = 1 << 6;
+
+/// Flags allowed by default
+pub const DEFAULT_ALLOWED: u32 = CASE_INSENSITIVE | MULTI_LINE;
+
+/// All flags, in the order of the constants above
+pub(crate) const ALL_FLAGS: &[Flag] = &[
+    Flag::CaseInsensitive,
+    Flag::MultiLine,
+    Flag::DotMatchesNewLine,
+    Flag::SwapGreed,
+    Flag::Unicode,
+    Flag::CRLF,
+    Flag::IgnoreWhitespace,
+];
+
+pub(crate) fn bit_and_name(flag: Flag) -> (u32, char) {
+    match flag {
+        Flag::CaseInsensitive => (CASE_INSENSITIVE, 'i'),
+        Flag::MultiLine => (MULTI_LINE, 'm'),
+        Flag::DotMatchesNewLine => (DOT_MATCHES_NEW_LINE, 's'),
+        Flag::SwapGreed => (SWAP_GREED, 'U'),
+        Flag::Unicode => (UNICODE, 'u'),
+        Flag::CRLF => (CRLF, 'R'),
+        Flag::IgnoreWhitespace => (IGNORE_WHITESPACE, 'x'),
+    }
+}
+
+fn forbidden(flags: &Flags, allowed: u32) -> Option<char> {
+    flags.items.iter().find_map(|item| match item.kind {
+        FlagsItemKind::Flag(flag) => {
+            let (bit, name) = bit_and_name(flag);
+            Some(name).filter(|_| allowed & bit == 0)
+        }
+        FlagsItemKind::Negation => None,
+    })
+}
+
+fn check(pattern: &str, allowed: u32) -> Result<(), String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut found = None;
+    visit(&ast, &mut |node| {
+        let flag = match node {
+            Ast::Flags(set) => forbidden(&set.flags, allowed),
+            Ast::Group(group) => match group.kind {
+                GroupKind::NonCapturing(ref flags) => {
+                    forbidden(flags, allowed)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if found.is_none() {
+            found = flag;
+        }
+    });
+    match found {
+        Some(flag) => Err(format!("flag {:?} is not allowed in {:?}",
+            flag, pattern)),
+        None => Ok(()),
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_allow::<D, DEFAULT_ALLOWED>(d)
+}
+
+/// Deserialize function allowing only flags in the `ALLOWED` mask
+pub fn deserialize_allow<'de, D, const ALLOWED: u32>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    check(&s, ALLOWED).map_err(D::Error::custom)?;
+    Regex::new(&s).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{DEFAULT_ALLOWED, UNICODE, check, deserialize};
+
+    #[test]
+    fn test_check() {
+        assert!(check("(?im)a(?i:b)(?-i)c", DEFAULT_ALLOWED).is_ok());
+        assert!(check("[?s]", DEFAULT_ALLOWED).is_ok());
+        assert_eq!(check("a(b(?s:.))", DEFAULT_ALLOWED).unwrap_err(),
+            r#"flag 's' is not allowed in "a(b(?s:.))""#);
+        assert!(check("(?i-U)a", DEFAULT_ALLOWED).is_err());
+        assert!(check("(?x)a", DEFAULT_ALLOWED).is_err());
+        assert!(check(r"(?-u)\w", DEFAULT_ALLOWED).is_err());
+        assert!(check(r"(?-u)\w", DEFAULT_ALLOWED | UNICODE).is_ok());
+        assert!(check(r"(?m)^x", 0).is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert!(deserialize(Value::from("(?i)a")).is_ok());
+        assert!(deserialize(Value::from("(?s)a")).is_err());
+        assert!(deserialize(Value::from("(")).is_err());
+    }
+}
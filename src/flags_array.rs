@@ -0,0 +1,175 @@
+//! (De)serialization of patterns with flags given as an array of letters
+//!
+//! Input is a map with the pattern and an optional array of flags:
+//!
+//! ```json
+//! { "pattern": "^error:", "flags": ["i", "m"] }
+//! ```
+//!
+//! Each flag is a single letter, mapped to the equivalent `RegexBuilder`
+//! setting:
+//!
+//! | flag | `RegexBuilder` method    |
+//! |------|--------------------------|
+//! | `i`  | `case_insensitive`       |
+//! | `m`  | `multi_line`             |
+//! | `s`  | `dot_matches_new_line`   |
+//! | `x`  | `ignore_whitespace`      |
+//! | `U`  | `swap_greed`             |
+//! | `R`  | `crlf`                   |
+//! | `u`  | `unicode` (on by default, so it has no effect) |
+//!
+//! Any other string in the array is an error. The settings are applied as
+//! a leading inline group like `(?im)`, which is equivalent to calling the
+//! builder methods, but is stored in the compiled pattern, so that it
+//! survives serialization.
+//!
+//! Serialization splits such a leading group back into the `flags` array,
+//! in the order of the table above and without duplicates. A pattern
+//! without a leading flag group is written with an empty array.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::flags_array")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": {
+//!     "pattern": "^error:",
+//!     "flags": ["m", "i"]
+//! }}"#).unwrap();
+//! assert!(rule.pattern.is_match("ok\nERROR: failed"));
+//! assert_eq!(serde_json::to_string(&rule).unwrap(),
+//!     r#"{"pattern":{"pattern":"^error:","flags":["i","m"]}}"#);
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::syntax;
+
+const FLAGS: &[char] = &['i', 'm', 's', 'x', 'U', 'R', 'u'];
+
+struct Flagged {
+    pattern: String,
+    flags: Vec<String>,
+}
+
+deserialize_struct!(Flagged {
+    pattern: String,
+    flags: Vec<String> = Vec::new(),
+});
+
+struct FlaggedRef<'a> {
+    pattern: &'a str,
+    flags: Vec<String>,
+}
+
+serialize_struct!(FlaggedRef<'_> as FlaggedRef { pattern, flags });
+
+fn letter(flag: &str) -> Option<char> {
+    let mut chars = flag.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if FLAGS.contains(&c) => Some(c),
+        _ => None,
+    }
+}
+
+fn canonical(letters: &str) -> Vec<char> {
+    FLAGS.iter().copied()
+        .filter(|&c| c != 'u' && letters.contains(c))
+        .collect()
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let flagged = Flagged::deserialize(d)?;
+    let mut letters = Vec::with_capacity(flagged.flags.len());
+    for flag in &flagged.flags {
+        letters.push(letter(flag).ok_or_else(|| D::Error::custom(format_args!(
+            "unknown flag {:?}, expected one of i, m, s, x, U, R, u", flag)))?);
+    }
+    Regex::new(&syntax::prefix_flags(FLAGS, &letters, &flagged.pattern))
+        .map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let (flags, pattern) = match syntax::split_flags(value.as_str(), FLAGS) {
+        Some((flags, rest)) => (canonical(flags), rest),
+        None => (Vec::new(), value.as_str()),
+    };
+    FlaggedRef {
+        pattern,
+        flags: flags.into_iter().map(String::from).collect(),
+    }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::flags_array")]
+        re: Regex,
+    }
+
+    fn load(json: &str) -> Result<String, String> {
+        from_str::<Rule>(&format!(r#"{{"re": {}}}"#, json))
+            .map(|rule| rule.re.as_str().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(load(r#"{"pattern": "a"}"#).unwrap(), "a");
+        assert_eq!(load(r#"{"pattern": "a", "flags": []}"#).unwrap(), "a");
+        assert_eq!(load(r#"{"pattern": "a", "flags": ["u"]}"#).unwrap(), "a");
+        assert_eq!(load(r#"{"pattern": "a", "flags": ["R", "x", "i", "x"]}"#)
+            .unwrap(), "(?ixR)a");
+        assert!(load(r#"{"pattern": "a", "flags": ["g"]}"#).unwrap_err()
+            .starts_with(r#"unknown flag "g""#));
+        assert!(load(r#"{"pattern": "a", "flags": ["im"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let json = |pattern| to_string(&Rule { re: Regex::new(pattern).unwrap() })
+            .unwrap();
+        assert_eq!(json("(?sU)a.b"),
+            r#"{"re":{"pattern":"a.b","flags":["s","U"]}}"#);
+        assert_eq!(json("(?i-s)a"), r#"{"re":{"pattern":"(?i-s)a","flags":[]}}"#);
+        assert_eq!(json("a"), r#"{"re":{"pattern":"a","flags":[]}}"#);
+        let text = json("(?mi)^a");
+        let back: Rule = from_str(&text).unwrap();
+        assert_eq!(back.re.as_str(), "(?im)^a");
+    }
+
+    #[test]
+    fn test_idempotent() {
+        for pattern in &["a", "", "(?i)a", "(?u)a", "(?ui)a", "(?sm)^a.",
+                         "(?i)(?i)a", "(?i:a)b"]
+        {
+            let first = to_string(&Rule { re: Regex::new(pattern).unwrap() })
+                .unwrap();
+            let back: Rule = from_str(&first).unwrap();
+            assert_eq!(to_string(&back).unwrap(), first, "{:?}", pattern);
+        }
+        assert!(!to_string(&Rule { re: Regex::new("(?u)a").unwrap() })
+            .unwrap().contains("(?"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Deserialization of patterns with case-insensitive segments
+//!
+//! Input is a map with the pattern and a marker string:
+//!
+//! ```json
+//! { "pattern": "Foo~Bar~", "fold_marker": "~" }
+//! ```
+//!
+//! Markers come in pairs: text between an opening and a closing marker is
+//! wrapped into `(?i:...)`, so the example above compiles to `Foo(?i:Bar)`
+//! and matches `FooBAR` but not `FOOBar`. The grammar is:
+//!
+//! * a doubled marker (`~~`) stands for the marker text itself, matched
+//!   literally (it is passed through `regex::escape`), both inside and
+//!   outside of a segment;
+//! * segments can't be nested, and should contain a complete sub-pattern,
+//!   e.g. `~(a|b)~` rather than `(~a|b~)`;
+//! * a marker which is not closed until the end of the pattern is an
+//!   error, as is an empty marker.
+//!
+//! Only the rewritten pattern is kept, so serialization emits it as
+//! `{"pattern": "..."}` without a marker.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::fold_marker")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": {
+//!     "pattern": "^Id:~[a-f]+~$",
+//!     "fold_marker": "~"
+//! }}"#).unwrap();
+//! assert_eq!(rule.pattern.as_str(), "^Id:(?i:[a-f]+)$");
+//! assert!(rule.pattern.is_match("Id:ABcd"));
+//! assert!(!rule.pattern.is_match("ID:abcd"));
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::Serde;
+
+struct Marked {
+    pattern: String,
+    fold_marker: Option<String>,
+}
+
+deserialize_struct!(Marked {
+    pattern: String,
+    fold_marker: Option<String> = None,
+});
+
+struct Rewritten<'a> {
+    pattern: Serde<&'a Regex>,
+}
+
+serialize_struct!(Rewritten<'_> as Rewritten { pattern });
+
+fn rewrite(pattern: &str, marker: &str) -> Result<String, String> {
+    if marker.is_empty() {
+        return Err("fold marker must not be empty".into());
+    }
+    let mut result = String::with_capacity(pattern.len());
+    let mut open = None;
+    let mut rest = pattern;
+    while let Some(start) = rest.find(marker) {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + marker.len()..];
+        if let Some(tail) = after.strip_prefix(marker) {
+            result.push_str(&regex::escape(marker));
+            rest = tail;
+            continue;
+        }
+        if open.take().is_some() {
+            result.push(')');
+        } else {
+            open = Some(pattern.len() - rest.len() + start);
+            result.push_str("(?i:");
+        }
+        rest = after;
+    }
+    if let Some(offset) = open {
+        return Err(format!("unbalanced fold marker {:?} at offset {}",
+            marker, offset));
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let marked = Marked::deserialize(d)?;
+    let pattern = match marked.fold_marker {
+        Some(marker) => rewrite(&marked.pattern, &marker)
+            .map_err(D::Error::custom)?,
+        None => marked.pattern,
+    };
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Rewritten { pattern: Serde(value) }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    use super::rewrite;
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::fold_marker")]
+        re: Regex,
+    }
+
+    #[test]
+    fn test_rewrite() {
+        assert_eq!(rewrite("Foo~Bar~", "~").unwrap(), "Foo(?i:Bar)");
+        assert_eq!(rewrite("~a~b~c~", "~").unwrap(), "(?i:a)b(?i:c)");
+        assert_eq!(rewrite("a~~b", "~").unwrap(), r"a\~b");
+        assert_eq!(rewrite("<<x||||y>>", "||").unwrap(), r"<<x\|\|y>>");
+        assert_eq!(rewrite("##a|b##c", "##").unwrap(), "(?i:a|b)c");
+        assert_eq!(rewrite("plain", "~").unwrap(), "plain");
+        assert_eq!(rewrite("Foo~Bar", "~").unwrap_err(),
+            r#"unbalanced fold marker "~" at offset 3"#);
+        assert!(rewrite("x", "").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rule: Rule = from_str(
+            r#"{"re": {"pattern": "a~b~", "fold_marker": "~"}}"#).unwrap();
+        assert!(rule.re.is_match("aB"));
+        assert!(!rule.re.is_match("Ab"));
+        assert_eq!(to_string(&rule).unwrap(),
+            r#"{"re":{"pattern":"a(?i:b)"}}"#);
+        assert!(from_str::<Rule>(
+            r#"{"re": {"pattern": "a~b", "fold_marker": "~"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_reserialize() {
+        let rule: Rule = from_str(
+            r#"{"re": {"pattern": "x~y~", "fold_marker": "~"}}"#).unwrap();
+        let text = to_string(&rule).unwrap();
+        let back: Rule = from_str(&text).unwrap();
+        assert_eq!(back.re.as_str(), "x(?i:y)");
+        assert_eq!(to_string(&back).unwrap(), text);
+
+        let plain: Rule = from_str(r#"{"re": {"pattern": "a~b"}}"#).unwrap();
+        assert_eq!(plain.re.as_str(), "a~b");
+    }
+}
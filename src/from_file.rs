@@ -0,0 +1,276 @@
+//! Deserialization of patterns stored in separate files
+//!
+//! Requires the `from_file` feature. It's opt-in because deserialization
+//! reads files from the filesystem, which is not something a config field
+//! is normally expected to do.
+//!
+//! Input is either a plain pattern string or a reference to a file holding
+//! the pattern, which is handy for secret or very large patterns:
+//!
+//! ```json
+//! { "from_file": "patterns/blocklist.re" }
+//! ```
+//!
+//! The whole file is read as the pattern, without any parsing, except that
+//! a single trailing newline (`\n` or `\r\n`) is removed, as most editors
+//! add one. A file which can't be read, or isn't valid UTF-8, is an error
+//! mentioning the path. The file is read on every deserialization.
+//!
+//! Relative paths are resolved against the directory set by
+//! [`set_base_dir`], or against the current working directory if none is
+//! set. The base dir is global state shared with the `include` module.
+//!
+//! A plain `Regex` doesn't retain the reference, so the module's serialize
+//! function always returns an error rather than writing the contents of
+//! the file. Use the [`FromFile`] type to keep the reference and serialize
+//! it back without exposing the pattern, or `deserialize_with` if the value
+//! is never serialized.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//! use serde_regex::from_file::FromFile;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::from_file")]
+//!     spam: Regex,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Secret {
+//!     secret: FromFile,
+//! }
+//!
+//! let dir = std::env::temp_dir().join("serde_regex_from_file_doc");
+//! std::fs::create_dir_all(&dir).unwrap();
+//! std::fs::write(dir.join("spam.re"), "(?i)viagra|casino\n").unwrap();
+//! std::fs::write(dir.join("secret.re"), "^token-[0-9a-f]{32}$\n").unwrap();
+//!
+//! serde_regex::from_file::set_base_dir(&dir);
+//! let filter: Filter = serde_json::from_str(
+//!     r#"{"spam": {"from_file": "spam.re"}}"#).unwrap();
+//! assert!(filter.spam.is_match("CASINO"));
+//! assert!(serde_json::to_string(&filter).is_err());
+//!
+//! let secret: Secret = serde_json::from_str(
+//!     r#"{"secret": {"from_file": "secret.re"}}"#).unwrap();
+//! assert_eq!(secret.secret.as_str(), "^token-[0-9a-f]{32}$");
+//! assert_eq!(serde_json::to_string(&secret).unwrap(),
+//!     r#"{"secret":{"from_file":"secret.re"}}"#);
+//! ```
+use std::{
+    fmt, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, MapAccess, Visitor, value::MapAccessDeserializer},
+    ser,
+};
+
+use crate::base_dir::resolve;
+
+pub use crate::base_dir::{clear_base_dir, set_base_dir};
+
+struct Reference {
+    from_file: PathBuf,
+}
+
+deserialize_struct!(Reference {
+    from_file: PathBuf,
+});
+
+struct ReferenceRef<'a> {
+    from_file: &'a Path,
+}
+
+serialize_struct!(ReferenceRef<'_> as ReferenceRef { from_file });
+
+fn load(path: &Path) -> Result<Regex, String> {
+    let full_path = resolve(path);
+    let text = fs::read_to_string(&full_path).map_err(|e| format!(
+        "can't read pattern file {:?}: {}", full_path, e))?;
+    let pattern = text.strip_suffix('\n')
+        .map(|rest| rest.strip_suffix('\r').unwrap_or(rest))
+        .unwrap_or(&text);
+    Regex::new(pattern).map_err(|e| format!(
+        "invalid pattern in {:?}: {}", full_path, e))
+}
+
+/// A regex loaded from a file, which remembers the file path
+///
+/// Deserializes from a `{"from_file": "path"}` reference only, see module
+/// docs for how the file is read. Serializes back to the same reference,
+/// with the path as written in the input, so the pattern itself never
+/// ends up in the serialized output.
+#[derive(Debug, Clone)]
+pub struct FromFile {
+    path: PathBuf,
+    regex: Regex,
+}
+
+impl FromFile {
+    /// Returns the path as written in the reference
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the compiled regex
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Unwraps the compiled regex
+    pub fn into_inner(self) -> Regex {
+        self.regex
+    }
+}
+
+impl<'de> Deserialize<'de> for FromFile {
+    fn deserialize<D>(d: D) -> Result<FromFile, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let reference = Reference::deserialize(d)?;
+        let regex = load(&reference.from_file).map_err(D::Error::custom)?;
+        Ok(FromFile { path: reference.from_file, regex })
+    }
+}
+
+impl Serialize for FromFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ReferenceRef { from_file: &self.path }.serialize(serializer)
+    }
+}
+
+impl Deref for FromFile {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+struct FromFileVisitor;
+
+impl<'de> Visitor<'de> for FromFileVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pattern or file reference")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(v).map_err(E::custom)
+    }
+    fn visit_map<A>(self, map: A) -> Result<Regex, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let reference = Reference::deserialize(MapAccessDeserializer::new(map))?;
+        load(&reference.from_file).map_err(A::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(FromFileVisitor)
+}
+
+/// Serialize function, always fails, see module docs for alternatives
+pub fn serialize<S>(_value: &Regex, _serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    Err(<S::Error as ser::Error>::custom("a plain Regex loaded by serde_regex::from_file \
+        can't be serialized without exposing the pattern, use FromFile"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use regex::Regex;
+    use serde_json::{from_value, json, to_value, Value};
+
+    use super::{FromFile, deserialize, serialize};
+
+    fn fixture(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("serde_regex_from_file_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn load(value: Value) -> Result<Regex, String> {
+        deserialize(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        for (name, content) in &[("lf.re", "a+\n"), ("crlf.re", "a+\r\n"),
+                                 ("none.re", "a+")]
+        {
+            let path = fixture(name, content);
+            assert_eq!(load(json!({"from_file": path})).unwrap().as_str(),
+                "a+");
+        }
+        let path = fixture("two.re", "a+\n\n");
+        assert_eq!(load(json!({"from_file": path})).unwrap().as_str(), "a+\n");
+        assert_eq!(load(json!("b")).unwrap().as_str(), "b");
+    }
+
+    #[test]
+    fn test_errors() {
+        let path = fixture("invalid.re", "(\n");
+        assert!(load(json!({"from_file": path})).unwrap_err()
+            .starts_with("invalid pattern in"));
+        assert!(load(json!({"from_file": path.with_extension("missing")}))
+            .unwrap_err().starts_with("can't read pattern file"));
+        assert!(load(json!({"from_file": path, "key": "a"})).is_err());
+    }
+
+    #[test]
+    fn test_retained() {
+        let path = fixture("retained.re", "^secret$\n");
+        let reference = json!({"from_file": path});
+        let regex: FromFile = from_value(reference.clone()).unwrap();
+        assert!(regex.is_match("secret"));
+        assert_eq!(regex.path(), path);
+        assert_eq!(to_value(&regex).unwrap(), reference);
+        assert!(from_value::<FromFile>(json!("^secret$")).is_err());
+    }
+
+    #[test]
+    fn test_no_contents() {
+        let path = fixture("hidden.re", "hunter2\n");
+        let regex = load(json!({"from_file": path})).unwrap();
+        let err = serialize(&regex, serde_json::value::Serializer)
+            .unwrap_err().to_string();
+        assert!(err.contains("use FromFile"));
+        assert!(!err.contains("hunter2"));
+        let retained: FromFile = from_value(json!({"from_file": path}))
+            .unwrap();
+        assert!(!to_value(&retained).unwrap().to_string()
+            .contains("hunter2"));
+    }
+}
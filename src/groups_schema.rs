@@ -0,0 +1,138 @@
+//! Serialization of patterns with a list of their named groups
+//!
+//! Meant for generating documentation from configs: each pattern is
+//! serialized as a schema object listing its named capture groups, taken
+//! from `Regex::capture_names()` in order, with unnamed groups left out:
+//!
+//! ```json
+//! { "pattern": "(?P<year>\\d{4})-(?P<month>\\d{2})", "groups": ["year", "month"] }
+//! ```
+//!
+//! The format is asymmetric. Serialization always emits the enriched form,
+//! while deserialization accepts both the enriched form and a plain
+//! pattern string. In the enriched form `groups` is optional and ignored,
+//! the names always come from the pattern itself, so a stale or edited
+//! list can't disagree with the compiled regex. Since either form is
+//! accepted this uses `deserialize_any`, so it needs a self-describing
+//! format.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Format {
+//!     #[serde(with = "serde_regex::groups_schema")]
+//!     date: Regex,
+//! }
+//!
+//! let format: Format = serde_json::from_str(
+//!     r#"{"date": "(?P<year>\\d{4})-(?P<month>\\d{2})-(\\d{2})"}"#).unwrap();
+//! let json = serde_json::to_value(&format).unwrap();
+//! assert_eq!(json["date"]["groups"], serde_json::json!(["year", "month"]));
+//! let back: Format = serde_json::from_value(json).unwrap();
+//! assert_eq!(back.date.as_str(), format.date.as_str());
+//! ```
+use std::fmt;
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, IgnoredAny, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+
+struct Schema {
+    pattern: String,
+    #[allow(dead_code)]
+    groups: IgnoredAny,
+}
+
+deserialize_struct!(Schema {
+    pattern: String,
+    groups: IgnoredAny = IgnoredAny,
+});
+
+struct SchemaRef<'a> {
+    pattern: &'a str,
+    groups: Vec<&'a str>,
+}
+
+serialize_struct!(SchemaRef<'_> as SchemaRef { pattern, groups });
+
+struct SchemaVisitor;
+
+impl<'de> Visitor<'de> for SchemaVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pattern or map with a pattern")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(v).map_err(E::custom)
+    }
+    fn visit_map<A>(self, map: A) -> Result<Regex, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let schema = Schema::deserialize(MapAccessDeserializer::new(map))?;
+        Regex::new(&schema.pattern).map_err(A::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(SchemaVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    SchemaRef {
+        pattern: value.as_str(),
+        groups: value.capture_names().flatten().collect(),
+    }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{Value, json};
+
+    use super::{deserialize, serialize};
+
+    #[test]
+    fn test_serialize() {
+        let schema = |pattern| serialize(&Regex::new(pattern).unwrap(),
+            serde_json::value::Serializer).unwrap();
+        assert_eq!(schema("a(b)(?<x>c)(?:d)(?P<y>e)"),
+            json!({"pattern": "a(b)(?<x>c)(?:d)(?P<y>e)", "groups": ["x", "y"]}));
+        assert_eq!(schema("a"), json!({"pattern": "a", "groups": []}));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let load = |value: Value| deserialize(value)
+            .map(|re| re.as_str().to_string());
+        assert_eq!(load(json!("(?P<a>x)")).unwrap(), "(?P<a>x)");
+        assert_eq!(load(json!({"pattern": "x"})).unwrap(), "x");
+        assert_eq!(load(json!({"pattern": "x", "groups": ["stale"]})).unwrap(),
+            "x");
+        assert!(load(json!({"pattern": "x", "extra": 1})).is_err());
+        assert!(load(json!({"groups": []})).is_err());
+        assert!(load(json!({"pattern": "("})).is_err());
+        assert!(load(json!(1)).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Deserialization of patterns included from shared pattern files
+//!
+//! Requires the `include` feature, which enables `serde_json`. It's opt-in
+//! because deserialization reads files from the filesystem, which is not
+//! something a config field is normally expected to do.
+//!
+//! Input is either a plain pattern string or a reference to a pattern in
+//! another file:
+//!
+//! ```json
+//! { "include": "patterns/common.json", "key": "email" }
+//! ```
+//!
+//! The referenced file must be a JSON object mapping keys to pattern
+//! strings, e.g. `{"email": "^[^@]+@[^@]+$"}`. The file is read and parsed
+//! on every reference (there is no caching), then the pattern under `key`
+//! is compiled. A file which can't be read or parsed, a missing key, or a
+//! value which isn't a string are errors mentioning the path and the key.
+//!
+//! Relative paths are resolved against the directory set by
+//! [`set_base_dir`], or against the current working directory if none is
+//! set. Typically the base dir is the directory of the main config file.
+//! It is global state, like the pattern transform, so set it before
+//! deserializing and avoid changing it while other threads deserialize.
+//! The same base dir is used by the `from_file` module, if its feature is
+//! enabled too.
+//!
+//! The compiled regex doesn't retain the reference, so serialization
+//! writes the resolved pattern as a plain string, which is read back
+//! without accessing any files.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Form {
+//!     #[serde(with = "serde_regex::include")]
+//!     email: Regex,
+//! }
+//!
+//! let dir = std::env::temp_dir().join("serde_regex_include_doc");
+//! std::fs::create_dir_all(dir.join("patterns")).unwrap();
+//! std::fs::write(dir.join("patterns/common.json"),
+//!     r#"{"email": "^[^@]+@[^@]+$"}"#).unwrap();
+//!
+//! serde_regex::include::set_base_dir(&dir);
+//! let form: Form = serde_json::from_str(r#"{"email": {
+//!     "include": "patterns/common.json",
+//!     "key": "email"
+//! }}"#).unwrap();
+//! assert!(form.email.is_match("user@example.com"));
+//! assert_eq!(serde_json::to_string(&form).unwrap(),
+//!     r#"{"email":"^[^@]+@[^@]+$"}"#);
+//! ```
+use std::{fmt, fs, path::PathBuf};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+    de::{Error, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+use serde_json::{Map, Value};
+
+use crate::base_dir::resolve;
+
+pub use crate::base_dir::{clear_base_dir, set_base_dir};
+
+struct Include {
+    include: PathBuf,
+    key: String,
+}
+
+deserialize_struct!(Include {
+    include: PathBuf,
+    key: String,
+});
+
+fn load(include: &Include) -> Result<String, String> {
+    let path = resolve(&include.include);
+    let text = fs::read_to_string(&path).map_err(|e| format!(
+        "can't read include {:?}: {}", path, e))?;
+    let mut map = serde_json::from_str::<Map<String, Value>>(&text)
+        .map_err(|e| format!("can't parse include {:?}: {}", path, e))?;
+    match map.remove(&include.key) {
+        Some(Value::String(pattern)) => Ok(pattern),
+        Some(_) => Err(format!("key {:?} in include {:?} is not a string",
+            include.key, path)),
+        None => Err(format!("no key {:?} in include {:?}",
+            include.key, path)),
+    }
+}
+
+struct IncludeVisitor;
+
+impl<'de> Visitor<'de> for IncludeVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pattern or include reference")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(v).map_err(E::custom)
+    }
+    fn visit_map<A>(self, map: A) -> Result<Regex, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let include = Include::deserialize(MapAccessDeserializer::new(map))?;
+        let pattern = load(&include).map_err(A::Error::custom)?;
+        Regex::new(&pattern).map_err(A::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(IncludeVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    use super::{deserialize, serialize};
+
+    fn fixture(content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("serde_regex_include_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("common.json");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn load(value: Value) -> Result<Regex, String> {
+        deserialize(value).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_include() {
+        let path = fixture(r#"{"digits": "^\\d+$", "number": 1}"#);
+        let re = load(json!({"include": path, "key": "digits"})).unwrap();
+        assert_eq!(re.as_str(), r"^\d+$");
+        assert_eq!(serialize(&re, serde_json::value::Serializer).unwrap(),
+            r"^\d+$");
+        assert_eq!(load(json!("a+")).unwrap().as_str(), "a+");
+
+        assert!(load(json!({"include": path, "key": "email"})).unwrap_err()
+            .starts_with(r#"no key "email" in include"#));
+        assert!(load(json!({"include": path, "key": "number"})).unwrap_err()
+            .contains("is not a string"));
+        assert!(load(json!({"include": path.with_extension("missing"),
+                            "key": "digits"}))
+            .unwrap_err().starts_with("can't read include"));
+        assert!(load(json!({"include": path})).is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let dir = std::env::temp_dir().join("serde_regex_include_invalid");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("broken.json");
+        fs::write(&path, r#"{"a": "("}"#).unwrap();
+        assert!(load(json!({"include": path, "key": "a"})).is_err());
+        fs::write(&path, "[").unwrap();
+        assert!(load(json!({"include": path, "key": "a"})).unwrap_err()
+            .starts_with("can't parse include"));
+    }
+}
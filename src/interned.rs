@@ -0,0 +1,146 @@
+//! Deserialization sharing compiled regexes between equal patterns
+//!
+//! Services holding hundreds of thousands of rules often have the same
+//! pattern repeated many times. This module keeps a global pool mapping
+//! pattern strings (stored as `Arc<str>`) to compiled regexes. When a
+//! pattern is already in the pool, the stored `Regex` is cloned, which
+//! only bumps reference counts: the compiled program and the pattern
+//! string are shared rather than duplicated.
+//!
+//! Use `deserialize_pattern` (and `serialize_pattern`) for `Arc<str>`
+//! fields, which validates that the pattern compiles and returns the
+//! interned string itself.
+//!
+//! The pool is never shrunk automatically. Call [`clear_pool`] when rules
+//! are reloaded; values already deserialized stay valid, they just aren't
+//! shared with ones deserialized afterwards.
+//!
+//! # Thread safety
+//!
+//! The pool is protected by a mutex, so deserialization can run on many
+//! threads at once. The lock is not held while compiling, so two threads
+//! may compile the same new pattern concurrently, in which case the result
+//! of the first one to finish is kept and shared.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::interned")]
+//!     pattern: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::interned::serialize_pattern",
+//!         deserialize_with = "serde_regex::interned::deserialize_pattern",
+//!     )]
+//!     source: Arc<str>,
+//! }
+//!
+//! let rules: Vec<Rule> = serde_json::from_str(r#"[
+//!     {"pattern": "^\\d+$", "source": "^\\d+$"},
+//!     {"pattern": "^\\d+$", "source": "^\\d+$"}
+//! ]"#).unwrap();
+//! assert!(Arc::ptr_eq(&rules[0].source, &rules[1].source));
+//! serde_regex::interned::clear_pool();
+//! ```
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+static POOL: OnceLock<Mutex<HashMap<Arc<str>, Regex>>> = OnceLock::new();
+
+fn pool() -> MutexGuard<'static, HashMap<Arc<str>, Regex>> {
+    POOL.get_or_init(Default::default)
+        .lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn intern(pattern: &str) -> Result<(Arc<str>, Regex), regex::Error> {
+    if let Some((key, regex)) = pool().get_key_value(pattern) {
+        return Ok((key.clone(), regex.clone()));
+    }
+    let regex = Regex::new(pattern)?;
+    let mut pool = pool();
+    if let Some((key, regex)) = pool.get_key_value(pattern) {
+        return Ok((key.clone(), regex.clone()));
+    }
+    let key = Arc::<str>::from(pattern);
+    pool.insert(key.clone(), regex.clone());
+    Ok((key, regex))
+}
+
+/// Removes all patterns from the pool
+pub fn clear_pool() {
+    pool().clear();
+}
+
+/// Returns number of distinct patterns in the pool
+pub fn pool_len() -> usize {
+    pool().len()
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    intern(&s).map(|(_, regex)| regex).map_err(D::Error::custom)
+}
+
+/// Deserialize function returning the interned pattern string
+pub fn deserialize_pattern<'de, D>(d: D) -> Result<Arc<str>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    intern(&s).map(|(pattern, _)| pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+/// Serialize function for patterns from `deserialize_pattern`
+pub fn serialize_pattern<S>(value: &Arc<str>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(value)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::{deserialize, deserialize_pattern};
+
+    #[test]
+    fn test_shared() {
+        // unique patterns, as the pool is shared with other tests
+        let a = deserialize_pattern(Value::from("interned-test-a")).unwrap();
+        let b = deserialize_pattern(Value::from("interned-test-a")).unwrap();
+        let c = deserialize_pattern(Value::from("interned-test-c")).unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        let regex = deserialize(Value::from("interned-test-a")).unwrap();
+        assert_eq!(regex.as_str(), &a[..]);
+        assert!(deserialize(Value::from("interned-test(")).is_err());
+    }
+}
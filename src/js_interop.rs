@@ -0,0 +1,137 @@
+//! (De)serialization with `/` escaped for JavaScript regex literals
+//!
+//! Patterns passed to `new RegExp(...)` in JavaScript can contain a plain
+//! `/`, but once such a pattern is embedded in a `/.../` literal (e.g. by
+//! code generators), an unescaped slash ends the literal early. This module
+//! writes every unescaped `/` as `\/`, so the output can be pasted between
+//! slashes as is. On deserialization `\/` is turned back into `/` before
+//! compiling.
+//!
+//! Only the `/` character is affected, other escapes are passed through
+//! untouched, and an escaped backslash before a slash (`\\/`) is handled
+//! correctly. This is a convenience for sharing patterns between
+//! ecosystems, not a conversion between regex dialects: syntax supported
+//! by only one of the engines is not translated.
+//!
+//! A pattern which already contains `\/` (valid in Rust too) is read as
+//! `/`, so it's serialized the same way as if it was written unescaped.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Route {
+//!     #[serde(with = "serde_regex::js_interop")]
+//!     path: Regex,
+//! }
+//!
+//! let route = Route { path: Regex::new(r"^/api/v\d+/").unwrap() };
+//! let json = serde_json::to_string(&route).unwrap();
+//! assert_eq!(json, r#"{"path":"^\\/api\\/v\\d+\\/"}"#);
+//! let back: Route = serde_json::from_str(&json).unwrap();
+//! assert_eq!(back.path.as_str(), r"^/api/v\d+/");
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+fn escape(pattern: &str) -> Cow<'_, str> {
+    if !pattern.contains('/') {
+        return Cow::Borrowed(pattern);
+    }
+    let mut result = String::with_capacity(pattern.len() + 4);
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                result.push(c);
+                result.extend(chars.next());
+            }
+            '/' => result.push_str(r"\/"),
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+fn unescape(pattern: &str) -> Cow<'_, str> {
+    if !pattern.contains(r"\/") {
+        return Cow::Borrowed(pattern);
+    }
+    let mut result = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('/') => result.push('/'),
+                next => {
+                    result.push(c);
+                    result.extend(next);
+                }
+            },
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    Regex::new(&unescape(&s)).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&escape(value.as_str()))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    use super::{escape, unescape};
+
+    #[derive(Serialize, Deserialize)]
+    struct Route {
+        #[serde(with = "crate::js_interop")]
+        re: Regex,
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a/b"), r"a\/b");
+        assert_eq!(escape(r"a\/b"), r"a\/b");
+        assert_eq!(escape(r"a\\/b"), r"a\\\/b");
+        assert_eq!(escape("[/]"), r"[\/]");
+        assert_eq!(escape(r"\d"), r"\d");
+        assert_eq!(unescape(r"a\/b"), "a/b");
+        assert_eq!(unescape(r"a\\\/b"), r"a\\/b");
+        assert_eq!(unescape(r"a\\/b"), r"a\\/b");
+        assert_eq!(unescape(r"\d\."), r"\d\.");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for pattern in &["/", r"^/a\\/[/]$", r"\d+", "é/ü"] {
+            let route = Route { re: Regex::new(pattern).unwrap() };
+            let json = to_string(&route).unwrap();
+            let back: Route = from_str(&json).unwrap();
+            assert_eq!(back.re.as_str(), *pattern);
+        }
+        let back: Route = from_str(r#"{"re": "a\\/b"}"#).unwrap();
+        assert!(back.re.is_match("a/b"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::slice;
+
+use regex::{Match, Regex};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    ser::SerializeSeq,
+};
+
+use crate::Serde;
+
+/// An ordered list of labeled patterns, e.g. token rules of a lexer
+///
+/// (De)serializes as a sequence of `[label, pattern]` pairs:
+///
+/// ```rust
+/// use serde_regex::LabeledMatchers;
+///
+/// let rules: LabeledMatchers = serde_json::from_str(
+///     r#"[["NUM", "\\d+"], ["WORD", "\\w+"]]"#).unwrap();
+/// let (label, m) = rules.match_first("abc").unwrap();
+/// assert_eq!((label, m.as_str()), ("WORD", "abc"));
+/// ```
+///
+/// Every pattern is compiled on deserialization, so an invalid rule is
+/// reported when the config is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct LabeledMatchers {
+    matchers: Vec<(String, Regex)>,
+}
+
+impl LabeledMatchers {
+    /// Tries patterns in order, returns label and match of the first one
+    /// matching anywhere in the `text`
+    pub fn match_first<'t>(&self, text: &'t str) -> Option<(&str, Match<'t>)> {
+        self.matchers.iter()
+            .find_map(|(label, regex)| {
+                regex.find(text).map(|m| (&label[..], m))
+            })
+    }
+
+    /// Iterates over labels and patterns in order
+    pub fn iter(&self) -> slice::Iter<'_, (String, Regex)> {
+        self.matchers.iter()
+    }
+}
+
+impl From<Vec<(String, Regex)>> for LabeledMatchers {
+    fn from(matchers: Vec<(String, Regex)>) -> LabeledMatchers {
+        LabeledMatchers { matchers }
+    }
+}
+
+impl<'de> Deserialize<'de> for LabeledMatchers {
+    fn deserialize<D>(d: D) -> Result<LabeledMatchers, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pairs = <Vec<(String, Serde<Regex>)>>::deserialize(d)?;
+        Ok(LabeledMatchers {
+            matchers: pairs.into_iter()
+                .map(|(label, Serde(regex))| (label, regex))
+                .collect(),
+        })
+    }
+}
+
+impl Serialize for LabeledMatchers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.matchers.len()))?;
+        for (label, regex) in &self.matchers {
+            seq.serialize_element(&(label, Serde(regex)))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    use super::LabeledMatchers;
+
+    #[test]
+    fn test_match_first() {
+        let json = r#"[["NUM","\\d+"],["WORD","\\w+"]]"#;
+        let rules: LabeledMatchers = from_str(json).unwrap();
+        let (label, m) = rules.match_first("42 apples").unwrap();
+        assert_eq!((label, m.as_str()), ("NUM", "42"));
+        assert!(rules.match_first("  ").is_none());
+        assert_eq!(to_string(&rules).unwrap(), json);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_str::<LabeledMatchers>(r#"[["A","a("]]"#).is_err());
+        assert!(from_str::<LabeledMatchers>(r#"[["A"]]"#).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! (De)serialization of a pattern map accepting a legacy key
+//!
+//! The current shape of the value is a map with the `pattern` key:
+//!
+//! ```json
+//! { "pattern": "^error:" }
+//! ```
+//!
+//! Older config files used the `regex` key instead, which is still
+//! accepted:
+//!
+//! ```json
+//! { "regex": "^error:" }
+//! ```
+//!
+//! Both keys at once, neither of them, or any other key is an error. With
+//! the `log` feature enabled, loading the legacy shape emits a `log::warn!`
+//! suggesting to rename the key. Serialization always writes the current
+//! shape, so saving a loaded config migrates it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::legacy_key")]
+//!     matcher: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"matcher": {"regex": "^a+"}}"#)
+//!     .unwrap();
+//! assert_eq!(serde_json::to_string(&rule).unwrap(),
+//!     r#"{"matcher":{"pattern":"^a+"}}"#);
+//! ```
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+struct Shape {
+    pattern: Option<String>,
+    regex: Option<String>,
+}
+
+deserialize_struct!(Shape {
+    pattern: Option<String> = None,
+    regex: Option<String> = None,
+});
+
+struct ShapeRef<'a> {
+    pattern: &'a str,
+}
+
+serialize_struct!(ShapeRef<'_> as ShapeRef { pattern });
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = match Shape::deserialize(d)? {
+        Shape { pattern: Some(pattern), regex: None } => pattern,
+        Shape { pattern: None, regex: Some(pattern) } => {
+            #[cfg(feature = "log")]
+            log::warn!("key \"regex\" is deprecated, \
+                        rename it to \"pattern\" (in {:?})", pattern);
+            pattern
+        }
+        Shape { pattern: Some(_), regex: Some(_) } => {
+            return Err(D::Error::custom(
+                "both \"pattern\" and legacy \"regex\" keys are specified"));
+        }
+        Shape { pattern: None, regex: None } => {
+            return Err(D::Error::missing_field("pattern"));
+        }
+    };
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ShapeRef { pattern: value.as_str() }.serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::legacy_key")]
+        re: Regex,
+    }
+
+    fn load(json: &str) -> Result<String, String> {
+        from_str::<Rule>(&format!(r#"{{"re": {}}}"#, json))
+            .map(|rule| rule.re.as_str().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_shapes() {
+        assert_eq!(load(r#"{"pattern": "a+"}"#).unwrap(), "a+");
+        assert_eq!(load(r#"{"regex": "a+"}"#).unwrap(), "a+");
+        assert!(load(r#"{"pattern": "a", "regex": "b"}"#).unwrap_err()
+            .starts_with("both \"pattern\" and legacy \"regex\" keys"));
+        assert!(load("{}").unwrap_err()
+            .starts_with("missing field `pattern`"));
+        assert!(load(r#"{"re": "a"}"#).is_err());
+        assert!(load(r#"{"regex": "("}"#).is_err());
+        assert!(load(r#""a""#).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let rule: Rule = from_str(r#"{"re": {"regex": "x"}}"#).unwrap();
+        assert_eq!(to_string(&rule).unwrap(), r#"{"re":{"pattern":"x"}}"#);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_warn() {
+        let messages = crate::test_log::capture(|| {
+            load(r#"{"regex": "a"}"#).unwrap();
+            load(r#"{"pattern": "b"}"#).unwrap();
+        });
+        assert_eq!(messages, vec![
+            r#"WARN: key "regex" is deprecated, rename it to "pattern" (in "a")"#,
+        ]);
+    }
+}
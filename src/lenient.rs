@@ -0,0 +1,89 @@
+//! Tolerant deserialization of a single pattern
+//!
+//! The field type is `Result<Regex, String>`: `Ok(regex)` when the pattern
+//! compiles and `Err(message)` with the compilation error otherwise. A bad
+//! pattern never makes deserialization fail by itself, so a single broken
+//! rule doesn't abort parsing of the whole config. The value still has to be
+//! a string though.
+//!
+//! The original pattern of a failed rule is not retained, so serializing an
+//! `Err` value returns a serialization error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::lenient")]
+//!     pattern: Result<Regex, String>,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": "a(b"}"#).unwrap();
+//! assert!(rule.pattern.is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, ser::Error};
+
+use crate::Serde;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Result<Regex, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    Ok(s.parse().map_err(|err: regex::Error| err.to_string()))
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Result<Regex, String>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Ok(regex) => Serde(regex).serialize(serializer),
+        Err(msg) => Err(S::Error::custom(format_args!(
+            "can't serialize invalid pattern, it was not retained: {}",
+            msg))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::lenient")]
+        pattern: Result<Regex, String>,
+    }
+
+    #[test]
+    fn test_valid() {
+        let rule: Rule = from_str(r#"{"pattern":"a+b"}"#).unwrap();
+        assert_eq!(rule.pattern.as_ref().unwrap().as_str(), "a+b");
+        assert_eq!(to_string(&rule).unwrap(), r#"{"pattern":"a+b"}"#);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let rules: Vec<Rule> = from_str(
+            r#"[{"pattern":"a(b"}, {"pattern":"c"}]"#).unwrap();
+        assert!(rules[0].pattern.is_err());
+        assert!(rules[1].pattern.is_ok());
+        assert!(to_string(&rules[0]).is_err());
+    }
+
+    #[test]
+    fn test_not_a_string() {
+        assert!(from_str::<Rule>(r#"{"pattern":1}"#).is_err());
+    }
+}
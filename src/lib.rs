@@ -12,7 +12,6 @@
 //!
 //! use regex::Regex;
 //! use serde::{Deserialize, Serialize};
-//! use serde_derive::{Serialize, Deserialize};
 //!
 //!
 //! #[derive(Serialize, Deserialize)]
@@ -24,17 +23,351 @@
 //! #
 //! # fn main() {}
 //! ```
+//!
+//! # Missing fields
+//!
+//! Serde treats missing `Option` fields as `None` only when no `with`
+//! attribute is set, so add `#[serde(default)]` to optional regex fields
+//! (and to collections which may be omitted). This is required for layered
+//! configuration loaded with the `config` crate, where any layer may leave
+//! the field unset:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Settings {
+//!     #[serde(with = "serde_regex", default)]
+//!     exclude: Option<Regex>,
+//!     #[serde(with = "serde_regex", default)]
+//!     include: Vec<Regex>,
+//! }
+//!
+//! let settings: Settings = serde_json::from_str("{}").unwrap();
+//! assert!(settings.exclude.is_none());
+//! ```
+//!
+//! The same struct can be read from a query string with `serde_qs`, where
+//! lists are written either as repeated keys (`include=a&include=b`) or
+//! with brackets (`include[]=a&include[]=b`), and patterns have to be
+//! percent-encoded:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! # #[derive(Deserialize)]
+//! # struct Settings {
+//! #     #[serde(with = "serde_regex", default)]
+//! #     exclude: Option<Regex>,
+//! #     #[serde(with = "serde_regex", default)]
+//! #     include: Vec<Regex>,
+//! # }
+//! let settings: Settings = serde_qs::from_str("include=%5Cd%2B&include=x")
+//!     .unwrap();
+//! assert_eq!(settings.include[0].as_str(), r"\d+");
+//! ```
+//!
+//! Environment variables read with `envy` work the same way: an unset
+//! variable becomes `None` (or an empty list) thanks to `#[serde(default)]`.
+//! Lists are split on commas, so list items can't contain a comma:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! # #[derive(Deserialize)]
+//! # struct Settings {
+//! #     #[serde(with = "serde_regex", default)]
+//! #     exclude: Option<Regex>,
+//! #     #[serde(with = "serde_regex", default)]
+//! #     include: Vec<Regex>,
+//! # }
+//! let vars = vec![("INCLUDE".to_string(), "^a,b$".to_string())];
+//! let settings: Settings = envy::from_iter(vars).unwrap();
+//! assert!(settings.exclude.is_none());
+//! assert_eq!(settings.include[1].as_str(), "b$");
+//! ```
+//!
+//! # XML
+//!
+//! With `quick-xml` a regex can be stored in an attribute (a field renamed
+//! to `@name`) or as element text, and a `Vec<Regex>` as repeated
+//! elements. Entities like `&amp;` are decoded before the pattern is
+//! compiled. Note that `quick-xml` trims leading and trailing whitespace of
+//! element text, so a pattern like `" a "` comes back as `"a"`. Use an
+//! attribute or a CDATA section for patterns where it matters:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Rule {
+//!     #[serde(rename = "@filter", with = "serde_regex")]
+//!     filter: Regex,
+//!     #[serde(with = "serde_regex")]
+//!     any: Vec<Regex>,
+//! }
+//!
+//! let rule: Rule = quick_xml::de::from_str(r#"<rule filter=" a&amp;b ">
+//!     <any> x </any>
+//!     <any><![CDATA[ y ]]></any>
+//! </rule>"#).unwrap();
+//! assert_eq!(rule.filter.as_str(), " a&b ");
+//! assert_eq!(rule.any[0].as_str(), "x");
+//! assert_eq!(rule.any[1].as_str(), " y ");
+//! ```
+//! # CSV
+//!
+//! Regex columns work with the `csv` crate's serde support, including
+//! quoted cells. An empty cell is `None` for an `Option<Regex>` field, and
+//! an empty pattern (which matches everything) for a plain `Regex` field.
+//! Note that this makes `Some` of an empty pattern come back as `None`
+//! after a round trip:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Row {
+//!     name: String,
+//!     #[serde(with = "serde_regex")]
+//!     pattern: Regex,
+//!     #[serde(with = "serde_regex", default)]
+//!     exclude: Option<Regex>,
+//! }
+//!
+//! let data = "name,pattern,exclude\nlogs,\"^[a-z]+,\\d+$\",\n";
+//! let row: Row = csv::Reader::from_reader(data.as_bytes())
+//!     .deserialize().next().unwrap().unwrap();
+//! assert_eq!(row.pattern.as_str(), r"^[a-z]+,\d+$");
+//! assert!(row.exclude.is_none());
+//! ```
+//!
+//! # KDL
+//!
+//! Regex fields work with `serde-kdl2`, which maps struct fields to KDL
+//! nodes, with a `Vec` written as multiple arguments of one node. KDL raw
+//! strings like `#"\d+"#` need no escaping, which makes them the most
+//! readable way to write patterns. Patterns which are valid identifiers
+//! may even be written without quotes:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex")]
+//!     path: Regex,
+//!     #[serde(with = "serde_regex", default)]
+//!     methods: Vec<Regex>,
+//! }
+//!
+//! let rule: Rule = serde_kdl2::from_str(r##"
+//!     path #"^/users/(\d+)$"#
+//!     methods GET #"(?i)post"#
+//! "##).unwrap();
+//! assert_eq!(rule.path.as_str(), r"^/users/(\d+)$");
+//! assert_eq!(rule.methods[1].as_str(), "(?i)post");
+//! ```
+//!
+//! # Transcoding
+//!
+//! Converting a config between formats with `serde-transcode` doesn't go
+//! through `Serde` at all: the transcoder streams strings from one format
+//! to the other without knowing which of them are patterns. So regex fields
+//! pass through as plain strings, without being compiled or validated, and
+//! nothing is materialized. Patterns are validated later, when the output
+//! is deserialized into the typed struct:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex")]
+//!     path: Regex,
+//! }
+//!
+//! let yaml = "path: ^/api/(v\\d+)/\n";
+//! let mut json = Vec::new();
+//! serde_transcode::transcode(
+//!     serde_yaml::Deserializer::from_str(yaml),
+//!     &mut serde_json::Serializer::new(&mut json),
+//! ).unwrap();
+//! assert_eq!(json, br#"{"path":"^/api/(v\\d+)/"}"#);
+//!
+//! // full deserialize elsewhere still validates the pattern
+//! let rule: Rule = serde_json::from_slice(&json).unwrap();
+//! assert!(rule.path.is_match("/api/v2/users"));
+//! ```
+//!
+//! # Newtypes
+//!
+//! A domain newtype around a regex can use `#[serde(transparent)]` with the
+//! `with` attribute on its only field:
+//!
+//! ```rust
+//! # use regex::Regex;
+//! # use serde_derive::{Serialize, Deserialize};
+//! #[derive(Serialize, Deserialize)]
+//! #[serde(transparent)]
+//! struct RoutePattern(#[serde(with = "serde_regex")] Regex);
+//! ```
+//!
+//! Or the impls can be generated by [`impl_serde_regex_newtype!`], which
+//! works for any type supported by this crate (`Regex`, `bytes::Regex`,
+//! `RegexSet`, `Option<Regex>`, etc.):
+//!
+//! ```rust
+//! use regex::{Regex, bytes};
+//!
+//! struct RoutePattern(Regex);
+//! struct Signature(bytes::Regex);
+//!
+//! serde_regex::impl_serde_regex_newtype!(RoutePattern, Signature);
+//!
+//! let route: RoutePattern = serde_json::from_str(r#""^/users/\\d+$""#)
+//!     .unwrap();
+//! assert!(route.0.is_match("/users/42"));
+//! assert_eq!(serde_json::to_string(&route).unwrap(),
+//!     r#""^/users/\\d+$""#);
+//! let sig: Signature = serde_json::from_str(r#""(?-u)^\\xFF""#).unwrap();
+//! assert!(sig.0.is_match(b"\xFF"));
+//! ```
+//!
+//! # Builder flags
+//!
+//! Patterns are serialized as returned by `Regex::as_str()`. Settings
+//! applied through `RegexBuilder`, like `case_insensitive(true)`, are not
+//! part of that string, and the `regex` crate has no API to read them back
+//! from a compiled `Regex`, so they are lost on a round-trip. Put flags
+//! into the pattern instead, every builder setting has an inline
+//! equivalent:
+//!
+//! | `RegexBuilder` method    | inline flag |
+//! |--------------------------|-------------|
+//! | `case_insensitive`       | `(?i)`      |
+//! | `multi_line`             | `(?m)`      |
+//! | `dot_matches_new_line`   | `(?s)`      |
+//! | `ignore_whitespace`      | `(?x)`      |
+//! | `swap_greed`             | `(?U)`      |
+//! | `crlf`                   | `(?R)`      |
+//! | `unicode(false)`         | `(?-u)`     |
+//!
+//! ```rust
+//! use regex::{Regex, RegexBuilder};
+//! use serde_regex::Serde;
+//!
+//! let built = RegexBuilder::new("aaa").case_insensitive(true).build()
+//!     .unwrap();
+//! let json = serde_json::to_string(&Serde(&built)).unwrap();
+//! let back: Serde<Regex> = serde_json::from_str(&json).unwrap();
+//! assert!(!back.is_match("AAA"));  // the flag is lost
+//!
+//! let inline = Regex::new("(?i)aaa").unwrap();
+//! let json = serde_json::to_string(&Serde(&inline)).unwrap();
+//! let back: Serde<Regex> = serde_json::from_str(&json).unwrap();
+//! assert!(back.is_match("AAA"));
+//! ```
+//!
+//! The [`with_flags`] and [`flags_array`] modules, and the `bson` module
+//! with the `bson` feature, store the same flags separately from the
+//! pattern.
+//!
+//! # Pattern transform
+//!
+//! A function registered with [`set_pattern_transform`] rewrites every
+//! pattern before it's compiled by the `Serde` impls (and so by
+//! `#[serde(with = "serde_regex")]`), including elements of collections and
+//! sets. This is handy for cross-cutting policies, like a mandatory prefix:
+//!
+//! ```rust
+//! use std::borrow::Cow;
+//!
+//! use regex::Regex;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex")]
+//!     path: Regex,
+//! }
+//!
+//! fn anchor(pattern: &str) -> Cow<'_, str> {
+//!     if pattern.starts_with('^') {
+//!         Cow::Borrowed(pattern)
+//!     } else {
+//!         Cow::Owned(format!("^{}", pattern))
+//!     }
+//! }
+//!
+//! serde_regex::set_pattern_transform(anchor);
+//! let rule: Rule = serde_json::from_str(r#"{"path": "/api/"}"#).unwrap();
+//! assert_eq!(rule.path.as_str(), "^/api/");
+//! assert!(!rule.path.is_match("/v1/api/"));
+//! serde_regex::clear_pattern_transform();
+//! ```
+//!
+//! By default there is no transform, i.e. patterns are compiled as is. The
+//! transform is global state shared by the whole process, including other
+//! libraries using `serde_regex`, so it's best set once at startup, before
+//! anything is deserialized. Serialization writes the compiled pattern, so
+//! the transform is applied again when the value is read back: make it
+//! idempotent, like the example above. The modules of this crate that
+//! compile patterns themselves don't apply the transform.
+//!
+//! # Prefix and suffix
+//!
+//! For scoping every pattern in a config, e.g. under a tenant path in a
+//! multi-tenant system, [`set_prefix_suffix`] makes the `Serde` impls wrap
+//! each pattern as `prefix(?:pattern)suffix` before compiling, after the
+//! pattern transform, if any. Use [`set_literal_prefix_suffix`] to match
+//! the prefix and suffix literally, i.e. to have them escaped:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex")]
+//!     path: Regex,
+//! }
+//!
+//! serde_regex::set_literal_prefix_suffix("/tenants/a.b", "");
+//! let rule: Rule = serde_json::from_str(r#"{"path": "/users|/groups"}"#)
+//!     .unwrap();
+//! assert_eq!(rule.path.as_str(), r"/tenants/a\.b(?:/users|/groups)");
+//! assert!(rule.path.is_match("/tenants/a.b/groups"));
+//! assert!(!rule.path.is_match("/tenants/axb/groups"));
+//! assert_eq!(serde_json::to_string(&rule).unwrap(),
+//!     r#"{"path":"/users|/groups"}"#);
+//! serde_regex::clear_prefix_suffix();
+//! ```
+//!
+//! The non-capturing group keeps alternations and inline flags like `(?i)`
+//! of the pattern from extending to the prefix and suffix. Capture groups
+//! in a raw prefix shift the numbers of the pattern's own groups, and named
+//! groups may clash, so prefer non-capturing groups there. Serialization
+//! strips the prefix and suffix again from patterns wrapped this way, so
+//! the output can be read back with the same settings. Wrapped patterns
+//! are remembered until the prefix and suffix are changed or cleared, and
+//! any other regex is written as is, even if it looks like a wrapped one.
+//! Like the transform, this is process-wide state best set once at
+//! startup.
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
 
 use regex::{Regex, RegexSet, bytes};
 use std::{
     borrow::Cow,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet, hash_map},
     fmt,
     hash::{BuildHasher, Hash},
     marker::PhantomData,
-    ops::{Deref, DerefMut}
+    ops::{Deref, DerefMut},
+    rc::Rc,
+    sync::{Arc, RwLock},
 };
 
 use serde::{
@@ -46,6 +379,253 @@ use serde::{
     ser::{SerializeMap, SerializeSeq}
 };
 
+#[macro_use]
+mod structs;
+
+pub mod lenient;
+#[cfg(feature = "serde_bytes")]
+pub mod byte_pattern;
+pub mod timed;
+pub mod strip_comments;
+pub mod canonical_classes;
+pub mod capped_vec;
+pub mod capped_set;
+pub mod aliases;
+pub mod or_combined;
+pub mod split_alternation;
+pub mod max_alternations;
+pub mod nonempty_match;
+pub mod literal;
+pub mod ascii_only;
+pub mod fold_marker;
+pub mod sorted_map;
+pub mod engine_tagged;
+pub mod vec_semantic_dedup;
+pub mod yaml_tagged;
+pub mod size_guarded;
+pub mod interned;
+pub mod flag_whitelist;
+pub mod registry;
+pub mod require_captures;
+pub mod js_interop;
+pub mod empty_as_never;
+pub mod empty_as_always;
+pub mod flags_array;
+pub mod url_path;
+pub mod disabled;
+pub mod max_repeat;
+pub mod smart_literal;
+pub mod proto;
+pub mod map_strict;
+pub mod groups_schema;
+pub mod no_nested_quantifiers;
+pub mod seed;
+pub mod codegen;
+pub mod set_block;
+pub mod examples;
+pub mod encoded;
+pub mod optimize_alternation;
+pub mod map_sorted_by_pattern;
+pub mod diff;
+pub mod tolerant_flags;
+pub mod restricted_grammar;
+pub mod flag_policy;
+pub mod budgeted_vec;
+pub mod explicit_dot;
+pub mod legacy_key;
+pub mod pretty_verbose;
+pub mod with_flags;
+#[cfg(feature = "log")]
+pub mod deprecating;
+#[cfg(feature = "log")]
+pub mod warn_unanchored;
+#[cfg(feature = "bson")]
+pub mod bson;
+#[cfg(feature = "toml_edit")]
+pub mod toml_edit;
+#[cfg(feature = "sha2")]
+pub mod checksum;
+#[cfg(feature = "pool_guarded")]
+pub mod pool_guarded;
+#[cfg(feature = "unicode-normalization")]
+pub mod nfc;
+#[cfg(feature = "include")]
+pub mod include;
+#[cfg(feature = "from_file")]
+pub mod from_file;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(feature = "unicase")]
+pub mod unicase_map;
+
+mod any_regex;
+#[cfg(any(feature = "include", feature = "from_file"))]
+mod base_dir;
+mod case_insensitive;
+#[cfg(feature = "regex-automata")]
+mod dfa;
+mod labeled;
+mod mixed;
+mod mutable_set;
+mod ranked;
+mod retained;
+mod syntax;
+mod transform;
+#[cfg(feature = "serde_json")]
+mod validate;
+#[cfg(all(test, feature = "log"))]
+mod test_log;
+
+pub use crate::any_regex::AnyRegex;
+pub use crate::case_insensitive::CaseInsensitive;
+#[cfg(feature = "regex-automata")]
+pub use crate::dfa::SerializableDfa;
+pub use crate::labeled::LabeledMatchers;
+pub use crate::mixed::{MatcherKind, MixedMatchers};
+pub use crate::mutable_set::MutableRegexSet;
+pub use crate::ranked::RankedRegexSet;
+pub use crate::retained::RetainedRegex;
+pub use crate::transform::TransformChain;
+#[cfg(feature = "serde_json")]
+pub use crate::validate::validate_all;
+
+/// A function rewriting patterns before compilation, see crate docs
+pub type PatternTransform = fn(&str) -> Cow<'_, str>;
+
+static PATTERN_TRANSFORM: RwLock<Option<PatternTransform>> = RwLock::new(None);
+
+/// Sets the global transform applied to patterns before compilation
+///
+/// Replaces the previous transform, if any. See crate docs for details.
+pub fn set_pattern_transform(transform: PatternTransform) {
+    *PATTERN_TRANSFORM.write().unwrap_or_else(|e| e.into_inner())
+        = Some(transform);
+}
+
+/// Removes the global pattern transform, so patterns are compiled as is
+pub fn clear_pattern_transform() {
+    *PATTERN_TRANSFORM.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn transform(pattern: &str) -> Cow<'_, str> {
+    let transform = *PATTERN_TRANSFORM.read().unwrap_or_else(|e| e.into_inner());
+    let pattern = match transform {
+        Some(transform) => transform(pattern),
+        None => Cow::Borrowed(pattern),
+    };
+    match prefix_suffix() {
+        Some(affixes) => Cow::Owned(affixes.wrap(&pattern)),
+        None => pattern,
+    }
+}
+
+/// Global prefix and suffix, with the patterns wrapped into them
+#[derive(Debug)]
+struct Affixes {
+    prefix: String,
+    suffix: String,
+    /// Results of `wrap`, so that serialization strips the prefix and
+    /// suffix only from these, not from any pattern which looks similar
+    wrapped: RwLock<HashSet<String>>,
+}
+
+impl Affixes {
+    fn new(prefix: &str, suffix: &str) -> Affixes {
+        Affixes {
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            wrapped: RwLock::new(HashSet::new()),
+        }
+    }
+
+    fn wrap(&self, pattern: &str) -> String {
+        let result = wrap(pattern, &self.prefix, &self.suffix);
+        self.wrapped.write().unwrap_or_else(|e| e.into_inner())
+            .insert(result.clone());
+        result
+    }
+
+    /// Reverts `wrap`, returns the pattern as is if it wasn't wrapped
+    fn unwrap<'a>(&self, pattern: &'a str) -> &'a str {
+        let wrapped = self.wrapped.read().unwrap_or_else(|e| e.into_inner());
+        if wrapped.contains(pattern) {
+            &pattern[self.prefix.len() + "(?:".len()
+                     ..pattern.len() - self.suffix.len() - ")".len()]
+        } else {
+            pattern
+        }
+    }
+}
+
+static PREFIX_SUFFIX: RwLock<Option<Arc<Affixes>>> = RwLock::new(None);
+
+/// Sets a regex prefix and suffix wrapped around every pattern
+///
+/// Both are used as regex syntax, see [`set_literal_prefix_suffix`] to
+/// match them literally. Replaces the previous prefix and suffix, if any.
+/// See crate docs for details.
+pub fn set_prefix_suffix(prefix: &str, suffix: &str) {
+    *PREFIX_SUFFIX.write().unwrap_or_else(|e| e.into_inner())
+        = Some(Arc::new(Affixes::new(prefix, suffix)));
+}
+
+/// Sets a literal prefix and suffix wrapped around every pattern
+///
+/// Same as [`set_prefix_suffix`] with both arguments escaped by
+/// `regex::escape`.
+pub fn set_literal_prefix_suffix(prefix: &str, suffix: &str) {
+    set_prefix_suffix(&regex::escape(prefix), &regex::escape(suffix));
+}
+
+/// Removes the global prefix and suffix, so patterns are compiled as is
+pub fn clear_prefix_suffix() {
+    *PREFIX_SUFFIX.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+fn wrap(pattern: &str, prefix: &str, suffix: &str) -> String {
+    format!("{}(?:{}){}", prefix, pattern, suffix)
+}
+
+fn prefix_suffix() -> Option<Arc<Affixes>> {
+    PREFIX_SUFFIX.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Returns the pattern to serialize, without the prefix and suffix
+///
+/// Collections get `affixes` from `prefix_suffix` once rather than taking
+/// the lock for every element.
+fn original<'a>(pattern: &'a str, affixes: Option<&Affixes>) -> &'a str {
+    match affixes {
+        Some(affixes) => affixes.unwrap(pattern),
+        None => pattern,
+    }
+}
+
+#[cfg(feature = "stats")]
+use crate::stats::record as record_stats;
+
+#[cfg(not(feature = "stats"))]
+fn record_stats(_pattern: &str) {}
+
+/// Makes an error for patterns which failed to compile
+///
+/// Backreferences are a common leftover from PCRE, and the parse error
+/// doesn't make it obvious that they can't be used at all.
+fn compile_error<E, S>(patterns: &[S], err: regex::Error) -> E
+where
+    E: Error,
+    S: AsRef<str>,
+{
+    for pattern in patterns {
+        if let Some(token) = syntax::backreference(pattern.as_ref()) {
+            return E::custom(format_args!(
+                "backreferences are not supported by the Rust regex engine, \
+                 found `{}` in {:?}", token, pattern.as_ref()));
+        }
+    }
+    E::custom(err)
+}
+
 /// A wrapper type which implements `Serialize` and `Deserialize` for
 /// types involving `Regex`
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
@@ -71,7 +651,7 @@ impl<'a> Visitor<'a> for RegexVecVisitor {
         while let Some(Serde(el)) = seq.next_element()? {
             vec.push(el);
         }
-        return Ok(Serde(vec));
+        Ok(Serde(vec))
     }
 }
 
@@ -92,7 +672,7 @@ impl<'a> Visitor<'a> for BytesRegexVecVisitor {
         while let Some(Serde(el)) = seq.next_element()? {
             vec.push(el);
         }
-        return Ok(Serde(vec));
+        Ok(Serde(vec))
     }
 }
 
@@ -134,7 +714,7 @@ where
         while let Some((key, Serde(value))) = map.next_entry()? {
             hashmap.insert(key, value);
         }
-        return Ok(Serde(hashmap));
+        Ok(Serde(hashmap))
     }
 }
 
@@ -159,7 +739,64 @@ where
         while let Some((key, Serde(value))) = map.next_entry()? {
             hashmap.insert(key, value);
         }
-        return Ok(Serde(hashmap));
+        Ok(Serde(hashmap))
+    }
+}
+
+struct RegexBTreeMapVisitor<K>(PhantomData<K>);
+struct BytesRegexBTreeMapVisitor<K>(PhantomData<K>);
+
+impl<K> Default for RegexBTreeMapVisitor<K> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<K> Default for BytesRegexBTreeMapVisitor<K> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<'a, K> Visitor<'a> for RegexBTreeMapVisitor<K>
+where
+    K: Ord + Deserialize<'a>,
+{
+    type Value = Serde<BTreeMap<K, Regex>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid map")
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'a>
+    {
+        let mut btreemap = BTreeMap::new();
+        while let Some((key, Serde(value))) = map.next_entry()? {
+            btreemap.insert(key, value);
+        }
+        Ok(Serde(btreemap))
+    }
+}
+
+impl<'a, K> Visitor<'a> for BytesRegexBTreeMapVisitor<K>
+where
+    K: Ord + Deserialize<'a>,
+{
+    type Value = Serde<BTreeMap<K, bytes::Regex>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid map")
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'a>
+    {
+        let mut btreemap = BTreeMap::new();
+        while let Some((key, Serde(value))) = map.next_entry()? {
+            btreemap.insert(key, value);
+        }
+        Ok(Serde(btreemap))
     }
 }
 
@@ -183,9 +820,13 @@ impl<'de> Deserialize<'de> for Serde<Regex> {
     {
         let s = <Cow<str>>::deserialize(d)?;
 
+        let s = transform(&s);
         match s.parse() {
-            Ok(regex) => Ok(Serde(regex)),
-            Err(err) => Err(D::Error::custom(err)),
+            Ok(regex) => {
+                record_stats(&s);
+                Ok(Serde(regex))
+            }
+            Err(err) => Err(compile_error(&[&s], err)),
         }
     }
 }
@@ -208,10 +849,15 @@ impl<'de> Deserialize<'de> for Serde<RegexSet> {
         D: Deserializer<'de>,
     {
         let regexes = <Vec<Cow<str>>>::deserialize(d)?;
-
-        match RegexSet::new(regexes) {
-            Ok(regexset) => Ok(Serde(regexset)),
-            Err(err) => Err(D::Error::custom(err)),
+        let regexes = regexes.iter()
+            .map(|s| transform(s))
+            .collect::<Vec<_>>();
+        match RegexSet::new(&regexes) {
+            Ok(regexset) => {
+                regexes.iter().for_each(|s| record_stats(s));
+                Ok(Serde(regexset))
+            }
+            Err(err) => Err(compile_error(&regexes, err)),
         }
     }
 }
@@ -238,6 +884,18 @@ where
     }
 }
 
+impl<'de, K> Deserialize<'de> for Serde<BTreeMap<K, Regex>>
+where
+    K: Ord + Deserialize<'de>,
+{
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_map(RegexBTreeMapVisitor::default())
+    }
+}
+
 impl<'de> Deserialize<'de> for Serde<Option<Vec<bytes::Regex>>> {
     fn deserialize<D>(d: D) -> Result<Serde<Option<Vec<bytes::Regex>>>, D::Error>
     where
@@ -266,6 +924,21 @@ where
     }
 }
 
+impl<'de, K> Deserialize<'de> for Serde<Option<BTreeMap<K, bytes::Regex>>>
+where
+    K: Ord + Deserialize<'de>,
+{
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<BTreeMap<K, bytes::Regex>>>::deserialize(d)? {
+            Some(Serde(map)) => Ok(Serde(Some(map))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Serde<Option<Vec<Regex>>> {
     fn deserialize<D>(d: D) -> Result<Serde<Option<Vec<Regex>>>, D::Error>
     where
@@ -294,6 +967,21 @@ where
     }
 }
 
+impl<'de, K> Deserialize<'de> for Serde<Option<BTreeMap<K, Regex>>>
+where
+    K: Ord + Deserialize<'de>,
+{
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<BTreeMap<K, Regex>>>::deserialize(d)? {
+            Some(Serde(map)) => Ok(Serde(Some(map))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Serde<Option<bytes::Regex>> {
     fn deserialize<D>(d: D) -> Result<Serde<Option<bytes::Regex>>, D::Error>
     where
@@ -313,9 +1001,25 @@ impl<'de> Deserialize<'de> for Serde<bytes::Regex> {
     {
         let s = <Cow<str>>::deserialize(d)?;
 
+        let s = transform(&s);
         match s.parse() {
-            Ok(regex) => Ok(Serde(regex)),
-            Err(err) => Err(D::Error::custom(err)),
+            Ok(regex) => {
+                record_stats(&s);
+                Ok(Serde(regex))
+            }
+            Err(err) => Err(compile_error(&[&s], err)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<bytes::RegexSet>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<bytes::RegexSet>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<bytes::RegexSet>>::deserialize(d)? {
+            Some(Serde(regexset)) => Ok(Serde(Some(regexset))),
+            None => Ok(Serde(None)),
         }
     }
 }
@@ -326,10 +1030,15 @@ impl<'de> Deserialize<'de> for Serde<bytes::RegexSet> {
         D: Deserializer<'de>,
     {
         let regexes = <Vec<Cow<str>>>::deserialize(d)?;
-
-        match bytes::RegexSet::new(regexes) {
-            Ok(regexset) => Ok(Serde(regexset)),
-            Err(err) => Err(D::Error::custom(err)),
+        let regexes = regexes.iter()
+            .map(|s| transform(s))
+            .collect::<Vec<_>>();
+        match bytes::RegexSet::new(&regexes) {
+            Ok(regexset) => {
+                regexes.iter().for_each(|s| record_stats(s));
+                Ok(Serde(regexset))
+            }
+            Err(err) => Err(compile_error(&regexes, err)),
         }
     }
 }
@@ -354,291 +1063,1160 @@ where
         d.deserialize_map(BytesRegexHashMapVisitor::default())
     }
 }
-/// Deserialize function, see crate docs to see how to use it
-pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
-where
-    D: Deserializer<'de>,
-    Serde<T>: Deserialize<'de>,
-{
-    Serde::deserialize(deserializer).map(|x| x.0)
-}
 
-/// Serialize function, see crate docs to see how to use it
-pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+impl<'de, K> Deserialize<'de> for Serde<BTreeMap<K, bytes::Regex>>
 where
-    S: Serializer,
-    for<'a> Serde<&'a T>: Serialize,
+    K: Ord + Deserialize<'de>,
 {
-    Serde(value).serialize(serializer)
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_map(BytesRegexBTreeMapVisitor::default())
+    }
 }
 
-impl<T> Deref for Serde<T> {
-    type Target = T;
-
-    fn deref(&self) -> &T {
-        &self.0
+impl<'de> Deserialize<'de> for Serde<Vec<Arc<Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Vec<Arc<Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(vec) = Serde::<Vec<Regex>>::deserialize(d)?;
+        Ok(Serde(vec.into_iter().map(Arc::new).collect()))
     }
 }
 
-impl<T> DerefMut for Serde<T> {
-    fn deref_mut(&mut self) -> &mut T {
-        &mut self.0
+impl<'de> Deserialize<'de> for Serde<Option<Vec<Arc<Regex>>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Vec<Arc<Regex>>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Vec<Arc<Regex>>>>::deserialize(d)? {
+            Some(Serde(vec)) => Ok(Serde(Some(vec))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Vec<Arc<bytes::Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Vec<Arc<bytes::Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(vec) = Serde::<Vec<bytes::Regex>>::deserialize(d)?;
+        Ok(Serde(vec.into_iter().map(Arc::new).collect()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Vec<Arc<bytes::Regex>>>> {
+    fn deserialize<D>(d: D)
+        -> Result<Serde<Option<Vec<Arc<bytes::Regex>>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Vec<Arc<bytes::Regex>>>>::deserialize(d)? {
+            Some(Serde(vec)) => Ok(Serde(Some(vec))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Arc<Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Arc<Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<Regex>::deserialize(d)?;
+        Ok(Serde(Arc::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Arc<Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Arc<Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Arc<Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Arc<bytes::Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Arc<bytes::Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<bytes::Regex>::deserialize(d)?;
+        Ok(Serde(Arc::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Arc<bytes::Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Arc<bytes::Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Arc<bytes::Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Rc<Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Rc<Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<Regex>::deserialize(d)?;
+        Ok(Serde(Rc::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Rc<Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Rc<Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Rc<Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Rc<bytes::Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Rc<bytes::Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<bytes::Regex>::deserialize(d)?;
+        Ok(Serde(Rc::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Rc<bytes::Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Rc<bytes::Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Rc<bytes::Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Box<Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Box<Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<Regex>::deserialize(d)?;
+        Ok(Serde(Box::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Box<Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Box<Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Box<Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Box<bytes::Regex>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Box<bytes::Regex>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Serde(regex) = Serde::<bytes::Regex>::deserialize(d)?;
+        Ok(Serde(Box::new(regex)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Serde<Option<Box<bytes::Regex>>> {
+    fn deserialize<D>(d: D) -> Result<Serde<Option<Box<bytes::Regex>>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<Serde<Box<bytes::Regex>>>::deserialize(d)? {
+            Some(Serde(regex)) => Ok(Serde(Some(regex))),
+            None => Ok(Serde(None)),
+        }
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` for newtypes like `struct A(Regex)`
+///
+/// Accepts one or more tuple struct names (or paths), whose only field is
+/// any type supported by [`Serde`]. See crate docs for an example.
+#[macro_export]
+macro_rules! impl_serde_regex_newtype {
+    ($($name:path),+ $(,)?) => {$(
+        impl $crate::__serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S)
+                -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::__serde::Serializer,
+            {
+                $crate::serialize(&self.0, serializer)
+            }
+        }
+
+        impl<'de> $crate::__serde::Deserialize<'de> for $name {
+            fn deserialize<D>(d: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: $crate::__serde::Deserializer<'de>,
+            {
+                $crate::deserialize(d).map($name)
+            }
+        }
+    )+};
+}
+
+#[doc(hidden)]
+pub use serde as __serde;
+
+/// Deserialize function, see crate docs to see how to use it
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    Serde<T>: Deserialize<'de>,
+{
+    Serde::deserialize(deserializer).map(|x| x.0)
+}
+
+/// Serialize function, see crate docs to see how to use it
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    for<'a> Serde<&'a T>: Serialize,
+{
+    Serde(value).serialize(serializer)
+}
+
+impl<T> Deref for Serde<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Serde<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> Serde<T> {
+    /// Consumes the `Serde`, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Serde<T> {
+    fn from(val: T) -> Serde<T> {
+        Serde(val)
+    }
+}
+
+impl Serde<Regex> {
+    /// Returns the string matched by the pattern if it's a plain literal
+    ///
+    /// The pattern is parsed with `regex-syntax` with flags applied, so
+    /// escapes are resolved (`a\.b` is the literal `a.b`) while anchors,
+    /// case-insensitive flags and capture groups make the pattern
+    /// non-literal. Callers may use plain substring search for literals.
+    pub fn as_literal(&self) -> Option<String> {
+        syntax::as_literal(self.0.as_str())
+    }
+
+    /// Returns true if the pattern is a plain literal
+    ///
+    /// See [`as_literal`](Serde::as_literal) for details.
+    pub fn is_literal(&self) -> bool {
+        self.as_literal().is_some()
+    }
+
+    /// Returns true if both regexes are the same up to cosmetic differences
+    ///
+    /// Unlike comparing pattern strings, this parses both patterns with
+    /// `regex-syntax` and compares the normalized representation, in which
+    /// flags are applied and some constructs are simplified. So `(?im)a`
+    /// equals `(?mi)a`, and `(?i)a` equals `[aA]`. This is a best-effort
+    /// check, not a decision procedure for equivalence: `false` doesn't mean
+    /// that the regexes match different text, e.g. `a+` and `aa*` are
+    /// considered different. Capture groups (and their names) must match.
+    pub fn semantic_eq(&self, other: &Regex) -> bool {
+        syntax::semantic_eq(self.0.as_str(), other.as_str())
+    }
+
+    /// Splits `text` by matches of the pattern used as a delimiter
+    ///
+    /// Same as `Regex::split`.
+    pub fn split<'r, 't>(&'r self, text: &'t str) -> regex::Split<'r, 't> {
+        self.0.split(text)
+    }
+
+    /// Splits `text` by the delimiter into at most `limit` substrings
+    ///
+    /// Same as `Regex::splitn`.
+    pub fn splitn<'r, 't>(&'r self, text: &'t str, limit: usize)
+        -> regex::SplitN<'r, 't>
+    {
+        self.0.splitn(text, limit)
+    }
+
+    /// Returns an empty set of capture locations for `captures_read`
+    ///
+    /// Same as `Regex::capture_locations`. Reusing locations in a loop
+    /// avoids allocating on every match:
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    /// use serde_regex::Serde;
+    ///
+    /// let re: Serde<Regex> = serde_json::from_str(r#""(\\w+)=(\\d+)""#)
+    ///     .unwrap();
+    /// let mut locs = re.capture_locations();
+    /// let mut total = 0;
+    /// for line in ["a=1", "b=22", "bad"] {
+    ///     if re.captures_read(&mut locs, line).is_some() {
+    ///         let (start, end) = locs.get(2).unwrap();
+    ///         total += line[start..end].parse::<u32>().unwrap();
+    ///     }
+    /// }
+    /// assert_eq!(total, 23);
+    /// ```
+    pub fn capture_locations(&self) -> regex::CaptureLocations {
+        self.0.capture_locations()
+    }
+
+    /// Searches for a match and fills `locs` with capture group positions
+    ///
+    /// Same as `Regex::captures_read`.
+    pub fn captures_read<'t>(&self, locs: &mut regex::CaptureLocations,
+        text: &'t str)
+        -> Option<regex::Match<'t>>
+    {
+        self.0.captures_read(locs, text)
+    }
+
+    /// Returns whether the pattern matches each of the inputs, in order
+    ///
+    /// ```rust
+    /// use regex::Regex;
+    /// use serde_regex::Serde;
+    ///
+    /// let re: Serde<Regex> = serde_json::from_str(r#""^\\d+$""#).unwrap();
+    /// assert_eq!(re.matches_batch(["1", "x", "23"]), [true, false, true]);
+    /// ```
+    ///
+    /// With the `rayon` feature, `par_matches_batch` does the same
+    /// using the rayon thread pool.
+    pub fn matches_batch<'a, I>(&self, inputs: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        inputs.into_iter().map(|input| self.0.is_match(input)).collect()
+    }
+
+    /// Same as [`matches_batch`](Serde::matches_batch) but in parallel
+    ///
+    /// Requires the `rayon` feature. Results are in the order of inputs.
+    /// Matching a single input is fast, so this only pays off for large
+    /// batches or long inputs.
+    #[cfg(feature = "rayon")]
+    pub fn par_matches_batch<'a, I>(&self, inputs: I) -> Vec<bool>
+    where
+        I: rayon::iter::IntoParallelIterator<Item = &'a str>,
+        I::Iter: rayon::iter::IndexedParallelIterator,
+    {
+        use rayon::iter::ParallelIterator;
+
+        inputs.into_par_iter().map(|input| self.0.is_match(input)).collect()
+    }
+}
+
+impl Serde<bytes::Regex> {
+    /// Returns whether the pattern matches each of the inputs, in order
+    ///
+    /// See [`Serde<Regex>::matches_batch`].
+    pub fn matches_batch<'a, I>(&self, inputs: I) -> Vec<bool>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        inputs.into_iter().map(|input| self.0.is_match(input)).collect()
+    }
+
+    /// Same as [`matches_batch`](Serde::matches_batch) but in parallel
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_matches_batch<'a, I>(&self, inputs: I) -> Vec<bool>
+    where
+        I: rayon::iter::IntoParallelIterator<Item = &'a [u8]>,
+        I::Iter: rayon::iter::IndexedParallelIterator,
+    {
+        use rayon::iter::ParallelIterator;
+
+        inputs.into_par_iter().map(|input| self.0.is_match(input)).collect()
+    }
+
+    /// Splits `text` by matches of the pattern used as a delimiter
+    ///
+    /// Same as `bytes::Regex::split`.
+    pub fn split<'r, 't>(&'r self, text: &'t [u8]) -> bytes::Split<'r, 't> {
+        self.0.split(text)
+    }
+
+    /// Splits `text` by the delimiter into at most `limit` substrings
+    ///
+    /// Same as `bytes::Regex::splitn`.
+    pub fn splitn<'r, 't>(&'r self, text: &'t [u8], limit: usize)
+        -> bytes::SplitN<'r, 't>
+    {
+        self.0.splitn(text, limit)
+    }
+}
+
+impl<K, V, S> Serde<HashMap<K, V, S>> {
+    /// Iterates over keys and compiled regexes of the map
+    pub fn iter(&self) -> hash_map::Iter<'_, K, V> {
+        self.0.iter()
+    }
+
+    /// Iterates over keys of the map
+    pub fn keys(&self) -> hash_map::Keys<'_, K, V> {
+        self.0.keys()
+    }
+
+    /// Iterates over compiled regexes of the map
+    pub fn values(&self) -> hash_map::Values<'_, K, V> {
+        self.0.values()
+    }
+}
+
+impl<K, V, S> IntoIterator for Serde<HashMap<K, V, S>> {
+    type Item = (K, V);
+    type IntoIter = hash_map::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a Serde<HashMap<K, V, S>> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = hash_map::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for Serde<&'a HashMap<K, V, S>> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = hash_map::Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Serialize for Serde<&Regex> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        original(self.0.as_str(), prefix_suffix().as_deref())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<Regex> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        original(self.0.as_str(), prefix_suffix().as_deref())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&RegexSet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let affixes = prefix_suffix();
+        serializer.collect_seq(self.0.patterns().iter()
+            .map(|pattern| original(pattern, affixes.as_deref())))
+    }
+}
+
+impl Serialize for Serde<RegexSet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Option<RegexSet>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<Option<RegexSet>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Option<Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<Option<Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<Vec<Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl<K, S> Serialize for Serde<HashMap<K, Regex, S>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl<K> Serialize for Serde<BTreeMap<K, Regex>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Vec<Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self.0 {
+            seq.serialize_element(
+                original(element.as_str(), affixes.as_deref()))?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for Serde<&Option<Vec<Regex>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<K, S> Serialize for Serde<&HashMap<K, Regex, S>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key,
+                original(value.as_str(), affixes.as_deref()))?;
+        }
+        map.end()
+    }
+}
+
+impl<K, S> Serialize for Serde<&Option<HashMap<K, Regex, S>>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<K> Serialize for Serde<&BTreeMap<K, Regex>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key,
+                original(value.as_str(), affixes.as_deref()))?;
+        }
+        map.end()
+    }
+}
+
+impl<K> Serialize for Serde<&Option<BTreeMap<K, Regex>>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<&bytes::Regex> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        original(self.0.as_str(), prefix_suffix().as_deref())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<bytes::Regex> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        original(self.0.as_str(), prefix_suffix().as_deref())
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Option<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<Option<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&bytes::RegexSet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let affixes = prefix_suffix();
+        serializer.collect_seq(self.0.patterns().iter()
+            .map(|pattern| original(pattern, affixes.as_deref())))
+    }
+}
+
+impl Serialize for Serde<bytes::RegexSet> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Option<bytes::RegexSet>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<Option<bytes::RegexSet>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<Vec<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Option<Vec<bytes::Regex>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<K, S> Serialize for Serde<HashMap<K, bytes::Regex, S>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl<K> Serialize for Serde<BTreeMap<K, bytes::Regex>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for Serde<&Vec<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self.0 {
+            seq.serialize_element(
+                original(element.as_str(), affixes.as_deref()))?;
+        }
+        seq.end()
+    }
+}
+
+impl<K, S> Serialize for Serde<&HashMap<K, bytes::Regex, S>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key,
+                original(value.as_str(), affixes.as_deref()))?;
+        }
+        map.end()
+    }
+}
+
+impl<K, S> Serialize for Serde<&Option<HashMap<K, bytes::Regex, S>>>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<K> Serialize for Serde<&BTreeMap<K, bytes::Regex>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key,
+                original(value.as_str(), affixes.as_deref()))?;
+        }
+        map.end()
+    }
+}
+
+impl<K> Serialize for Serde<&Option<BTreeMap<K, bytes::Regex>>>
+where
+    K: Ord + Serialize,
+{
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl Serialize for Serde<&Vec<Arc<Regex>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let affixes = prefix_suffix();
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self.0 {
+            seq.serialize_element(
+                original(element.as_str(), affixes.as_deref()))?;
+        }
+        seq.end()
     }
 }
 
-impl<T> Serde<T> {
-    /// Consumes the `Serde`, returning the inner value.
-    pub fn into_inner(self) -> T {
-        self.0
+impl Serialize for Serde<Vec<Arc<Regex>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Serde(&self.0).serialize(serializer)
     }
 }
 
-impl<T> From<T> for Serde<T> {
-    fn from(val: T) -> Serde<T> {
-        Serde(val)
+impl Serialize for Serde<&Option<Vec<Arc<Regex>>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
     }
 }
 
-impl<'a> Serialize for Serde<&'a Regex> {
+impl Serialize for Serde<&Vec<Arc<bytes::Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.as_str().serialize(serializer)
+        let affixes = prefix_suffix();
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for element in self.0 {
+            seq.serialize_element(
+                original(element.as_str(), affixes.as_deref()))?;
+        }
+        seq.end()
     }
 }
 
-impl Serialize for Serde<Regex> {
+impl Serialize for Serde<Vec<Arc<bytes::Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.as_str().serialize(serializer)
+        Serde(&self.0).serialize(serializer)
     }
 }
 
-impl Serialize for Serde<Option<RegexSet>> {
+impl Serialize for Serde<&Option<Vec<Arc<bytes::Regex>>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         match self.0 {
-            Some(ref value) => value.patterns().serialize(serializer),
+            Some(value) => serializer.serialize_some(&Serde(value)),
             None => serializer.serialize_none(),
         }
     }
 }
 
-impl Serialize for Serde<RegexSet> {
+impl Serialize for Serde<&Arc<Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.patterns().serialize(serializer)
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a Option<Regex>> {
+impl Serialize for Serde<Arc<Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match self.0 {
-            &Some(ref value) => serializer.serialize_some(&Serde(value)),
-            &None => serializer.serialize_none(),
-        }
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl Serialize for Serde<Option<Regex>> {
+impl Serialize for Serde<&Option<Arc<Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
     }
 }
 
-impl Serialize for Serde<Vec<Regex>> {
+impl Serialize for Serde<&Arc<bytes::Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl<K, S> Serialize for Serde<HashMap<K, Regex, S>>
-where
-    K: Hash + Eq + Serialize,
-    S: BuildHasher + Default,
-{
-    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+impl Serialize for Serde<Arc<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        Se: Serializer,
+        S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a Vec<Regex>> {
+impl Serialize for Serde<&Option<Arc<bytes::Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(self.len()))?;
-        for element in self.0 {
-            seq.serialize_element(&Serde(element))?;
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
         }
-        seq.end()
     }
 }
 
-impl<'a> Serialize for Serde<&'a Option<Vec<Regex>>> {
+impl Serialize for Serde<&Rc<Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match self.0 {
-            &Some(ref value) => serializer.serialize_some(&Serde(value)),
-            &None => serializer.serialize_none(),
-        }
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl<'a, K, S> Serialize for Serde<&'a HashMap<K, Regex, S>>
-where
-    K: Hash + Eq + Serialize,
-    S: BuildHasher + Default,
-{
-    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+impl Serialize for Serde<Rc<Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        Se: Serializer,
+        S: Serializer,
     {
-        let mut map = serializer.serialize_map(Some(self.len()))?;
-        for (key, value) in self.0.iter() {
-            map.serialize_entry(key, &Serde(value))?;
-        }
-        map.end()
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a bytes::Regex> {
+impl Serialize for Serde<&Option<Rc<Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.as_str().serialize(serializer)
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
     }
 }
 
-impl Serialize for Serde<bytes::Regex> {
+impl Serialize for Serde<&Rc<bytes::Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.as_str().serialize(serializer)
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a Option<bytes::Regex>> {
+impl Serialize for Serde<Rc<bytes::Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match self.0 {
-            &Some(ref value) => serializer.serialize_some(&Serde(value)),
-            &None => serializer.serialize_none(),
-        }
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl Serialize for Serde<Option<bytes::Regex>> {
+impl Serialize for Serde<&Option<Rc<bytes::Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
+        }
     }
 }
 
-impl Serialize for Serde<bytes::RegexSet> {
+impl Serialize for Serde<&Box<Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.0.patterns().serialize(serializer)
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl Serialize for Serde<Vec<bytes::Regex>> {
+impl Serialize for Serde<Box<Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a Option<Vec<bytes::Regex>>> {
+impl Serialize for Serde<&Option<Box<Regex>>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         match self.0 {
-            &Some(ref value) => serializer.serialize_some(&Serde(value)),
-            &None => serializer.serialize_none(),
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
         }
     }
 }
 
-impl<K, S> Serialize for Serde<HashMap<K, bytes::Regex, S>>
-where
-    K: Hash + Eq + Serialize,
-    S: BuildHasher + Default,
-{
-    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+impl Serialize for Serde<&Box<bytes::Regex>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        Se: Serializer,
+        S: Serializer,
     {
-        Serde(&self.0).serialize(serializer)
+        Serde(&**self.0).serialize(serializer)
     }
 }
 
-impl<'a> Serialize for Serde<&'a Vec<bytes::Regex>> {
+impl Serialize for Serde<Box<bytes::Regex>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(self.len()))?;
-        for element in self.0 {
-            seq.serialize_element(&Serde(element))?;
-        }
-        seq.end()
+        Serde(&*self.0).serialize(serializer)
     }
 }
 
-impl<'a, K, S> Serialize for Serde<&'a HashMap<K, bytes::Regex, S>>
-where
-    K: Hash + Eq + Serialize,
-    S: BuildHasher + Default,
-{
-    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+impl Serialize for Serde<&Option<Box<bytes::Regex>>> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        Se: Serializer,
+        S: Serializer,
     {
-        let mut map = serializer.serialize_map(Some(self.len()))?;
-        for (key, value) in self.0.iter() {
-            map.serialize_entry(key, &Serde(value))?;
+        match self.0 {
+            Some(value) => serializer.serialize_some(&Serde(value)),
+            None => serializer.serialize_none(),
         }
-        map.end()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
-
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, HashMap},
+        rc::Rc,
+        sync::Arc,
+    };
+
+    use serde_derive::{Serialize, Deserialize};
     use serde_json::{json, from_value, from_str, to_string, to_value};
     use regex::{Regex, RegexSet, bytes};
     use crate::Serde;
@@ -706,6 +2284,39 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_btreemap() -> Result<(), Box<dyn std::error::Error>> {
+        let json = r#"{"b":"y?","a":"x+"}"#;
+        let map: Serde<BTreeMap<String, Regex>> = from_str(json)?;
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(to_string(&map)?, r#"{"a":"x+","b":"y?"}"#);
+        assert!(from_str::<Serde<BTreeMap<String, Regex>>>(r#"{"a":"("}"#)
+            .is_err());
+        let map: Serde<BTreeMap<String, bytes::Regex>> = from_str(json)?;
+        assert_eq!(to_string(&map)?, r#"{"a":"x+","b":"y?"}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_btreemap_field() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Serialize, Deserialize)]
+        struct Rules {
+            #[serde(with = "crate")]
+            rules: BTreeMap<String, Regex>,
+            #[serde(with = "crate", default)]
+            optional: Option<BTreeMap<u32, bytes::Regex>>,
+        }
+        let rules: Rules = from_str(r#"{"rules": {"b": "y?", "a": "x+"}}"#)?;
+        assert!(rules.optional.is_none());
+        assert_eq!(to_string(&rules)?,
+            r#"{"rules":{"a":"x+","b":"y?"},"optional":null}"#);
+        let rules: Rules = from_str(r#"{"rules": {},
+            "optional": {"2": "b", "1": "a"}}"#)?;
+        assert_eq!(to_string(&rules)?,
+            r#"{"rules":{},"optional":{"1":"a","2":"b"}}"#);
+        Ok(())
+    }
+
     #[test]
     fn test_simple() {
         let re: Serde<Regex> = from_str(SAMPLE_JSON).unwrap();
@@ -713,6 +2324,331 @@ mod test {
         assert_eq!(to_string(&re).unwrap(), SAMPLE_JSON);
     }
 
+    #[test]
+    fn test_inline_flags_round_trip() {
+        let cases: &[(&str, &[&str], &[&str])] = &[
+            ("(?i)aaa", &["AaA"], &["aab"]),
+            ("(?m)^a$", &["b\na\nc"], &["ba"]),
+            ("(?s)a.b", &["a\nb"], &["ab"]),
+            ("(?x)a b", &["ab"], &["a b"]),
+            ("(?U)^a+", &["aaa"], &["b"]),
+            ("(?mR)^a$", &["b\r\na\r\n"], &["b\r\nab"]),
+            ("(?imsxUR)^ a . $", &["X\r\nA\n"], &["a"]),
+        ];
+        for &(pattern, matching, other) in cases {
+            let regex = Regex::new(pattern).unwrap();
+            let json = to_string(&Serde(&regex)).unwrap();
+            let back: Serde<Regex> = from_str(&json).unwrap();
+            assert_eq!(back.as_str(), pattern);
+            for text in matching.iter().chain(other) {
+                assert_eq!(back.is_match(text), regex.is_match(text),
+                    "{:?} on {:?}", pattern, text);
+                assert_eq!(back.find(text).map(|m| m.range()),
+                    regex.find(text).map(|m| m.range()));
+            }
+            assert!(matching.iter().all(|text| back.is_match(text)));
+            assert!(!other.iter().any(|text| back.is_match(text)));
+        }
+        let bytes = bytes::Regex::new("(?-u)\\xff").unwrap();
+        let back: Serde<bytes::Regex> = from_str(
+            &to_string(&Serde(&bytes)).unwrap()).unwrap();
+        assert!(back.is_match(b"\xff"));
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let eq = |a, b| Serde(Regex::new(a).unwrap())
+            .semantic_eq(&Regex::new(b).unwrap());
+        assert!(eq("(?im)^a$", "(?mi)^a$"));
+        assert!(eq("(?i)a", "[aA]"));
+        assert!(eq("(?:a)b", "ab"));
+        assert!(eq("[a]", "a"));
+        assert!(eq("(?s:.)", "(?s)."));
+        assert!(!eq("(?i)a", "a"));
+        assert!(!eq("(a)", "a"));
+        assert!(!eq("(?P<x>a)", "(a)"));
+        assert!(!eq("a+", "aa*"));
+    }
+
+    #[test]
+    fn test_matches_batch() {
+        let re = Serde(Regex::new("^a+$").unwrap());
+        let inputs = ["a", "b", "aaa", ""];
+        assert_eq!(re.matches_batch(inputs),
+            [true, false, true, false]);
+        assert!(re.matches_batch(Vec::new()).is_empty());
+        #[cfg(feature = "rayon")]
+        assert_eq!(re.par_matches_batch(inputs),
+            re.matches_batch(inputs));
+        let re = Serde(bytes::Regex::new("(?-u)^\\xff").unwrap());
+        assert_eq!(re.matches_batch([&b"\xff1"[..], b"1"]), [true, false]);
+    }
+
+    #[test]
+    fn test_as_literal() {
+        let literal = |pattern| Serde(Regex::new(pattern).unwrap())
+            .as_literal();
+        assert_eq!(literal("abc"), Some("abc".into()));
+        assert_eq!(literal(r"a\.b(?:c)"), Some("a.bc".into()));
+        assert_eq!(literal(""), Some("".into()));
+        assert_eq!(literal("(?i)abc"), None);
+        assert_eq!(literal("^abc"), None);
+        assert_eq!(literal("(abc)"), None);
+        assert_eq!(literal("ab+"), None);
+    }
+
+    #[test]
+    fn test_backreference_error() {
+        let error = |json| from_str::<Serde<Regex>>(json).unwrap_err()
+            .to_string();
+        assert_eq!(error(r#""(a)\\1""#),
+            "backreferences are not supported by the Rust regex engine, \
+             found `\\1` in \"(a)\\\\1\"");
+        for (json, token) in &[
+            (r#""(?<n>a)\\k<n>b""#, r"`\k<n>`"),
+            (r#""(?<n>a)\\k{n}""#, r"`\k{n}`"),
+            (r#""(?<n>a)\\k'n'""#, r"`\k'n'`"),
+            (r#""(a)\\g12""#, r"`\g12`"),
+            (r#""(?P<n>a)(?P=n)""#, r"`(?P=n)`"),
+            (r#""[\\1]""#, r"`\1`"),
+        ] {
+            assert!(error(json).contains(token), "{}", error(json));
+        }
+        assert!(!error(r#""a\\q""#).contains("backreference"));
+        let set = from_str::<Serde<RegexSet>>(r#"["a", "(b)\\1"]"#);
+        assert!(set.unwrap_err().to_string().contains("found `\\1`"));
+        let bytes = from_str::<Serde<bytes::Regex>>(r#""(b)\\1""#);
+        assert!(bytes.unwrap_err().to_string().contains("found `\\1`"));
+    }
+
+    #[test]
+    fn test_pattern_transform() {
+        // transform is global, so it only touches patterns of this test
+        fn unmark(pattern: &str) -> Cow<'_, str> {
+            match pattern.strip_prefix("%transform-test%") {
+                Some(rest) => Cow::Owned(format!("^{}$", rest)),
+                None => Cow::Borrowed(pattern),
+            }
+        }
+        let json = r#""%transform-test%a+""#;
+        let Serde(regex) = from_str::<Serde<Regex>>(json).unwrap();
+        assert_eq!(regex.as_str(), "%transform-test%a+");
+        crate::set_pattern_transform(unmark);
+        let Serde(regex) = from_str::<Serde<Regex>>(json).unwrap();
+        assert_eq!(regex.as_str(), "^a+$");
+        let Serde(set) = from_str::<Serde<bytes::RegexSet>>(
+            r#"["%transform-test%b", "c"]"#).unwrap();
+        assert_eq!(set.patterns(), ["^b$", "c"]);
+        crate::clear_pattern_transform();
+        let Serde(regex) = from_str::<Serde<Regex>>(json).unwrap();
+        assert_eq!(regex.as_str(), "%transform-test%a+");
+    }
+
+    #[test]
+    fn test_prefix_suffix() {
+        use super::{Affixes, wrap};
+
+        let affixes = Affixes::new("^/t/", "$");
+        let wrapped = affixes.wrap("a|b");
+        assert_eq!(wrapped, "^/t/(?:a|b)$");
+        assert_eq!(affixes.unwrap(&wrapped), "a|b");
+        assert_eq!(affixes.unwrap("^/t/a$"), "^/t/a$");
+        assert_eq!(affixes.unwrap("^/t/(?:b)$"), "^/t/(?:b)$");
+        let empty = Affixes::new("", "");
+        assert_eq!(empty.unwrap("(?:a)"), "(?:a)");
+        assert_eq!(empty.unwrap("(?:a)|(?:b)"), "(?:a)|(?:b)");
+        let wrapped = empty.wrap("(?:a)|(?:b)");
+        assert_eq!(empty.unwrap(&wrapped), "(?:a)|(?:b)");
+        let re = Regex::new(&wrap("(?i)x", "(?:y)", "")).unwrap();
+        assert!(re.is_match("yX") && !re.is_match("YX"));
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct EveryType {
+        #[serde(with = "crate")]
+        regex: Regex,
+        #[serde(with = "crate")]
+        option: Option<Regex>,
+        #[serde(with = "crate")]
+        set: RegexSet,
+        #[serde(with = "crate")]
+        option_set: Option<RegexSet>,
+        #[serde(with = "crate")]
+        vec: Vec<Regex>,
+        #[serde(with = "crate")]
+        option_vec: Option<Vec<Regex>>,
+        #[serde(with = "crate")]
+        map: HashMap<String, Regex>,
+        #[serde(with = "crate")]
+        option_map: Option<HashMap<String, Regex>>,
+        #[serde(with = "crate")]
+        vec_arc: Vec<Arc<Regex>>,
+        #[serde(with = "crate")]
+        option_vec_arc: Option<Vec<Arc<Regex>>>,
+        #[serde(with = "crate")]
+        bytes: bytes::Regex,
+        #[serde(with = "crate")]
+        bytes_option: Option<bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_set: bytes::RegexSet,
+        #[serde(with = "crate")]
+        bytes_option_set: Option<bytes::RegexSet>,
+        #[serde(with = "crate")]
+        bytes_vec: Vec<bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_vec: Option<Vec<bytes::Regex>>,
+        #[serde(with = "crate")]
+        bytes_map: HashMap<String, bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_map: Option<HashMap<String, bytes::Regex>>,
+        #[serde(with = "crate")]
+        bytes_vec_arc: Vec<Arc<bytes::Regex>>,
+        #[serde(with = "crate")]
+        bytes_option_vec_arc: Option<Vec<Arc<bytes::Regex>>>,
+        #[serde(with = "crate")]
+        btree_map: BTreeMap<String, Regex>,
+        #[serde(with = "crate")]
+        option_btree_map: Option<BTreeMap<String, Regex>>,
+        #[serde(with = "crate")]
+        bytes_btree_map: BTreeMap<String, bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_btree_map: Option<BTreeMap<String, bytes::Regex>>,
+        #[serde(with = "crate")]
+        arc: Arc<Regex>,
+        #[serde(with = "crate")]
+        option_arc: Option<Arc<Regex>>,
+        #[serde(with = "crate")]
+        bytes_arc: Arc<bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_arc: Option<Arc<bytes::Regex>>,
+        #[serde(with = "crate")]
+        rc: Rc<Regex>,
+        #[serde(with = "crate")]
+        option_rc: Option<Rc<Regex>>,
+        #[serde(with = "crate")]
+        bytes_rc: Rc<bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_rc: Option<Rc<bytes::Regex>>,
+        #[serde(with = "crate")]
+        boxed: Box<Regex>,
+        #[serde(with = "crate")]
+        option_boxed: Option<Box<Regex>>,
+        #[serde(with = "crate")]
+        bytes_boxed: Box<bytes::Regex>,
+        #[serde(with = "crate")]
+        bytes_option_boxed: Option<Box<bytes::Regex>>,
+    }
+
+    #[test]
+    fn test_with_every_type() -> Result<(), Box<dyn std::error::Error>> {
+        // (field, value, is optional)
+        let table = [
+            ("regex", json!("a+"), false),
+            ("option", json!("a+"), true),
+            ("set", json!(["a+", "b"]), false),
+            ("option_set", json!(["a+", "b"]), true),
+            ("vec", json!(["a+", "b"]), false),
+            ("option_vec", json!(["a+", "b"]), true),
+            ("map", json!({"k": "a+"}), false),
+            ("option_map", json!({"k": "a+"}), true),
+            ("vec_arc", json!(["a+", "b"]), false),
+            ("option_vec_arc", json!(["a+", "b"]), true),
+            ("bytes", json!("a+"), false),
+            ("bytes_option", json!("a+"), true),
+            ("bytes_set", json!(["a+", "b"]), false),
+            ("bytes_option_set", json!(["a+", "b"]), true),
+            ("bytes_vec", json!(["a+", "b"]), false),
+            ("bytes_option_vec", json!(["a+", "b"]), true),
+            ("bytes_map", json!({"k": "a+"}), false),
+            ("bytes_option_map", json!({"k": "a+"}), true),
+            ("bytes_vec_arc", json!(["a+", "b"]), false),
+            ("bytes_option_vec_arc", json!(["a+", "b"]), true),
+            ("btree_map", json!({"k": "a+"}), false),
+            ("option_btree_map", json!({"k": "a+"}), true),
+            ("bytes_btree_map", json!({"k": "a+"}), false),
+            ("bytes_option_btree_map", json!({"k": "a+"}), true),
+            ("arc", json!("a+"), false),
+            ("option_arc", json!("a+"), true),
+            ("bytes_arc", json!("a+"), false),
+            ("bytes_option_arc", json!("a+"), true),
+            ("rc", json!("a+"), false),
+            ("option_rc", json!("a+"), true),
+            ("bytes_rc", json!("a+"), false),
+            ("bytes_option_rc", json!("a+"), true),
+            ("boxed", json!("a+"), false),
+            ("option_boxed", json!("a+"), true),
+            ("bytes_boxed", json!("a+"), false),
+            ("bytes_option_boxed", json!("a+"), true),
+        ];
+        let some = table.iter()
+            .map(|(name, value, _)| (name.to_string(), value.clone()))
+            .collect::<serde_json::Map<_, _>>();
+        let none = table.iter()
+            .map(|(name, value, optional)| {
+                let value = if *optional { json!(null) } else { value.clone() };
+                (name.to_string(), value)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        for (name, value, _) in &table {
+            let mut input = some.clone();
+            let invalid = value.to_string().replace("a+", "(");
+            input.insert(name.to_string(), serde_json::from_str(&invalid)?);
+            let input = serde_json::Value::Object(input);
+            assert!(from_value::<EveryType>(input).is_err(), "{}", name);
+        }
+        for input in [some, none] {
+            let input = serde_json::Value::Object(input);
+            let value: EveryType = from_value(input.clone())?;
+            assert_eq!(to_value(&value)?, input);
+        }
+        Ok(())
+    }
+
+    struct Route(Regex);
+    struct Routes(Option<Vec<bytes::Regex>>);
+    crate::impl_serde_regex_newtype!(Route, self::Routes);
+
+    mod result_alias {
+        // the macro must not depend on `Result` in scope of the caller
+        #[allow(dead_code)]
+        type Result<T> = std::result::Result<T, String>;
+
+        pub struct Aliased(pub regex::Regex);
+        crate::impl_serde_regex_newtype!(Aliased);
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct TransparentRoute(#[serde(with = "crate")] Regex);
+
+    #[test]
+    fn test_newtype() -> Result<(), Box<dyn std::error::Error>> {
+        let route: Route = from_str(SAMPLE_JSON)?;
+        assert_eq!(route.0.as_str(), SAMPLE);
+        assert_eq!(to_string(&route)?, SAMPLE_JSON);
+        let routes: Routes = from_value(json!(["a", "b"]))?;
+        assert_eq!(routes.0.as_ref().unwrap()[1].as_str(), "b");
+        assert_eq!(to_value(&routes)?, json!(["a", "b"]));
+        assert!(from_value::<Routes>(json!(null))?.0.is_none());
+        let route: TransparentRoute = from_str(SAMPLE_JSON)?;
+        assert_eq!(to_string(&route)?, SAMPLE_JSON);
+        assert!(from_str::<Route>(r#""(""#).is_err());
+        let aliased: result_alias::Aliased = from_str(SAMPLE_JSON)?;
+        assert_eq!(to_string(&aliased)?, SAMPLE_JSON);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split() {
+        let re: Serde<Regex> = from_str(r#""\\s*,\\s*""#).unwrap();
+        assert_eq!(re.split("a, b ,c").collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(re.splitn("a, b ,c", 2).collect::<Vec<_>>(), ["a", "b ,c"]);
+        let re: Serde<bytes::Regex> = from_str(r#""(?-u)\\xFF""#).unwrap();
+        assert_eq!(re.split(b"a\xFFb").collect::<Vec<_>>(), [b"a", b"b"]);
+        assert_eq!(re.splitn(b"a\xFFb\xFF", 1).collect::<Vec<_>>(),
+            [b"a\xFFb\xFF"]);
+    }
+
     #[test]
     fn test_option_some() {
         let re: Serde<Option<Regex>> = from_str(SAMPLE_JSON).unwrap();
@@ -764,6 +2700,24 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_hashmap_iter() -> Result<(), Box<dyn std::error::Error>> {
+        let json = json!({"a": "a.*b", "b": "c?d"});
+        let map: Serde<HashMap<String, bytes::Regex>> = from_value(json)?;
+        let mut keys = map.keys().cloned().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["a", "b"]);
+        assert!(map.values().any(|regex| regex.as_str() == "c?d"));
+        for (key, regex) in &map {
+            assert_eq!(map.0[key].as_str(), regex.as_str());
+        }
+        assert_eq!(Serde(&map.0).into_iter().count(), 2);
+        let mut owned = map.into_iter().collect::<Vec<_>>();
+        owned.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(owned[0].1.as_str(), "a.*b");
+        Ok(())
+    }
+
     #[test]
     fn test_option_vec() -> Result<(), Box<dyn std::error::Error>> {
         let json = json!(["a.*b", "c?d"]);
@@ -775,6 +2729,96 @@ mod test {
         assert!(v.len() == 2);
         Ok(())
     }
+    #[test]
+    fn test_vec_arc() -> Result<(), Box<dyn std::error::Error>> {
+        let json = json!(["a.*b", "c?d"]);
+        let vec: Serde<Vec<Arc<Regex>>> = from_value(json.clone())?;
+        let shared = Arc::clone(&vec[1]);
+        assert!(shared.is_match("d"));
+        assert_eq!(to_value(&vec)?, json);
+        let bytes: Serde<Option<Vec<Arc<bytes::Regex>>>> = from_value(json)?;
+        assert_eq!(bytes.as_ref().unwrap()[0].as_str(), "a.*b");
+        assert_eq!(to_value(Serde(&bytes.0))?, json!(["a.*b", "c?d"]));
+        let none: Serde<Option<Vec<Arc<Regex>>>> = from_value(json!(null))?;
+        assert!(none.is_none());
+        assert!(from_value::<Serde<Vec<Arc<Regex>>>>(json!(["("])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arc() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Serialize, Deserialize)]
+        struct Shared {
+            #[serde(with = "crate")]
+            re: Arc<Regex>,
+            #[serde(with = "crate", default)]
+            opt: Option<Arc<bytes::Regex>>,
+        }
+        let shared: Shared = from_str(r#"{"re": "a+b"}"#)?;
+        let clone = Arc::clone(&shared.re);
+        assert!(Arc::ptr_eq(&clone, &shared.re));
+        assert_eq!(clone.as_str(), "a+b");
+        assert!(clone.is_match("xaab"));
+        assert!(shared.opt.is_none());
+        assert_eq!(to_string(&shared)?, r#"{"re":"a+b","opt":null}"#);
+        let shared: Shared = from_str(r#"{"re": "a", "opt": "b?"}"#)?;
+        assert_eq!(to_string(&shared)?, r#"{"re":"a","opt":"b?"}"#);
+        assert!(from_str::<Shared>(r#"{"re": "("}"#).is_err());
+        let re: Serde<Arc<bytes::Regex>> = from_str(r#""c*""#)?;
+        assert_eq!(to_string(&re)?, r#""c*""#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rc() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Serialize, Deserialize)]
+        struct Local {
+            #[serde(with = "crate")]
+            re: Rc<Regex>,
+            #[serde(with = "crate", default)]
+            opt: Option<Rc<bytes::Regex>>,
+        }
+        let local: Local = from_str(r#"{"re": "a+b", "opt": "c"}"#)?;
+        assert_eq!(Rc::strong_count(&local.re), 1);
+        let clone = Rc::clone(&local.re);
+        assert_eq!(Rc::strong_count(&local.re), 2);
+        assert_eq!(to_string(&local)?, r#"{"re":"a+b","opt":"c"}"#);
+        // serialization doesn't keep references either
+        assert_eq!(Rc::strong_count(&local.re), 2);
+        drop(clone);
+        assert_eq!(Rc::strong_count(&local.re), 1);
+        assert_eq!(Rc::strong_count(local.opt.as_ref().unwrap()), 1);
+        let local: Local = from_str(r#"{"re": "a"}"#)?;
+        assert!(local.opt.is_none());
+        assert_eq!(to_string(&local)?, r#"{"re":"a","opt":null}"#);
+        assert!(from_str::<Local>(r#"{"re": "("}"#).is_err());
+        let re: Serde<Rc<bytes::Regex>> = from_str(r#""c*""#)?;
+        assert_eq!(to_string(&re)?, r#""c*""#);
+        Ok(())
+    }
+
+    #[test]
+    fn test_box() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Serialize, Deserialize)]
+        struct Boxed {
+            #[serde(with = "crate")]
+            re: Box<Regex>,
+            #[serde(with = "crate", default)]
+            opt: Option<Box<bytes::Regex>>,
+            #[serde(with = "crate", default)]
+            plain: Option<Box<Regex>>,
+        }
+        let json = r#"{"re":"a+b","opt":"(?-u)\\xff","plain":null}"#;
+        let boxed: Boxed = from_str(json)?;
+        assert!(boxed.re.is_match("aab"));
+        assert!(boxed.opt.as_ref().unwrap().is_match(b"\xff"));
+        assert_eq!(to_string(&boxed)?, json);
+        assert!(from_str::<Boxed>(r#"{"re": "("}"#).is_err());
+        let re: Serde<Box<bytes::Regex>> = from_str(r#""c*""#)?;
+        assert_eq!(to_string(&re)?, r#""c*""#);
+        Ok(())
+    }
+
     #[test]
     fn test_option_hashmap() -> Result<(), Box<dyn std::error::Error>> {
         let json = json!({"a": "a.*b", "b": "c?d"});
@@ -841,4 +2885,358 @@ mod test {
         assert!(re.is_none());
         assert_eq!(to_string(&re).unwrap(), "null");
     }
+
+    #[derive(Deserialize)]
+    struct Layered {
+        #[serde(with = "crate")]
+        pattern: Regex,
+        #[serde(with = "crate", default)]
+        optional: Option<Regex>,
+        #[serde(with = "crate", default)]
+        list: Vec<Regex>,
+        #[serde(with = "crate", default)]
+        map: HashMap<String, Regex>,
+    }
+
+    #[test]
+    fn test_config_crate() -> Result<(), Box<dyn std::error::Error>> {
+        use config::{Config, File, FileFormat};
+
+        let layered: Layered = Config::builder()
+            .add_source(File::from_str(
+                r#"{"pattern": "a+", "list": ["b", "c?"], "map": {"k": "d"}}"#,
+                FileFormat::Json))
+            .set_override("pattern", "x+")?
+            .build()?
+            .try_deserialize()?;
+        assert_eq!(layered.pattern.as_str(), "x+");
+        assert!(layered.optional.is_none());
+        assert_eq!(layered.list[1].as_str(), "c?");
+        assert_eq!(layered.map["k"].as_str(), "d");
+
+        let layered: Layered = Config::builder()
+            .set_override("pattern", "x+")?
+            .set_override("optional", "y")?
+            .build()?
+            .try_deserialize()?;
+        assert_eq!(layered.optional.unwrap().as_str(), "y");
+        assert!(layered.list.is_empty());
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Event {
+        #[serde(with = "crate")]
+        validator: Regex,
+        #[serde(with = "crate")]
+        validators: Vec<Regex>,
+    }
+
+    #[test]
+    fn test_avro() -> Result<(), Box<dyn std::error::Error>> {
+        use apache_avro::{Reader, Schema, Writer, from_value};
+
+        let schema = Schema::parse_str(r#"{
+            "type": "record",
+            "name": "Event",
+            "fields": [
+                {"name": "validator", "type": "string"},
+                {"name": "validators", "type": {
+                    "type": "array", "items": "string"
+                }}
+            ]
+        }"#)?;
+        let mut writer = Writer::new(&schema, Vec::new())?;
+        writer.append_ser(Event {
+            validator: Regex::new(SAMPLE)?,
+            validators: vec![Regex::new("a+")?, Regex::new("b?")?],
+        })?;
+        let encoded = writer.into_inner()?;
+        let mut reader = Reader::new(&encoded[..])?;
+        let event: Event = from_value(&reader.next().unwrap()?)?;
+        assert_eq!(event.validator.as_str(), SAMPLE);
+        assert_eq!(event.validators[0].as_str(), "a+");
+        assert_eq!(event.validators[1].as_str(), "b?");
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Query {
+        #[serde(with = "crate")]
+        filter: Regex,
+        #[serde(with = "crate", default)]
+        exclude: Option<Regex>,
+        #[serde(with = "crate", default)]
+        any: Vec<Regex>,
+    }
+
+    #[test]
+    fn test_serde_qs() -> Result<(), Box<dyn std::error::Error>> {
+        let query = Query {
+            filter: Regex::new(SAMPLE)?,
+            exclude: None,
+            any: vec![Regex::new("a&b")?, Regex::new("c=d+")?],
+        };
+        let qs = serde_qs::to_string(&query)?;
+        let query: Query = serde_qs::from_str(&qs)?;
+        assert_eq!(query.filter.as_str(), SAMPLE);
+        assert!(query.exclude.is_none());
+        assert_eq!(query.any[0].as_str(), "a&b");
+        assert_eq!(query.any[1].as_str(), "c=d+");
+
+        for qs in &[
+            "filter=%5Cd%2B&exclude=x&any[]=a&any[]=b%7Cc",
+            "filter=%5Cd%2B&exclude=x&any=a&any=b%7Cc",
+        ] {
+            let query: Query = serde_qs::from_str(qs)?;
+            assert_eq!(query.filter.as_str(), r"\d+");
+            assert_eq!(query.exclude.unwrap().as_str(), "x");
+            assert_eq!(query.any[1].as_str(), "b|c");
+        }
+        Ok(())
+    }
+
+    #[derive(Deserialize)]
+    struct Env {
+        #[serde(with = "crate")]
+        log_filter: Regex,
+        #[serde(with = "crate", default)]
+        log_exclude: Option<Regex>,
+        #[serde(with = "crate", default)]
+        log_targets: Vec<Regex>,
+    }
+
+    #[test]
+    fn test_envy() -> Result<(), Box<dyn std::error::Error>> {
+        let vars = |pairs: &[(&str, &str)]| pairs.iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        let env: Env = envy::from_iter(vars(&[
+            ("LOG_FILTER", SAMPLE),
+            ("LOG_TARGETS", "^app::.*,^lib$"),
+        ]))?;
+        assert_eq!(env.log_filter.as_str(), SAMPLE);
+        assert!(env.log_exclude.is_none());
+        assert_eq!(env.log_targets[0].as_str(), "^app::.*");
+        assert_eq!(env.log_targets[1].as_str(), "^lib$");
+
+        let env: Env = envy::from_iter(vars(&[
+            ("LOG_FILTER", "a"),
+            ("LOG_EXCLUDE", "b+"),
+        ]))?;
+        assert_eq!(env.log_exclude.unwrap().as_str(), "b+");
+        assert!(env.log_targets.is_empty());
+
+        assert!(envy::from_iter::<_, Env>(vars(&[
+            ("LOG_FILTER", "a("),
+        ])).is_err());
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Section {
+        #[serde(with = "crate")]
+        filter: Regex,
+        #[serde(with = "crate", default)]
+        exclude: Option<Regex>,
+        #[serde(with = "crate")]
+        targets: Vec<Regex>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Merged {
+        name: String,
+        #[serde(flatten)]
+        section: Section,
+        #[serde(flatten)]
+        rest: HashMap<String, serde_json::Value>,
+    }
+
+    #[test]
+    fn test_flatten() -> Result<(), Box<dyn std::error::Error>> {
+        // flatten buffers the values, then replays them to the `with` module
+        let json = json!({
+            "name": "app",
+            "filter": SAMPLE,
+            "exclude": "b+",
+            "targets": ["^lib$", "^core::"],
+            "level": 3,
+        });
+        let merged: Merged = from_value(json.clone())?;
+        assert_eq!(merged.section.filter.as_str(), SAMPLE);
+        assert_eq!(merged.section.exclude.as_ref().unwrap().as_str(), "b+");
+        assert_eq!(merged.section.targets[1].as_str(), "^core::");
+        assert_eq!(merged.rest["level"], 3);
+        assert_eq!(to_value(&merged)?, json);
+
+        let yaml: Merged = serde_yaml::from_str("name: app\nfilter: a\n\
+            targets: []\n")?;
+        assert!(yaml.section.exclude.is_none());
+        assert!(from_value::<Merged>(json!({
+            "name": "app", "filter": "(", "targets": [],
+        })).is_err());
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Row {
+        name: String,
+        #[serde(with = "crate")]
+        pattern: Regex,
+        #[serde(with = "crate", default)]
+        exclude: Option<Regex>,
+    }
+
+    #[test]
+    fn test_csv() -> Result<(), Box<dyn std::error::Error>> {
+        let input = format!("name,pattern,exclude\n\
+            quoted,\"{}\",b+\n\
+            empty,,\n", SAMPLE.replace('"', "\"\""));
+        let rows = csv::Reader::from_reader(input.as_bytes())
+            .deserialize::<Row>()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(rows[0].pattern.as_str(), SAMPLE);
+        assert_eq!(rows[0].exclude.as_ref().unwrap().as_str(), "b+");
+        assert_eq!(rows[1].pattern.as_str(), "");
+        assert!(rows[1].exclude.is_none());
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for row in &rows {
+            writer.serialize(row)?;
+        }
+        assert_eq!(String::from_utf8(writer.into_inner()?)?, input);
+
+        let invalid = "name,pattern,exclude\nx,a(,\n";
+        assert!(csv::Reader::from_reader(invalid.as_bytes())
+            .deserialize::<Row>().next().unwrap().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode() -> Result<(), Box<dyn std::error::Error>> {
+        let query = Query {
+            filter: Regex::new(SAMPLE)?,
+            exclude: None,
+            any: vec![Regex::new("a|b")?, Regex::new(r"\s+$")?],
+        };
+        let yaml = serde_yaml::to_string(&query)?;
+        let mut json = Vec::new();
+        serde_transcode::transcode(
+            serde_yaml::Deserializer::from_str(&yaml),
+            &mut serde_json::Serializer::new(&mut json))?;
+        assert_eq!(json, serde_json::to_vec(&query)?);
+        let back: Query = serde_json::from_slice(&json)?;
+        assert_eq!(back.filter.as_str(), SAMPLE);
+        assert!(back.exclude.is_none());
+        assert_eq!(back.any[1].as_str(), r"\s+$");
+
+        // invalid patterns are passed through and only fail typed loading
+        let mut json = Vec::new();
+        serde_transcode::transcode(
+            serde_yaml::Deserializer::from_str("filter: a(\n"),
+            &mut serde_json::Serializer::new(&mut json))?;
+        assert_eq!(json, br#"{"filter":"a("}"#);
+        assert!(serde_json::from_slice::<Query>(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kdl() -> Result<(), Box<dyn std::error::Error>> {
+        let query: Query = serde_kdl2::from_str(&format!(r##"
+            filter #"{}"#
+            exclude "\\s+$"
+            any a #"b|c"# "\"d\""
+        "##, SAMPLE))?;
+        assert_eq!(query.filter.as_str(), SAMPLE);
+        assert_eq!(query.exclude.as_ref().unwrap().as_str(), r"\s+$");
+        assert_eq!(query.any[2].as_str(), r#""d""#);
+
+        let text = serde_kdl2::to_string(&query)?;
+        let back: Query = serde_kdl2::from_str(&text)?;
+        assert_eq!(back.filter.as_str(), SAMPLE);
+        assert_eq!(back.exclude.unwrap().as_str(), r"\s+$");
+        assert_eq!(back.any.iter().map(|re| re.as_str()).collect::<Vec<_>>(),
+            ["a", "b|c", r#""d""#]);
+
+        let minimal: Query = serde_kdl2::from_str("filter x\nany b\n")?;
+        assert!(minimal.exclude.is_none());
+        assert_eq!(minimal.any.len(), 1);
+        assert!(serde_kdl2::from_str::<Query>("filter \"(\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json5() -> Result<(), Box<dyn std::error::Error>> {
+        let query = Query {
+            filter: Regex::new(SAMPLE)?,
+            exclude: Some(Regex::new(r#"'"\s"#)?),
+            any: vec![Regex::new("a|b")?],
+        };
+        let text = json5::to_string(&query)?;
+        let query: Query = json5::from_str(&text)?;
+        assert_eq!(query.filter.as_str(), SAMPLE);
+        assert_eq!(query.exclude.unwrap().as_str(), r#"'"\s"#);
+        assert_eq!(query.any[0].as_str(), "a|b");
+
+        let query: Query = json5::from_str(r#"{
+            // comments are skipped
+            filter: '\\d+', /* single quotes and unquoted keys */
+            exclude: null,
+            any: [
+                "^a",
+                'b$',  // trailing comma
+            ],
+        }"#)?;
+        assert_eq!(query.filter.as_str(), r"\d+");
+        assert!(query.exclude.is_none());
+        assert_eq!(query.any[1].as_str(), "b$");
+
+        assert!(json5::from_str::<Query>("{filter: '('}").is_err());
+        Ok(())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename = "rule")]
+    struct XmlRule {
+        #[serde(rename = "@filter", with = "crate")]
+        filter: Regex,
+        #[serde(with = "crate")]
+        exclude: Regex,
+        #[serde(with = "crate", default)]
+        any: Vec<Regex>,
+    }
+
+    #[test]
+    fn test_quick_xml() -> Result<(), Box<dyn std::error::Error>> {
+        let rule = XmlRule {
+            filter: Regex::new(r#"a&b<"c">"#)?,
+            exclude: Regex::new("<x> & 'y'")?,
+            any: vec![Regex::new(" a ")?, Regex::new(r"\d+")?],
+        };
+        let xml = quick_xml::se::to_string(&rule)?;
+        assert_eq!(xml, concat!(
+            r#"<rule filter="a&amp;b&lt;&quot;c&quot;&gt;">"#,
+            r#"<exclude>&lt;x&gt; &amp; 'y'</exclude>"#,
+            r#"<any> a </any><any>\d+</any></rule>"#,
+        ));
+        let rule: XmlRule = quick_xml::de::from_str(&xml)?;
+        assert_eq!(rule.filter.as_str(), r#"a&b<"c">"#);
+        assert_eq!(rule.exclude.as_str(), "<x> & 'y'");
+        // element text is trimmed by quick-xml
+        assert_eq!(rule.any[0].as_str(), "a");
+        assert_eq!(rule.any[1].as_str(), r"\d+");
+
+        let rule: XmlRule = quick_xml::de::from_str(r#"
+            <rule filter="^a&amp;b$">
+                <exclude><![CDATA[<x>&]]></exclude>
+                <any>x&#124;y</any>
+                <any><![CDATA[  z  ]]></any>
+            </rule>
+        "#)?;
+        assert_eq!(rule.filter.as_str(), "^a&b$");
+        assert_eq!(rule.exclude.as_str(), "<x>&");
+        assert_eq!(rule.any[0].as_str(), "x|y");
+        assert_eq!(rule.any[1].as_str(), "  z  ");
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+//! (De)serialization of literal search strings as regexes
+//!
+//! The input string is passed through `regex::escape` before compiling, so
+//! every character matches itself: `a.b` only matches `a.b`, not `axb`.
+//! This gives "contains" semantics with the same `Regex` API as other
+//! fields.
+//!
+//! Serialization emits the original unescaped text, so values round-trip
+//! unchanged. A regex which is not a plain literal (i.e. wasn't created by
+//! this module) can't be serialized and produces an error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Search {
+//!     #[serde(with = "serde_regex::literal")]
+//!     term: Regex,
+//! }
+//!
+//! let search: Search = serde_json::from_str(r#"{"term": "1+1"}"#).unwrap();
+//! assert_eq!(search.term.as_str(), r"1\+1");
+//! assert!(search.term.is_match("is 1+1 two?"));
+//! assert_eq!(serde_json::to_string(&search).unwrap(), r#"{"term":"1+1"}"#);
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de, ser};
+
+use crate::syntax::as_literal;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    Regex::new(&regex::escape(&s)).map_err(de::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match as_literal(value.as_str()) {
+        Some(literal) => serializer.serialize_str(&literal),
+        None => Err(ser::Error::custom(format_args!(
+            "pattern {:?} is not a literal", value.as_str()))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Search {
+        #[serde(with = "crate::literal")]
+        term: Regex,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let json = r#"{"term":"(a|b)* \\d [x]"}"#;
+        let search: Search = from_str(json).unwrap();
+        assert!(search.term.is_match(r"x(a|b)* \d [x]"));
+        assert!(!search.term.is_match("ab 1 x"));
+        assert_eq!(to_string(&search).unwrap(), json);
+    }
+
+    #[test]
+    fn test_not_literal() {
+        let search = Search { term: Regex::new("a+").unwrap() };
+        assert!(to_string(&search).is_err());
+    }
+}
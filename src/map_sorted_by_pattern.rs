@@ -0,0 +1,146 @@
+//! Serialization of regex maps with entries sorted by pattern
+//!
+//! Like [`sorted_map`](crate::sorted_map), this gives reproducible output
+//! for a `HashMap`, whose iteration order is random, but entries are
+//! ordered by the pattern string instead of the key. So keys don't need to
+//! implement `Ord`, which is handy for keys like tuples of floats, foreign
+//! types or enums without a meaningful order.
+//!
+//! Keys must implement `Debug`: if several keys map to the same pattern,
+//! those entries are ordered by the `Debug` representation of the key, so
+//! the output is deterministic as long as `Debug` is (which holds for all
+//! derived impls).
+//!
+//! Only the serialization order is affected. It requires collecting and
+//! sorting references to all entries on every serialization, i.e. an
+//! allocation of a vector of the map's size. Deserialization works exactly
+//! like the default one. Works for both `Regex` and `bytes::Regex` values.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Routes {
+//!     #[serde(with = "serde_regex::map_sorted_by_pattern")]
+//!     routes: HashMap<String, Regex>,
+//! }
+//!
+//! let routes: Routes = serde_json::from_str(r#"{"routes": {
+//!     "users": "^/users/\\d+$",
+//!     "index": "^/$",
+//!     "home": "^/$"
+//! }}"#).unwrap();
+//! assert_eq!(serde_json::to_string(&routes).unwrap(),
+//!     r#"{"routes":{"home":"^/$","index":"^/$","users":"^/users/\\d+$"}}"#);
+//! ```
+use std::{collections::HashMap, fmt::Debug};
+
+use serde::{Deserialize, Deserializer, Serializer, ser::SerializeMap};
+
+use crate::Serde;
+
+mod sealed {
+    pub trait Pattern {
+        fn pattern(&self) -> &str;
+    }
+
+    impl Pattern for regex::Regex {
+        fn pattern(&self) -> &str {
+            self.as_str()
+        }
+    }
+
+    impl Pattern for regex::bytes::Regex {
+        fn pattern(&self) -> &str {
+            self.as_str()
+        }
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D, K, V, H>(d: D) -> Result<HashMap<K, V, H>, D::Error>
+where
+    D: Deserializer<'de>,
+    Serde<HashMap<K, V, H>>: Deserialize<'de>,
+{
+    Serde::<HashMap<K, V, H>>::deserialize(d).map(Serde::into_inner)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<K, V, H, S>(value: &HashMap<K, V, H>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    K: Debug + serde::Serialize,
+    V: sealed::Pattern,
+    for<'a> Serde<&'a V>: serde::Serialize,
+    S: Serializer,
+{
+    let mut entries = value.iter().collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| {
+        a.1.pattern().cmp(b.1.pattern())
+            .then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+    });
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, &Serde(value))?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::{Regex, bytes};
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum Kind {
+        Page,
+        Api,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Maps {
+        #[serde(with = "crate::map_sorted_by_pattern")]
+        text: HashMap<Kind, Regex>,
+        #[serde(with = "crate::map_sorted_by_pattern")]
+        bytes: HashMap<u32, bytes::Regex>,
+    }
+
+    fn build(reversed: bool) -> Maps {
+        let mut order = (0..20).collect::<Vec<_>>();
+        if reversed {
+            order.reverse();
+        }
+        let mut bytes = HashMap::new();
+        for i in order {
+            let pattern = format!("b{}", i % 3);
+            bytes.insert(i, bytes::Regex::new(&pattern).unwrap());
+        }
+        let mut text = HashMap::new();
+        text.insert(Kind::Page, Regex::new("^/").unwrap());
+        text.insert(Kind::Api, Regex::new("^/api/").unwrap());
+        Maps { text, bytes }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let json = to_string(&build(false)).unwrap();
+        assert!(json.starts_with(concat!(
+            r#"{"text":{"Page":"^/","Api":"^/api/"},"#,
+            r#""bytes":{"0":"b0","12":"b0","#)));
+        for i in 0..20 {
+            assert_eq!(to_string(&build(i % 2 == 0)).unwrap(), json);
+        }
+        let back: Maps = from_str(&json).unwrap();
+        assert_eq!(back.text[&Kind::Api].as_str(), "^/api/");
+        assert_eq!(to_string(&back).unwrap(), json);
+    }
+}
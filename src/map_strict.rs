@@ -0,0 +1,137 @@
+//! Deserialization of maps of patterns rejecting duplicate keys
+//!
+//! JSON technically allows the same key to appear more than once in an
+//! object, and `serde_json` passes every occurrence on, so a map keeps the
+//! last value. In hand-edited configs a duplicate is usually a
+//! copy-paste mistake which silently drops a rule, so this module fails
+//! with an error naming the key instead. It's opt-in: the default
+//! `serde_regex` module keeps the last value, as `serde` does elsewhere.
+//!
+//! Works for `HashMap<K, Regex, S>` fields with any key type which
+//! implements `Debug` (used in the error message). Duplicates are detected
+//! before the pattern is compiled.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Routes {
+//!     #[serde(with = "serde_regex::map_strict")]
+//!     routes: HashMap<String, Regex>,
+//! }
+//!
+//! let routes: Routes = serde_json::from_str(r#"{"routes": {
+//!     "users": "^/users/",
+//!     "posts": "^/posts/"
+//! }}"#).unwrap();
+//! assert_eq!(routes.routes.len(), 2);
+//! let err = serde_json::from_str::<Routes>(r#"{"routes": {
+//!     "users": "^/users/",
+//!     "users": "^/u/"
+//! }}"#).err().unwrap();
+//! assert!(err.to_string().starts_with(r#"duplicate key "users""#));
+//! ```
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, MapAccess, Visitor},
+};
+
+use crate::Serde;
+
+struct StrictMapVisitor<K, S>(PhantomData<(K, S)>);
+
+impl<'de, K, S> Visitor<'de> for StrictMapVisitor<K, S>
+where
+    K: Hash + Eq + fmt::Debug + Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    type Value = HashMap<K, Regex, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid map")
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut hashmap = match map.size_hint() {
+            Some(size) => HashMap::with_capacity_and_hasher(size, S::default()),
+            None => HashMap::with_hasher(S::default()),
+        };
+        while let Some(key) = map.next_key::<K>()? {
+            if hashmap.contains_key(&key) {
+                return Err(A::Error::custom(format_args!(
+                    "duplicate key {:?}", key)));
+            }
+            let Serde(value) = map.next_value()?;
+            hashmap.insert(key, value);
+        }
+        Ok(hashmap)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, K, S, D>(d: D) -> Result<HashMap<K, Regex, S>, D::Error>
+where
+    D: Deserializer<'de>,
+    K: Hash + Eq + fmt::Debug + Deserialize<'de>,
+    S: BuildHasher + Default,
+{
+    d.deserialize_map(StrictMapVisitor(PhantomData))
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<K, S, Se>(value: &HashMap<K, Regex, S>, serializer: Se)
+    -> Result<Se::Ok, Se::Error>
+where
+    K: Hash + Eq + Serialize,
+    S: BuildHasher + Default,
+    Se: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_value, json};
+
+    #[derive(Serialize, Deserialize)]
+    struct Routes {
+        #[serde(with = "crate::map_strict")]
+        routes: HashMap<u32, Regex>,
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let routes: Routes = from_str(r#"{"routes": {"1": "a", "2": "b"}}"#)
+            .unwrap();
+        assert_eq!(to_value(&routes).unwrap(),
+            json!({"routes": {"1": "a", "2": "b"}}));
+        let err = from_str::<Routes>(r#"{"routes": {"1": "a", "1": "("}}"#)
+            .err().unwrap();
+        assert!(err.to_string().starts_with("duplicate key 1"), "{}", err);
+        assert!(from_str::<Routes>(r#"{"routes": {"1": "("}}"#).is_err());
+        let routes: Routes = serde_yaml::from_str("routes: {}").unwrap();
+        assert!(routes.routes.is_empty());
+    }
+}
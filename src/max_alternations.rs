@@ -0,0 +1,143 @@
+//! Deserialization which limits the number of alternatives in a pattern
+//!
+//! Patterns with thousands of alternatives compile into large automata
+//! and are a common accidental DoS. The pattern is parsed with
+//! `regex-syntax` before compiling and rejected if it has too many
+//! alternatives.
+//!
+//! By default only the top-level alternation is counted, i.e. `a|b|(c|d)`
+//! has three alternatives. The `*_nested` functions sum alternatives of
+//! every alternation in the pattern, including ones inside groups, so
+//! `a|b|(c|d)` counts as five and `^(a|b)$` as two. A pattern without
+//! alternation counts as zero.
+//!
+//! The default limit is [`DEFAULT_MAX_ALTERNATIVES`]. Use
+//! `deserialize_max` or `deserialize_max_nested` with an explicit limit to
+//! override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::max_alternations")]
+//!     pattern: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::max_alternations::serialize",
+//!         deserialize_with =
+//!             "serde_regex::max_alternations::deserialize_max_nested::<_, 3>",
+//!     )]
+//!     word: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{
+//!     "pattern": "a|b|c|d",
+//!     "word": "^(a|b|c|d)$"
+//! }"#).is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{Ast, parse::Parser};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit;
+
+/// Number of alternatives allowed by default
+pub const DEFAULT_MAX_ALTERNATIVES: usize = 1000;
+
+fn count(ast: &Ast, nested: bool) -> usize {
+    if !nested {
+        return match ast {
+            Ast::Alternation(alt) => alt.asts.len(),
+            _ => 0,
+        };
+    }
+    let mut total = 0;
+    visit(ast, &mut |node| {
+        if let Ast::Alternation(alt) = node {
+            total += alt.asts.len();
+        }
+    });
+    total
+}
+
+fn compile<'de, D>(d: D, max: usize, nested: bool) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let ast = Parser::new().parse(&s).map_err(D::Error::custom)?;
+    let alternatives = count(&ast, nested);
+    if alternatives > max {
+        return Err(D::Error::custom(format_args!(
+            "pattern has {} alternatives, at most {} allowed",
+            alternatives, max)));
+    }
+    Regex::new(&s).map_err(D::Error::custom)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, DEFAULT_MAX_ALTERNATIVES, false)
+}
+
+/// Deserialize function allowing at most `MAX` top-level alternatives
+pub fn deserialize_max<'de, D, const MAX: usize>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, MAX, false)
+}
+
+/// Deserialize function allowing at most `MAX` alternatives in total
+pub fn deserialize_max_nested<'de, D, const MAX: usize>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, MAX, true)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex_syntax::ast::parse::Parser;
+    use serde_json::Value;
+
+    use super::{count, deserialize_max, deserialize_max_nested};
+
+    fn counts(pattern: &str) -> (usize, usize) {
+        let ast = Parser::new().parse(pattern).unwrap();
+        (count(&ast, false), count(&ast, true))
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(counts("abc"), (0, 0));
+        assert_eq!(counts("a|b|(c|d)"), (3, 5));
+        assert_eq!(counts("^(a|b)$"), (0, 2));
+        assert_eq!(counts("[|]|x"), (2, 2));
+    }
+
+    #[test]
+    fn test_limit() {
+        let value = || Value::from("a|b|c");
+        assert!(deserialize_max::<_, 3>(value()).is_ok());
+        assert_eq!(deserialize_max::<_, 2>(value()).unwrap_err().to_string(),
+            "pattern has 3 alternatives, at most 2 allowed");
+        assert!(deserialize_max_nested::<_, 1>(Value::from("(a|b)")).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Deserialization which limits counted repetitions like `a{1000}`
+//!
+//! Each counted repetition is compiled by copying the repeated expression,
+//! so a short pattern like `\w{100000}` compiles into a huge automaton (or
+//! fails with a size limit error only after doing a lot of work). This
+//! module parses the pattern with `regex-syntax` before compiling and
+//! rejects it if any repetition has a bound above the limit, naming the
+//! offending quantifier.
+//!
+//! The largest number in the quantifier is checked, so `{n}`, `{n,m}` and
+//! `{n,}` are rejected when `n` (or `m`) is above the limit. Unbounded `*`,
+//! `+` and `?` are allowed by default, since they don't copy anything. The
+//! `*_finite` functions reject every unbounded repetition (`*`, `+` and
+//! `{n,}`) as well, for callers which need every match to be bounded in
+//! length.
+//!
+//! The bound is checked per quantifier: nested ones like `(a{100}){100}`
+//! multiply, use [`size_guarded`](crate::size_guarded) to limit the total.
+//!
+//! The default limit is [`DEFAULT_MAX_REPEAT`]. Use `deserialize_max` or
+//! `deserialize_max_finite` with an explicit limit to override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::max_repeat")]
+//!     pattern: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::max_repeat::serialize",
+//!         deserialize_with =
+//!             "serde_regex::max_repeat::deserialize_max_finite::<_, 64>",
+//!     )]
+//!     code: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{
+//!     "pattern": "\\w+ \\d{1,10}",
+//!     "code": "[A-Z]{2}\\d{1,8}"
+//! }"#).is_ok());
+//! let err = serde_json::from_str::<Config>(r#"{
+//!     "pattern": "a{1000000}",
+//!     "code": "x"
+//! }"#).err().unwrap();
+//! assert!(err.to_string().starts_with(
+//!     "repetition `{1000000}` exceeds the limit of 1000"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    Ast,
+    RepetitionKind,
+    RepetitionRange,
+    parse::Parser,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit;
+
+/// Largest repetition bound allowed by default
+pub const DEFAULT_MAX_REPEAT: u32 = 1000;
+
+fn check(pattern: &str, max: u32, finite: bool) -> Result<(), String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut error = None;
+    visit(&ast, &mut |node| {
+        let rep = match node {
+            Ast::Repetition(rep) if error.is_none() => rep,
+            _ => return,
+        };
+        let (bound, unbounded) = match rep.op.kind {
+            RepetitionKind::ZeroOrOne => (1, false),
+            RepetitionKind::ZeroOrMore | RepetitionKind::OneOrMore => {
+                (0, true)
+            }
+            RepetitionKind::Range(RepetitionRange::Exactly(n)) => (n, false),
+            RepetitionKind::Range(RepetitionRange::AtLeast(n)) => (n, true),
+            RepetitionKind::Range(RepetitionRange::Bounded(n, m)) => {
+                (n.max(m), false)
+            }
+        };
+        let op = &pattern[rep.op.span.start.offset..rep.op.span.end.offset];
+        if bound > max {
+            error = Some(format!("repetition `{}` exceeds the limit of {}",
+                op, max));
+        } else if unbounded && finite {
+            error = Some(format!("unbounded repetition `{}` is not allowed",
+                op));
+        }
+    });
+    error.map_or(Ok(()), Err)
+}
+
+fn compile<'de, D>(d: D, max: u32, finite: bool) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    check(&s, max, finite).map_err(D::Error::custom)?;
+    Regex::new(&s).map_err(D::Error::custom)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, DEFAULT_MAX_REPEAT, false)
+}
+
+/// Deserialize function allowing repetition bounds of at most `MAX`
+pub fn deserialize_max<'de, D, const MAX: u32>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, MAX, false)
+}
+
+/// Deserialize function allowing only bounded repetitions, up to `MAX`
+pub fn deserialize_max_finite<'de, D, const MAX: u32>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, MAX, true)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{check, deserialize, deserialize_max};
+
+    #[test]
+    fn test_check() {
+        assert!(check(r"a{1000} b{0,1000} c{5,}* d+? e?", 1000, false).is_ok());
+        assert_eq!(check(r"(x(a{3,1001}?))", 1000, false).unwrap_err(),
+            "repetition `{3,1001}?` exceeds the limit of 1000");
+        assert!(check(r"a{1001,}", 1000, false).is_err());
+        assert!(check(r"[a{5000}]", 10, false).is_ok());
+        assert!(check(r"a{0,8}", 10, true).is_ok());
+        assert_eq!(check(r"a{0,8}b+", 10, true).unwrap_err(),
+            "unbounded repetition `+` is not allowed");
+        assert!(check(r"a{2,}", 10, true).is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert!(deserialize(Value::from(r"\d{4}")).is_ok());
+        assert!(deserialize(Value::from(r"\w{100000}")).is_err());
+        assert!(deserialize_max::<_, 3>(Value::from(r"\d{4}")).is_err());
+        assert!(deserialize(Value::from("a{")).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+
+use regex::{RegexSet, SetMatches};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::Error,
+    ser::SerializeSeq,
+};
+
+const GLOB_PREFIX: &str = "glob:";
+const REGEX_PREFIX: &str = "re:";
+
+/// Kind of an entry of [`MixedMatchers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatcherKind {
+    /// A shell-style glob, written with the `glob:` prefix
+    Glob,
+    /// A regular expression, written with the `re:` prefix or without one
+    Regex,
+}
+
+/// A list of globs and regexes matched as a single set
+///
+/// Deserializes from a sequence of strings, each one of:
+///
+/// * `glob:<glob>` -- a shell-style glob matching the whole string;
+/// * `re:<pattern>` -- a regular expression, matching anywhere like
+///   `Regex::is_match`;
+/// * `<pattern>` -- no prefix means a regular expression too.
+///
+/// Globs are translated into regexes: `*` matches any characters except
+/// `/`, `**` matches any characters including `/`, `?` matches a single
+/// character except `/`, `[abc]` and `[a-z]` are classes (`[!abc]` is a
+/// negated one) and every other character is literal. All entries are
+/// compiled into a single `RegexSet`, so [`is_match`] checks the text once.
+///
+/// Serialization writes every entry with its prefix, so unprefixed regexes
+/// get the `re:` prefix and globs are written as globs, not as translated
+/// regexes.
+///
+/// ```rust
+/// use serde_regex::MixedMatchers;
+///
+/// let ignore: MixedMatchers = serde_json::from_str(r#"[
+///     "glob:target/**",
+///     "glob:*.swp",
+///     "re:(^|/)\\.#"
+/// ]"#).unwrap();
+/// assert!(ignore.is_match("target/debug/app"));
+/// assert!(ignore.is_match("main.rs.swp"));
+/// assert!(!ignore.is_match("src/main.rs.swp"));
+/// assert!(ignore.is_match("src/.#lock"));
+/// assert!(!ignore.is_match("src/main.rs"));
+/// ```
+///
+/// [`is_match`]: MixedMatchers::is_match
+#[derive(Debug, Clone)]
+pub struct MixedMatchers {
+    entries: Vec<(MatcherKind, String)>,
+    set: RegexSet,
+}
+
+/// Translates the glob into an anchored regex
+fn glob_to_regex(glob: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(glob.len() * 2 + 2);
+    result.push('^');
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                result.push_str(".*");
+            }
+            '*' => result.push_str("[^/]*"),
+            '?' => result.push_str("[^/]"),
+            '[' => {
+                result.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    result.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    match c {
+                        ']' => {
+                            closed = true;
+                            break;
+                        }
+                        '-' => result.push('-'),
+                        c => result.push_str(&regex::escape(c.encode_utf8(
+                            &mut [0; 4]))),
+                    }
+                }
+                if !closed {
+                    return Err(format!("unclosed class in glob {:?}", glob));
+                }
+                result.push(']');
+            }
+            c => result.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    result.push('$');
+    Ok(result)
+}
+
+impl MixedMatchers {
+    /// Compiles the entries into a set
+    pub fn new<I, S>(entries: I) -> Result<MixedMatchers, String>
+    where
+        I: IntoIterator<Item = (MatcherKind, S)>,
+        S: Into<String>,
+    {
+        let entries = entries.into_iter()
+            .map(|(kind, source)| (kind, source.into()))
+            .collect::<Vec<_>>();
+        let mut patterns = Vec::with_capacity(entries.len());
+        for (kind, source) in &entries {
+            patterns.push(match kind {
+                MatcherKind::Glob => Cow::Owned(glob_to_regex(source)?),
+                MatcherKind::Regex => Cow::Borrowed(&source[..]),
+            });
+        }
+        let set = RegexSet::new(patterns).map_err(|e| e.to_string())?;
+        Ok(MixedMatchers { entries, set })
+    }
+
+    /// Returns true if any of the entries matches the text
+    pub fn is_match(&self, text: &str) -> bool {
+        self.set.is_match(text)
+    }
+
+    /// Returns indexes of all entries matching the text
+    pub fn matches(&self, text: &str) -> SetMatches {
+        self.set.matches(text)
+    }
+
+    /// Returns the compiled set, globs are translated into regexes
+    pub fn as_set(&self) -> &RegexSet {
+        &self.set
+    }
+
+    /// Iterates over kinds and sources of entries as they were written
+    pub fn iter(&self) -> impl Iterator<Item = (MatcherKind, &str)> {
+        self.entries.iter().map(|(kind, source)| (*kind, &source[..]))
+    }
+}
+
+impl<'de> Deserialize<'de> for MixedMatchers {
+    fn deserialize<D>(d: D) -> Result<MixedMatchers, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let items = <Vec<Cow<str>>>::deserialize(d)?;
+        let entries = items.iter().map(|item| {
+            if let Some(glob) = item.strip_prefix(GLOB_PREFIX) {
+                (MatcherKind::Glob, glob)
+            } else if let Some(pattern) = item.strip_prefix(REGEX_PREFIX) {
+                (MatcherKind::Regex, pattern)
+            } else {
+                (MatcherKind::Regex, &item[..])
+            }
+        });
+        MixedMatchers::new(entries).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for MixedMatchers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.entries.len()))?;
+        for (kind, source) in &self.entries {
+            let prefix = match kind {
+                MatcherKind::Glob => GLOB_PREFIX,
+                MatcherKind::Regex => REGEX_PREFIX,
+            };
+            seq.serialize_element(&format!("{}{}", prefix, source))?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    use super::{MatcherKind, MixedMatchers, glob_to_regex};
+
+    #[test]
+    fn test_glob() {
+        assert_eq!(glob_to_regex("*.rs").unwrap(), r"^[^/]*\.rs$");
+        assert_eq!(glob_to_regex("a/**/b?").unwrap(), r"^a/.*/b[^/]$");
+        assert_eq!(glob_to_regex("[!a-c.]x").unwrap(), r"^[^a-c\.]x$");
+        assert!(glob_to_regex("[ab").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let matchers: MixedMatchers = from_str(
+            r#"["glob:*.log", "re:^tmp/", "\\.bak$"]"#).unwrap();
+        assert_eq!(matchers.iter().collect::<Vec<_>>(), vec![
+            (MatcherKind::Glob, "*.log"),
+            (MatcherKind::Regex, "^tmp/"),
+            (MatcherKind::Regex, r"\.bak$"),
+        ]);
+        assert!(matchers.is_match("error.log"));
+        assert!(!matchers.is_match("logs/error.log"));
+        assert!(matchers.is_match("tmp/x"));
+        assert_eq!(matchers.matches("tmp/a.bak").into_iter()
+            .collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(to_string(&matchers).unwrap(),
+            r#"["glob:*.log","re:^tmp/","re:\\.bak$"]"#);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_str::<MixedMatchers>(r#"["re:("]"#).is_err());
+        assert!(from_str::<MixedMatchers>(r#"["glob:[a"]"#).unwrap_err()
+            .to_string().starts_with("unclosed class in glob"));
+        assert!(from_str::<MixedMatchers>("[]").unwrap().iter().next()
+            .is_none());
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::OnceLock;
+
+use regex::{Regex, RegexSet, SetMatches};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+/// A set of patterns which can be changed at runtime
+///
+/// (De)serializes as a sequence of patterns, just like `RegexSet`. The
+/// patterns are stored as strings, and the `RegexSet` is compiled lazily:
+/// `add` and `remove` only mark the set as dirty, and the next call to
+/// `matches` (or an explicit `rebuild`) compiles all patterns again.
+///
+/// Rebuilding costs as much as compiling a new `RegexSet` of all patterns,
+/// so batch changes together rather than interleaving them with matching.
+/// Every added pattern is compiled on its own first, to report an invalid
+/// one right away, so a rebuild can only fail if the combined set exceeds
+/// the size limit of the `regex` crate.
+///
+/// ```rust
+/// use serde_regex::MutableRegexSet;
+///
+/// let mut set: MutableRegexSet = serde_json::from_str(r#"["a+", "b+"]"#)
+///     .unwrap();
+/// set.add(r"\d+").unwrap();
+/// assert!(set.remove("a+"));
+/// assert!(set.is_dirty());
+/// let matches = set.matches("aa 12").unwrap();
+/// assert_eq!(matches.into_iter().collect::<Vec<_>>(), vec![1]);
+/// assert_eq!(serde_json::to_string(&set).unwrap(), r#"["b+","\\d+"]"#);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MutableRegexSet {
+    patterns: Vec<String>,
+    set: OnceLock<RegexSet>,
+}
+
+impl MutableRegexSet {
+    /// Creates an empty set
+    pub fn new() -> MutableRegexSet {
+        MutableRegexSet::default()
+    }
+
+    /// Adds a pattern to the end of the set, marking the set dirty
+    ///
+    /// Returns an error without changing the set if the pattern is invalid.
+    pub fn add<S: Into<String>>(&mut self, pattern: S)
+        -> Result<(), regex::Error>
+    {
+        let pattern = pattern.into();
+        Regex::new(&pattern)?;
+        self.patterns.push(pattern);
+        self.set = OnceLock::new();
+        Ok(())
+    }
+
+    /// Removes the first occurrence of the pattern, marking the set dirty
+    ///
+    /// Returns false if there is no such pattern. Indexes of patterns after
+    /// the removed one shift by one.
+    pub fn remove(&mut self, pattern: &str) -> bool {
+        match self.patterns.iter().position(|p| p == pattern) {
+            Some(idx) => {
+                self.patterns.remove(idx);
+                self.set = OnceLock::new();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if the set was changed since it was last compiled
+    pub fn is_dirty(&self) -> bool {
+        self.set.get().is_none()
+    }
+
+    /// Compiles the set if it's dirty and returns it
+    pub fn rebuild(&self) -> Result<&RegexSet, regex::Error> {
+        if let Some(set) = self.set.get() {
+            return Ok(set);
+        }
+        let set = RegexSet::new(&self.patterns)?;
+        Ok(self.set.get_or_init(|| set))
+    }
+
+    /// Returns indexes of patterns matching `text`, rebuilding if dirty
+    pub fn matches(&self, text: &str) -> Result<SetMatches, regex::Error> {
+        Ok(self.rebuild()?.matches(text))
+    }
+
+    /// Returns patterns in order
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+impl<'de> Deserialize<'de> for MutableRegexSet {
+    fn deserialize<D>(d: D) -> Result<MutableRegexSet, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let patterns = <Vec<String>>::deserialize(d)?;
+        let set = RegexSet::new(&patterns).map_err(D::Error::custom)?;
+        Ok(MutableRegexSet { patterns, set: OnceLock::from(set) })
+    }
+}
+
+impl Serialize for MutableRegexSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.patterns.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::from_str;
+
+    use super::MutableRegexSet;
+
+    #[test]
+    fn test_dirty() {
+        let mut set: MutableRegexSet = from_str(r#"["a"]"#).unwrap();
+        assert!(!set.is_dirty());
+        assert!(set.add("(").is_err());
+        assert!(!set.is_dirty());
+        set.add("b").unwrap();
+        assert!(set.is_dirty());
+        assert_eq!(set.rebuild().unwrap().len(), 2);
+        assert!(!set.is_dirty());
+        assert!(!set.remove("c"));
+        assert!(!set.is_dirty());
+        assert!(set.remove("a"));
+        assert!(set.matches("b").unwrap().matched(0));
+        assert!(from_str::<MutableRegexSet>(r#"["("]"#).is_err());
+    }
+}
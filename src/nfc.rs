@@ -0,0 +1,98 @@
+//! (De)serialization of patterns normalized to Unicode NFC
+//!
+//! Requires the `unicode-normalization` feature, which adds a dependency on
+//! the crate of the same name.
+//!
+//! The same accented letter can be written as a single code point (`é`,
+//! U+00E9) or as a letter followed by a combining mark (`e` U+0065 and
+//! U+0301). They look identical, but a regex built from one doesn't match
+//! text written the other way. This module converts the pattern to the
+//! composed form (NFC) before compiling, so patterns behave the same
+//! whichever form the config file happens to use. Serialization writes the
+//! NFC form as well. Note that only the pattern is normalized: the text
+//! being matched should be NFC too for this to help.
+//!
+//! Normalization works on the pattern as plain text, so it affects literal
+//! characters only, while escapes like `\u{301}` are left as they are. Be
+//! careful with combining marks inside classes: `[e\u{301}]` is kept as a
+//! class of two characters, but the same class with a raw combining mark
+//! is composed into `[é]`, a class of one character.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Search {
+//!     #[serde(with = "serde_regex::nfc")]
+//!     word: Regex,
+//! }
+//!
+//! // decomposed form, as typed on some keyboards
+//! let search: Search = serde_json::from_str(r#"{"word": "cafe\u0301"}"#)
+//!     .unwrap();
+//! assert!(search.word.is_match("caf\u{e9}"));
+//! assert_eq!(search.word.as_str(), "caf\u{e9}");
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+fn normalize(pattern: &str) -> Cow<'_, str> {
+    if is_nfc(pattern) {
+        Cow::Borrowed(pattern)
+    } else {
+        Cow::Owned(pattern.nfc().collect())
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    Regex::new(&normalize(&s)).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&normalize(value.as_str()))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize};
+
+    const COMPOSED: &str = "caf\u{e9}";
+    const DECOMPOSED: &str = "cafe\u{301}";
+
+    #[test]
+    fn test_normalize() {
+        for pattern in &[COMPOSED, DECOMPOSED] {
+            let regex = deserialize(Value::from(*pattern)).unwrap();
+            assert_eq!(regex.as_str(), COMPOSED);
+            assert!(regex.is_match(COMPOSED));
+        }
+        assert!(!Regex::new(DECOMPOSED).unwrap().is_match(COMPOSED));
+        let regex = deserialize(Value::from(r"[e\u{301}]")).unwrap();
+        assert!(regex.is_match("\u{301}"));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let regex = Regex::new(DECOMPOSED).unwrap();
+        assert_eq!(serialize(&regex, serde_json::value::Serializer).unwrap(),
+            COMPOSED);
+    }
+}
@@ -0,0 +1,134 @@
+//! Deserialization rejecting nested unbounded quantifiers like `(a+)+`
+//!
+//! The `regex` crate matches in linear time, so these patterns are not a
+//! problem for it. But they are the classic shape of catastrophic
+//! backtracking (ReDoS) in backtracking engines, like the ones in
+//! JavaScript, Python, Java or PCRE. If patterns loaded with this crate are
+//! also exported to such engines, use this module to reject them at load
+//! time. It's a cross-engine safety measure, not a guarantee: other slow
+//! shapes, like overlapping alternatives `(a|a)+`, are not detected.
+//!
+//! The pattern is parsed with `regex-syntax` and rejected if an unbounded
+//! repetition (`*`, `+` or `{n,}`) contains another unbounded repetition
+//! anywhere inside it, for example:
+//!
+//! * `(a+)+`, `(a*)*`, `(?:a+b)*`;
+//! * `(a|b+)+`, where only one alternative repeats;
+//! * `(\w+\s?)*`, the usual shape in real-world patterns;
+//! * `((ab)+c){2,}`.
+//!
+//! Bounded repetitions are allowed on either level, so `(a+){3}`,
+//! `(a{1,5})+` and `(a?)+` pass, as do sequential ones like `a+b+`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::no_nested_quantifiers")]
+//!     pattern: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Rule>(r#"{"pattern": "^\\w+@\\w+$"}"#)
+//!     .is_ok());
+//! let err = serde_json::from_str::<Rule>(r#"{"pattern": "^(\\w+\\s?)*$"}"#)
+//!     .err().unwrap();
+//! assert!(err.to_string().starts_with(
+//!     r"nested unbounded repetition `(\w+\s?)*`"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    Ast,
+    Repetition,
+    RepetitionKind,
+    RepetitionRange,
+    parse::Parser,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit;
+
+fn is_unbounded(rep: &Repetition) -> bool {
+    matches!(rep.op.kind,
+        RepetitionKind::ZeroOrMore
+        | RepetitionKind::OneOrMore
+        | RepetitionKind::Range(RepetitionRange::AtLeast(_)))
+}
+
+fn contains_unbounded(ast: &Ast) -> bool {
+    let mut found = false;
+    visit(ast, &mut |node| {
+        if let Ast::Repetition(rep) = node {
+            found |= is_unbounded(rep);
+        }
+    });
+    found
+}
+
+fn check(pattern: &str) -> Result<(), String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut error = None;
+    visit(&ast, &mut |node| match node {
+        Ast::Repetition(rep) if error.is_none()
+            && is_unbounded(rep) && contains_unbounded(&rep.ast)
+        => {
+            error = Some(format!("nested unbounded repetition `{}`",
+                &pattern[rep.span.start.offset..rep.span.end.offset]));
+        }
+        _ => {}
+    });
+    error.map_or(Ok(()), Err)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    check(&s).map_err(D::Error::custom)?;
+    Regex::new(&s).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{check, deserialize};
+
+    #[test]
+    fn test_check() {
+        for pattern in &["(a+)+", "(a*)*", "(?:a+b)*", "(a|b+)+", r"(\w+\s?)*",
+                         "((ab)+c){2,}", "x(?i:(y{2,})*)"]
+        {
+            assert!(check(pattern).is_err(), "{:?}", pattern);
+        }
+        for pattern in &["(a+){3}", "(a{1,5})+", "(a?)+", "a+b+", "(ab)*",
+                         "[+*]+", r"\(a+\)+"]
+        {
+            assert!(check(pattern).is_ok(), "{:?}", pattern);
+        }
+        assert_eq!(check("x((a+)+)*").unwrap_err(),
+            "nested unbounded repetition `((a+)+)*`");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert!(deserialize(Value::from("a+b*")).is_ok());
+        assert!(deserialize(Value::from("(a+)+")).is_err());
+        assert!(deserialize(Value::from("(")).is_err());
+    }
+}
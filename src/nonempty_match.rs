@@ -0,0 +1,70 @@
+//! Deserialization which rejects patterns matching the empty string
+//!
+//! Patterns like `a*` or `x?|y` match empty input, which makes loops over
+//! successive matches spin without consuming input in some code. After
+//! compiling, the pattern is checked with `is_match("")`, which is a single
+//! cheap match at load time. This is a semantic check: an empty pattern is
+//! rejected, but so is any other pattern which can match nothing.
+//!
+//! Note that only matching the empty *input* is checked, so a pattern like
+//! `^|a` is rejected while `\ba*` (which can match empty only between
+//! characters) is not.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::nonempty_match")]
+//!     separator: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{"separator": ",+"}"#).is_ok());
+//! assert!(serde_json::from_str::<Config>(r#"{"separator": ",*"}"#).is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    if regex.is_match("") {
+        return Err(D::Error::custom(format_args!(
+            "pattern {:?} matches the empty string", regex.as_str())));
+    }
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::deserialize;
+
+    #[test]
+    fn test_check() {
+        assert!(deserialize(Value::from("a+")).is_ok());
+        assert!(deserialize(Value::from(r"\ba*")).is_ok());
+        assert_eq!(deserialize(Value::from("")).unwrap_err().to_string(),
+            r#"pattern "" matches the empty string"#);
+        assert!(deserialize(Value::from("x?|y")).is_err());
+        assert!(deserialize(Value::from("^$")).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Deserialization of a list of patterns into one combined regex
+//!
+//! Input is a sequence of patterns, which are combined into a single regex
+//! matching if any of them matches. Matching a single regex is faster than
+//! trying a list of regexes one by one: the text is scanned once, and the
+//! `regex` crate optimizes alternations itself, e.g. alternatives starting
+//! with literals are searched for together with an Aho-Corasick prefilter.
+//!
+//! On top of plain joining with `|`, common structure is factored out:
+//!
+//! * duplicate patterns are removed;
+//! * if every pattern starts with `^`, the anchor is hoisted out of the
+//!   alternation, and so is a trailing `$` if every pattern ends with one,
+//!   i.e. `["^foo$", "^bar$"]` becomes `^(?:foo|bar)$`, which the engine
+//!   recognizes as anchored and never retries at later positions;
+//! * a pattern with a top-level flag group like `(?i)` is wrapped into a
+//!   non-capturing group, so the flags don't leak into other alternatives
+//!   (such patterns also disable hoisting of anchors).
+//!
+//! A sequence of a single pattern is compiled as is, and an empty sequence
+//! is an error.
+//!
+//! The combined regex only tells whether *some* pattern matched: identity
+//! of the individual patterns is lost, and capture groups are numbered
+//! across all of them. Use a `RegexSet` when you need to know which
+//! pattern matched. Serialization writes the combined pattern as a
+//! sequence of one element, which reads back unchanged.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::optimize_alternation")]
+//!     hosts: Regex,
+//! }
+//!
+//! let filter: Filter = serde_json::from_str(r#"{"hosts": [
+//!     "^example\\.com$",
+//!     "^www\\.example\\.com$",
+//!     "^example\\.com$"
+//! ]}"#).unwrap();
+//! assert_eq!(filter.hosts.as_str(),
+//!     r"^(?:example\.com|www\.example\.com)$");
+//! assert!(filter.hosts.is_match("www.example.com"));
+//! assert_eq!(serde_json::to_string(&filter).unwrap(),
+//!     r#"{"hosts":["^(?:example\\.com|www\\.example\\.com)$"]}"#);
+//! ```
+use std::{borrow::Cow, collections::HashSet};
+
+use regex::Regex;
+use regex_syntax::ast::{Ast, AssertionKind, parse::Parser};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::has_top_level_flags;
+
+struct Alternative<'a> {
+    pattern: &'a str,
+    flags: bool,
+    start: Option<usize>,
+    end: Option<usize>,
+}
+
+fn is_assertion(ast: &Ast, kind: AssertionKind) -> bool {
+    matches!(ast, Ast::Assertion(assertion) if assertion.kind == kind)
+}
+
+fn analyze(pattern: &str) -> Result<Alternative<'_>, String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let items = match &ast {
+        Ast::Concat(concat) => &concat.asts[..],
+        ast => std::slice::from_ref(ast),
+    };
+    let start = items.first()
+        .filter(|first| is_assertion(first, AssertionKind::StartLine))
+        .map(|first| first.span().end.offset);
+    let end = items.last()
+        .filter(|last| is_assertion(last, AssertionKind::EndLine))
+        .filter(|_| start.is_none() || items.len() > 1)
+        .map(|last| last.span().start.offset);
+    Ok(Alternative { pattern, flags: has_top_level_flags(&ast), start, end })
+}
+
+fn combine(patterns: &[Cow<str>]) -> Result<String, String> {
+    let mut seen = HashSet::new();
+    let alternatives = patterns.iter()
+        .filter(|p| seen.insert(&p[..]))
+        .map(|p| analyze(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [single] = &alternatives[..] {
+        return Ok(single.pattern.to_string());
+    }
+    let no_flags = alternatives.iter().all(|a| !a.flags);
+    let hoist_start = no_flags && alternatives.iter().all(|a| a.start.is_some());
+    let hoist_end = no_flags && alternatives.iter().all(|a| a.end.is_some());
+    let hoist = hoist_start || hoist_end;
+
+    let mut result = String::new();
+    if hoist_start {
+        result.push('^');
+    }
+    if hoist {
+        result.push_str("(?:");
+    }
+    for (index, alternative) in alternatives.iter().enumerate() {
+        if index > 0 {
+            result.push('|');
+        }
+        let start = alternative.start.filter(|_| hoist_start).unwrap_or(0);
+        let end = alternative.end.filter(|_| hoist_end)
+            .unwrap_or(alternative.pattern.len());
+        let inner = &alternative.pattern[start..end];
+        if alternative.flags {
+            result.push_str("(?:");
+            result.push_str(inner);
+            result.push(')');
+        } else {
+            result.push_str(inner);
+        }
+    }
+    if hoist {
+        result.push(')');
+    }
+    if hoist_end {
+        result.push('$');
+    }
+    Ok(result)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let patterns = <Vec<Cow<str>>>::deserialize(d)?;
+    if patterns.is_empty() {
+        return Err(D::Error::invalid_length(0, &"at least one pattern"));
+    }
+    let combined = combine(&patterns).map_err(D::Error::custom)?;
+    Regex::new(&combined).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(std::iter::once(value.as_str()))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    use super::{deserialize, serialize};
+
+    fn load(value: Value) -> Result<String, String> {
+        deserialize(value)
+            .map(|re| re.as_str().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_hoist() {
+        assert_eq!(load(json!(["^a$", "^b+$", "^$"])).unwrap(),
+            "^(?:a|b+|)$");
+        assert_eq!(load(json!(["^a", "^b$"])).unwrap(), "^(?:a|b$)");
+        assert_eq!(load(json!(["a$", "^b$"])).unwrap(), "(?:a|^b)$");
+        assert_eq!(load(json!(["^", "^a$"])).unwrap(), "^(?:|a$)");
+        assert_eq!(load(json!(["$", "^a$"])).unwrap(), "(?:|^a)$");
+        assert_eq!(load(json!(["^a|b$", "^c$"])).unwrap(), "^a|b$|^c$");
+        assert_eq!(load(json!([r"\^a$", "^b$"])).unwrap(), r"(?:\^a|^b)$");
+    }
+
+    #[test]
+    fn test_combine() {
+        assert_eq!(load(json!(["a", "b", "a"])).unwrap(), "a|b");
+        assert_eq!(load(json!(["a", "a"])).unwrap(), "a");
+        assert_eq!(load(json!(["^a(?i)$", "^b$"])).unwrap(),
+            "(?:^a(?i)$)|^b$");
+        assert_eq!(load(json!(["^(?:a|b)$"])).unwrap(), "^(?:a|b)$");
+        assert!(load(json!([])).is_err());
+        assert!(load(json!(["a", "("])).is_err());
+        assert!(load(json!("a")).is_err());
+    }
+
+    #[test]
+    fn test_same_matches() {
+        let patterns = ["^a+$", "^(?m)b$", "^c", "d$", "(?i)e"];
+        let combined = Regex::new(&load(json!(patterns)).unwrap()).unwrap();
+        for text in &["aa", "xa", "b", "x\nb", "cx", "xd", "E", "x"] {
+            let expected = patterns.iter()
+                .any(|p| Regex::new(p).unwrap().is_match(text));
+            assert_eq!(combined.is_match(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let re = deserialize(json!(["^a$", "^b$"])).unwrap();
+        let value = serialize(&re, serde_json::value::Serializer).unwrap();
+        assert_eq!(value, json!(["^(?:a|b)$"]));
+        assert_eq!(load(value).unwrap(), re.as_str());
+    }
+}
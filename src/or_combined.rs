@@ -0,0 +1,126 @@
+//! Deserialization of a regex from a list of alternatives
+//!
+//! Accepts either a plain pattern string or a sequence of alternatives,
+//! which are combined with `|` into a single regex. An alternative is
+//! wrapped into a non-capturing group only if it contains a top-level flag
+//! group like `(?i)`, which would otherwise apply to the alternatives
+//! following it. Other alternatives are joined verbatim, so output of
+//! [`split_alternation`](crate::split_alternation) is read back into
+//! exactly the same pattern.
+//!
+//! An empty sequence is an error. Serialization emits the combined pattern
+//! as a string.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::or_combined")]
+//!     pattern: Regex,
+//! }
+//!
+//! let config: Config = serde_json::from_str(
+//!     r#"{"pattern": ["foo", "(?i)bar", "baz"]}"#).unwrap();
+//! assert_eq!(config.pattern.as_str(), "foo|(?:(?i)bar)|baz");
+//! ```
+use std::{borrow::Cow, fmt};
+
+use regex::Regex;
+use regex_syntax::ast::parse::Parser;
+use serde::{
+    Deserializer,
+    Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+use crate::syntax::has_top_level_flags;
+
+struct AlternativesVisitor;
+
+impl<'a> Visitor<'a> for AlternativesVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pattern or sequence of alternatives")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(v).map_err(E::custom)
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Regex, A::Error>
+    where
+        A: SeqAccess<'a>,
+    {
+        let mut pattern = String::new();
+        let mut empty = true;
+        while let Some(alternative) = seq.next_element::<Cow<str>>()? {
+            if !empty {
+                pattern.push('|');
+            }
+            empty = false;
+            let ast = Parser::new().parse(&alternative)
+                .map_err(A::Error::custom)?;
+            if has_top_level_flags(&ast) {
+                pattern.push_str("(?:");
+                pattern.push_str(&alternative);
+                pattern.push(')');
+            } else {
+                pattern.push_str(&alternative);
+            }
+        }
+        if empty {
+            return Err(A::Error::invalid_length(0, &self));
+        }
+        Regex::new(&pattern).map_err(A::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(AlternativesVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{Deserializer, from_str, Value};
+
+    fn load(json: &str) -> Result<Regex, serde_json::Error> {
+        super::deserialize(&mut Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn test_combine() {
+        assert_eq!(load(r#"["a", "b|c", "(d)"]"#).unwrap().as_str(),
+            "a|b|c|(d)");
+        assert_eq!(load(r#"["x(?i)a", "b"]"#).unwrap().as_str(),
+            "(?:x(?i)a)|b");
+        assert_eq!(load(r#""a|b""#).unwrap().as_str(), "a|b");
+        let value: Value = from_str(r#"["(?i:a)", "b"]"#).unwrap();
+        assert_eq!(super::deserialize(value).unwrap().as_str(), "(?i:a)|b");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(load("[]").is_err());
+        assert!(load(r#"["a", "b("]"#).is_err());
+        assert!(load("1").is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Deserialization compiling patterns on a shared thread pool with a timeout
+//!
+//! Requires the `pool_guarded` feature. Loading thousands of untrusted
+//! patterns under a time bound shouldn't spawn a thread per pattern, so
+//! this module sends each pattern to a global pool of worker threads and
+//! waits for the result up to a timeout. If it doesn't arrive in time,
+//! deserialization fails with an error naming the pattern.
+//!
+//! Works for `Regex` and `Vec<Regex>` fields. All patterns of a vector are
+//! submitted before waiting for any of them, so they compile in parallel.
+//!
+//! The pool is started on first use with [`DEFAULT_THREADS`] workers,
+//! unless [`set_threads`] was called before that. The timeout is
+//! [`DEFAULT_TIMEOUT`] and can be changed at any time with [`set_timeout`].
+//! Both settings are global for the process.
+//!
+//! The timeout is counted from the moment a pattern is submitted, so it
+//! includes waiting in the queue. Compilation itself can't be interrupted:
+//! a pattern which timed out keeps its worker busy until it's compiled, and
+//! the result is then discarded. So a batch of slow patterns can occupy the
+//! whole pool and make the patterns queued after them time out as well.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::pool_guarded")]
+//!     patterns: Vec<Regex>,
+//! }
+//!
+//! serde_regex::pool_guarded::set_threads(2);
+//! serde_regex::pool_guarded::set_timeout(Duration::from_secs(5));
+//! let filter: Filter = serde_json::from_str(r#"{"patterns": ["a+", "b+"]}"#)
+//!     .unwrap();
+//! assert!(filter.patterns[1].is_match("bb"));
+//! ```
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{
+        Arc,
+        Mutex,
+        OnceLock,
+        RwLock,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+use crate::Serde;
+
+/// Number of worker threads used if not configured
+pub const DEFAULT_THREADS: usize = 4;
+/// Time to wait for each pattern if not configured
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+type Job = (String, Sender<Result<Regex, regex::Error>>);
+
+static THREADS: Mutex<usize> = Mutex::new(DEFAULT_THREADS);
+static TIMEOUT: RwLock<Duration> = RwLock::new(DEFAULT_TIMEOUT);
+static POOL: OnceLock<Sender<Job>> = OnceLock::new();
+
+/// Sets the number of worker threads
+///
+/// Zero is treated as one. Only has effect before the pool is started by
+/// the first deserialized pattern. Returns false if the pool is already
+/// running.
+pub fn set_threads(threads: usize) -> bool {
+    let mut configured = THREADS.lock().unwrap_or_else(|e| e.into_inner());
+    if POOL.get().is_some() {
+        return false;
+    }
+    *configured = threads.max(1);
+    true
+}
+
+/// Sets the time to wait for each pattern, applies to new patterns
+pub fn set_timeout(timeout: Duration) {
+    *TIMEOUT.write().unwrap_or_else(|e| e.into_inner()) = timeout;
+}
+
+fn pool() -> &'static Sender<Job> {
+    POOL.get_or_init(|| {
+        // holding the lock makes `set_threads` wait until the pool exists
+        let threads = THREADS.lock().unwrap_or_else(|e| e.into_inner());
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for idx in 0..*threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("serde_regex-{}", idx))
+                .spawn(move || loop {
+                    let job = receiver.lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .recv();
+                    match job {
+                        Ok((pattern, reply)) => {
+                            reply.send(Regex::new(&pattern)).ok();
+                        }
+                        Err(_) => break,
+                    }
+                })
+                .expect("can spawn pattern compilation thread");
+        }
+        sender
+    })
+}
+
+type Pending = mpsc::Receiver<Result<Regex, regex::Error>>;
+
+fn submit(pattern: &str) -> Result<(Pending, Instant), String> {
+    let (reply, result) = mpsc::channel();
+    pool().send((pattern.to_string(), reply))
+        .map_err(|_| "pattern compilation pool is stopped".to_string())?;
+    Ok((result, Instant::now()))
+}
+
+fn wait(pattern: &str, (result, submitted): (Pending, Instant))
+    -> Result<Regex, String>
+{
+    let timeout = *TIMEOUT.read().unwrap_or_else(|e| e.into_inner());
+    let left = timeout.saturating_sub(submitted.elapsed());
+    match result.recv_timeout(left) {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(RecvTimeoutError::Timeout) => Err(format!(
+            "compiling pattern {:?} timed out after {:?}", pattern, timeout)),
+        Err(RecvTimeoutError::Disconnected) => Err(format!(
+            "compiling pattern {:?} failed in a worker thread", pattern)),
+    }
+}
+
+struct VecVisitor;
+
+impl<'de> Visitor<'de> for VecVisitor {
+    type Value = Vec<Regex>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid sequence")
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        // submit all patterns first, so that they compile in parallel
+        let mut pending = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(pattern) = seq.next_element::<Cow<str>>()? {
+            let job = submit(&pattern).map_err(A::Error::custom)?;
+            pending.push((pattern, job));
+        }
+        pending.into_iter()
+            .map(|(pattern, job)| wait(&pattern, job).map_err(A::Error::custom))
+            .collect()
+    }
+}
+
+mod sealed {
+    pub trait PoolGuarded: Sized {
+        fn deserialize<'de, D>(d: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>;
+    }
+}
+
+impl sealed::PoolGuarded for Regex {
+    fn deserialize<'de, D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <Cow<str>>::deserialize(d)?;
+        submit(&s).and_then(|job| wait(&s, job)).map_err(D::Error::custom)
+    }
+}
+
+impl sealed::PoolGuarded for Vec<Regex> {
+    fn deserialize<'de, D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_seq(VecVisitor)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: sealed::PoolGuarded,
+{
+    T::deserialize(d)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    for<'a> Serde<&'a T>: Serialize,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use regex::Regex;
+    use serde_json::{Value, json};
+
+    use super::{DEFAULT_TIMEOUT, deserialize, set_threads, set_timeout};
+
+    #[test]
+    fn test_pool() {
+        // all in one test, as the pool and its settings are global
+        assert!(set_threads(2));
+        let regex: Regex = deserialize(Value::from("a+")).unwrap();
+        assert_eq!(regex.as_str(), "a+");
+        assert!(!set_threads(3));
+        let vec: Vec<Regex> = deserialize(json!(["a", "b", "c"])).unwrap();
+        assert_eq!(vec[2].as_str(), "c");
+        assert!(deserialize::<Regex, _>(Value::from("(")).unwrap_err()
+            .to_string().contains("regex parse error"));
+        assert!(deserialize::<Vec<Regex>, _>(json!(["a", "("])).is_err());
+
+        set_timeout(Duration::ZERO);
+        assert_eq!(deserialize::<Regex, _>(Value::from(r"\w{100}"))
+            .unwrap_err().to_string(),
+            r#"compiling pattern "\\w{100}" timed out after 0ns"#);
+        set_timeout(DEFAULT_TIMEOUT);
+        assert!(deserialize::<Regex, _>(Value::from("b+")).is_ok());
+    }
+}
@@ -0,0 +1,280 @@
+//! Serialization into an indented verbose `(?x)` form
+//!
+//! Generated patterns tend to be long and dense. This module serializes a
+//! pattern in extended mode, split into lines and indented by nesting, so
+//! it can be read and reviewed in exported documentation:
+//!
+//! ```text
+//! (?x)
+//! ^
+//! (?P<user>  # group 1
+//!     [\w\.]+
+//! )
+//! @
+//! (?:
+//!     example\.com
+//! |
+//!     example\.org
+//! )
+//! $
+//! ```
+//!
+//! Every group goes on its own lines with its contents indented, each
+//! alternative of an alternation goes on its own line(s) separated by `|`,
+//! and capture groups are commented with their index. Literal whitespace
+//! and `#`, which are insignificant in extended mode, are written as
+//! escapes like `\x{20}`, and inline `x` flags of the original pattern are
+//! dropped, so the output matches exactly the same text as the original,
+//! with the same capture groups.
+//!
+//! This form is meant for humans: it's several times larger than the
+//! original pattern and comments of the original are not preserved.
+//! Deserialization accepts any pattern, including the verbose one, as
+//! extended mode is handled by the `regex` crate itself. Serializing a
+//! deserialized verbose pattern again yields the same text.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Doc {
+//!     #[serde(with = "serde_regex::pretty_verbose")]
+//!     pattern: Regex,
+//! }
+//!
+//! let doc = Doc { pattern: Regex::new(r"^(\d+) (?:kb|mb)$").unwrap() };
+//! let json = serde_json::to_string(&doc).unwrap();
+//! assert_eq!(json, r#"{"pattern":"(?x)\n^\n(  # group 1\n    \\d+\n)\n\\x{20}\n(?:\n    kb\n|\n    mb\n)\n$"}"#);
+//!
+//! let back: Doc = serde_json::from_str(&json).unwrap();
+//! assert!(back.pattern.is_match("10 kb"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    self,
+    Ast,
+    ClassSet,
+    ClassSetItem,
+    Flag,
+    GroupKind,
+    HexLiteralKind,
+    LiteralKind,
+    print::Printer,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::{remove_flag, visit_mut};
+
+const INDENT: &str = "    ";
+
+/// Escapes literal characters which are insignificant in verbose mode
+fn escape_literal(literal: &mut ast::Literal) {
+    if literal.c.is_whitespace() || literal.c == '#' {
+        literal.kind = LiteralKind::HexBrace(HexLiteralKind::X);
+    }
+}
+
+fn escape_class(set: &mut ClassSet) {
+    match set {
+        ClassSet::Item(item) => escape_class_item(item),
+        ClassSet::BinaryOp(op) => {
+            escape_class(&mut op.lhs);
+            escape_class(&mut op.rhs);
+        }
+    }
+}
+
+fn escape_class_item(item: &mut ClassSetItem) {
+    match item {
+        ClassSetItem::Literal(literal) => escape_literal(literal),
+        ClassSetItem::Range(range) => {
+            escape_literal(&mut range.start);
+            escape_literal(&mut range.end);
+        }
+        ClassSetItem::Bracketed(class) => escape_class(&mut class.kind),
+        ClassSetItem::Union(union) => {
+            for item in &mut union.items {
+                escape_class_item(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn print(ast: &Ast) -> String {
+    let mut result = String::new();
+    Printer::new().print(ast, &mut result)
+        .expect("writing to a string never fails");
+    result
+}
+
+/// Returns true if the node is written on separate lines
+fn is_block(ast: &Ast) -> bool {
+    match ast {
+        Ast::Group(_) | Ast::Alternation(_) => true,
+        Ast::Repetition(rep) => is_block(&rep.ast),
+        _ => false,
+    }
+}
+
+struct Writer {
+    lines: Vec<String>,
+}
+
+impl Writer {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.lines.push(format!("{}{}", INDENT.repeat(depth), text));
+    }
+
+    fn write(&mut self, ast: &Ast, depth: usize) {
+        match ast {
+            Ast::Empty(_) => {}
+            Ast::Alternation(alt) => {
+                for (idx, branch) in alt.asts.iter().enumerate() {
+                    if idx > 0 {
+                        // outdented to the level of the enclosing group
+                        self.line(depth.saturating_sub(1), "|");
+                    }
+                    self.write(branch, depth);
+                }
+            }
+            Ast::Concat(concat) => {
+                let mut inline = String::new();
+                for item in &concat.asts {
+                    if is_block(item) {
+                        if !inline.is_empty() {
+                            self.line(depth, &inline);
+                            inline.clear();
+                        }
+                        self.write(item, depth);
+                    } else {
+                        inline.push_str(&print(item));
+                    }
+                }
+                if !inline.is_empty() {
+                    self.line(depth, &inline);
+                }
+            }
+            Ast::Group(group) => self.group(group, "", depth),
+            Ast::Repetition(rep) if is_block(&rep.ast) => {
+                // print the operator alone by replacing the operand
+                let mut op = (**rep).clone();
+                op.ast = Box::new(Ast::empty(rep.span));
+                let suffix = print(&Ast::repetition(op));
+                match &*rep.ast {
+                    Ast::Group(group) => self.group(group, &suffix, depth),
+                    // the parser only produces repetitions of groups here
+                    _ => self.line(depth, &print(ast)),
+                }
+            }
+            other => self.line(depth, &print(other)),
+        }
+    }
+
+    fn group(&mut self, group: &ast::Group, suffix: &str, depth: usize) {
+        // print the opening alone by replacing the contents
+        let mut open = group.clone();
+        open.ast = Box::new(Ast::empty(group.span));
+        let mut header = print(&Ast::group(open));
+        header.pop();
+        match group.kind {
+            GroupKind::CaptureIndex(index) => {
+                header.push_str(&format!("  # group {}", index));
+            }
+            GroupKind::CaptureName { ref name, .. } => {
+                header.push_str(&format!("  # group {}", name.index));
+            }
+            GroupKind::NonCapturing(_) => {}
+        }
+        self.line(depth, &header);
+        self.write(&group.ast, depth + 1);
+        self.line(depth, &format!("){}", suffix));
+    }
+}
+
+/// Formats the pattern in indented verbose form
+///
+/// Returns `None` if the pattern can't be parsed.
+fn pretty(pattern: &str) -> Option<String> {
+    let mut ast = ast::parse::Parser::new().parse(pattern).ok()?;
+    remove_flag(&mut ast, Flag::IgnoreWhitespace);
+    visit_mut(&mut ast, &mut |node| match node {
+        Ast::Literal(literal) => escape_literal(literal),
+        Ast::ClassBracketed(class) => escape_class(&mut class.kind),
+        _ => {}
+    });
+    let mut writer = Writer { lines: vec!["(?x)".into()] };
+    writer.write(&ast, 0);
+    Some(writer.lines.join("\n"))
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    Regex::new(&s).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match pretty(value.as_str()) {
+        Some(pattern) => serializer.serialize_str(&pattern),
+        None => serializer.serialize_str(value.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use crate::syntax::semantic_eq;
+    use super::{deserialize, pretty};
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(pretty("abc").unwrap(), "(?x)\nabc");
+        assert_eq!(pretty("").unwrap(), "(?x)");
+        assert_eq!(pretty("a|b c").unwrap(), "(?x)\na\n|\nb\\x{20}c");
+        assert_eq!(pretty("x(?P<n>a(b)*)+?y").unwrap(), "(?x)\nx\n\
+            (?P<n>  # group 1\n\
+            \x20   a\n\
+            \x20   (  # group 2\n\
+            \x20       b\n\
+            \x20   )*\n\
+            )+?\n\
+            y");
+        assert_eq!(pretty("[# ]#").unwrap(), "(?x)\n[\\x{23}\\x{20}]\\x{23}");
+        assert_eq!(pretty("(?x) a (?-x) b").unwrap(), "(?x)\na\\x{20}b");
+        assert_eq!(pretty("(?i:a|b){2}").unwrap(),
+            "(?x)\n(?i:\n    a\n|\n    b\n){2}");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for pattern in &[
+            "abc", "", "a|b c", "x(?P<n>a(b)*)+?y", "[# ]#", "(?x) a (?-x) b",
+            r"^(?i)(\d{1,3}\.){3}\d{1,3}$", "a\tb\nc", "(?s:.)|(?:)",
+            r"[a-z&&[^aeiou]]+ # end", r"\p{Greek}\b(?<w>\w+)",
+            "(a|(b|c)|)", "\u{2003}x",
+        ] {
+            let verbose = pretty(pattern).unwrap();
+            let regex = deserialize(Value::from(&verbose[..])).unwrap();
+            assert!(semantic_eq(pattern, regex.as_str()),
+                "{:?} vs {:?}", pattern, verbose);
+            assert_eq!(Regex::new(pattern).unwrap().captures_len(),
+                regex.captures_len());
+            assert_eq!(pretty(&verbose).unwrap(), verbose, "{:?}", pattern);
+        }
+    }
+}
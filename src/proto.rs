@@ -0,0 +1,97 @@
+//! Conversions for protobuf messages which store patterns as strings
+//!
+//! Types generated by `prost` aren't serde-based, so a regex arrives as a
+//! plain `String` field (or `Vec<String>` for `repeated string`). These
+//! functions convert such fields to compiled regexes and back, so that a
+//! generated type only needs a thin accessor. Errors are the ones returned
+//! by `Regex::new`.
+//!
+//! In proto3 an unset `string` field is an empty string, so
+//! [`compile_optional`] treats `""` as `None`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_regex::proto;
+//!
+//! // as generated by prost from:
+//! //     message Route { string path = 1; repeated string hosts = 2; }
+//! #[derive(Clone, PartialEq, Default)]
+//! pub struct Route {
+//!     pub path: String,
+//!     pub hosts: Vec<String>,
+//! }
+//!
+//! impl Route {
+//!     fn path_regex(&self) -> Result<Option<Regex>, regex::Error> {
+//!         proto::compile_optional(&self.path)
+//!     }
+//!     fn hosts_regex(&self) -> Result<Vec<Regex>, regex::Error> {
+//!         proto::compile_repeated(&self.hosts)
+//!     }
+//! }
+//!
+//! let route = Route {
+//!     path: r"^/api/v\d+/".into(),
+//!     hosts: vec![r"\.example\.com$".into()],
+//! };
+//! assert!(route.path_regex().unwrap().unwrap().is_match("/api/v2/users"));
+//! assert_eq!(route.hosts_regex().unwrap().len(), 1);
+//! assert!(Route::default().path_regex().unwrap().is_none());
+//!
+//! let reply = Route {
+//!     path: proto::decompile(&Regex::new("^/health$").unwrap()),
+//!     ..Route::default()
+//! };
+//! assert_eq!(reply.path, "^/health$");
+//! ```
+use regex::Regex;
+
+/// Compiles the pattern stored in a `string` field
+pub fn compile(field: &str) -> Result<Regex, regex::Error> {
+    Regex::new(field)
+}
+
+/// Compiles the pattern, treating an empty (unset) field as `None`
+pub fn compile_optional(field: &str) -> Result<Option<Regex>, regex::Error> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    Regex::new(field).map(Some)
+}
+
+/// Compiles every pattern of a `repeated string` field
+///
+/// Stops at the first invalid pattern.
+pub fn compile_repeated<S: AsRef<str>>(field: &[S])
+    -> Result<Vec<Regex>, regex::Error>
+{
+    field.iter().map(|pattern| Regex::new(pattern.as_ref())).collect()
+}
+
+/// Returns the pattern to store in a `string` field
+pub fn decompile(regex: &Regex) -> String {
+    regex.as_str().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+
+    use super::{compile, compile_optional, compile_repeated, decompile};
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(compile("a+").unwrap().as_str(), "a+");
+        assert!(compile("(").is_err());
+        assert!(compile("").unwrap().is_match("x"));
+        assert!(compile_optional("").unwrap().is_none());
+        assert!(compile_optional("a").unwrap().is_some());
+        assert!(compile_optional("(").is_err());
+        let hosts = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(compile_repeated(&hosts).unwrap()[1].as_str(), "b");
+        assert!(compile_repeated(&["a", "("]).is_err());
+        assert_eq!(decompile(&Regex::new(r"\d").unwrap()), r"\d");
+    }
+}
@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+/// A `RegexSet` which can pick the best of matching patterns
+///
+/// (De)serializes as a sequence of patterns, just like `RegexSet`. Besides
+/// the set, every pattern is also compiled as an individual `Regex`, which
+/// roughly doubles memory used, but makes it possible to rank matches.
+///
+/// ```rust
+/// use serde_regex::RankedRegexSet;
+///
+/// let set: RankedRegexSet = serde_json::from_str(
+///     r#"["\\w+", "\\d+", "[a-z]+\\d+"]"#).unwrap();
+/// assert_eq!(set.best_match("abc123"), Some(0));
+/// assert_eq!(set.best_match("x 123"), Some(1));
+/// assert_eq!(set.best_match("  "), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RankedRegexSet {
+    set: RegexSet,
+    regexes: Vec<Regex>,
+}
+
+impl RankedRegexSet {
+    /// Compiles a set from patterns
+    pub fn new<I, S>(patterns: I) -> Result<RankedRegexSet, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let set = RegexSet::new(patterns)?;
+        let regexes = set.patterns().iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(RankedRegexSet { set, regexes })
+    }
+
+    /// Returns index of the pattern with the longest match in `text`
+    ///
+    /// Length of the leftmost-first match (the one returned by
+    /// `Regex::find`) is used for each pattern matched by the set. If
+    /// several patterns have the same length the first of them wins.
+    pub fn best_match(&self, text: &str) -> Option<usize> {
+        let mut best: Option<(usize, usize)> = None;
+        for idx in self.set.matches(text).iter() {
+            let len = match self.regexes[idx].find(text) {
+                Some(m) => m.end() - m.start(),
+                None => continue,
+            };
+            if best.map(|(_, best_len)| len > best_len).unwrap_or(true) {
+                best = Some((idx, len));
+            }
+        }
+        best.map(|(idx, _)| idx)
+    }
+
+    /// Returns the underlying set
+    pub fn set(&self) -> &RegexSet {
+        &self.set
+    }
+
+    /// Returns individually compiled patterns in order
+    pub fn regexes(&self) -> &[Regex] {
+        &self.regexes
+    }
+}
+
+impl<'de> Deserialize<'de> for RankedRegexSet {
+    fn deserialize<D>(d: D) -> Result<RankedRegexSet, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let patterns = <Vec<Cow<str>>>::deserialize(d)?;
+        RankedRegexSet::new(patterns).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for RankedRegexSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.set.patterns().serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    use super::RankedRegexSet;
+
+    #[test]
+    fn test_best_match() {
+        let json = r#"["a+","b+","a+b"]"#;
+        let set: RankedRegexSet = from_str(json).unwrap();
+        assert_eq!(set.best_match("aab"), Some(2));
+        assert_eq!(set.best_match("bbb a"), Some(1));
+        // tie between "b+" and "a+b", first one wins
+        assert_eq!(set.best_match("bb ab"), Some(1));
+        assert_eq!(set.best_match("ccc"), None);
+        assert_eq!(to_string(&set).unwrap(), json);
+    }
+}
@@ -0,0 +1,145 @@
+//! (De)serialization of references to named patterns
+//!
+//! Large rule sets often repeat the same patterns across many files. This
+//! module lets a value refer to a pattern registered by the application:
+//!
+//! ```json
+//! { "from": "@email", "subject": "^\\[urgent\\]" }
+//! ```
+//!
+//! A string starting with `@` is a reference: the rest is looked up in the
+//! registry and the registered pattern is compiled. An unknown name is a
+//! deserialization error. Any other string is compiled as a pattern, and
+//! `@@` stands for a pattern starting with a literal `@`.
+//!
+//! On serialization the reference is emitted again if the pattern is still
+//! registered (if several names share the pattern, the smallest name is
+//! used), otherwise the pattern itself is written.
+//!
+//! The registry is global and empty by default. Populate it with
+//! [`set_registry`] before deserializing, which replaces the whole
+//! registry at once. It's protected by a read-write lock, so it's safe to
+//! deserialize on many threads while the registry is being replaced: each
+//! value is resolved against either the old or the new registry.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::registry")]
+//!     from: Regex,
+//! }
+//!
+//! let mut registry = HashMap::new();
+//! registry.insert("email".to_string(), r"^[^@\s]+@[^@\s]+$".to_string());
+//! serde_regex::registry::set_registry(registry);
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"from": "@email"}"#).unwrap();
+//! assert!(rule.from.is_match("user@example.com"));
+//! assert_eq!(serde_json::to_string(&rule).unwrap(), r#"{"from":"@email"}"#);
+//! assert!(serde_json::from_str::<Rule>(r#"{"from": "@phone"}"#).is_err());
+//! ```
+use std::{borrow::Cow, collections::HashMap, sync::RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+struct Registry {
+    patterns: HashMap<String, String>,
+    /// The smallest name of every registered pattern
+    names: HashMap<String, String>,
+}
+
+static REGISTRY: RwLock<Option<Registry>> = RwLock::new(None);
+
+/// Replaces the global registry of named patterns
+pub fn set_registry(patterns: HashMap<String, String>) {
+    let mut names = HashMap::<String, String>::with_capacity(patterns.len());
+    for (name, pattern) in &patterns {
+        let smallest = names.entry(pattern.clone())
+            .or_insert_with(|| name.clone());
+        if name < smallest {
+            *smallest = name.clone();
+        }
+    }
+    *REGISTRY.write().unwrap_or_else(|e| e.into_inner()) =
+        Some(Registry { patterns, names });
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    match s.strip_prefix('@') {
+        Some(name) if !name.starts_with('@') => {
+            let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+            let pattern = registry.as_ref().and_then(|r| r.patterns.get(name))
+                .ok_or_else(|| D::Error::custom(format_args!(
+                    "unknown pattern name {:?}", name)))?;
+            Regex::new(pattern)
+        }
+        Some(escaped) => Regex::new(escaped),
+        None => Regex::new(&s),
+    }.map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    let name = registry.as_ref().and_then(|r| r.names.get(value.as_str()));
+    match name {
+        Some(name) => serializer.collect_str(&format_args!("@{}", name)),
+        None if value.as_str().starts_with('@') => {
+            serializer.collect_str(&format_args!("@{}", value.as_str()))
+        }
+        None => serializer.serialize_str(value.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize, set_registry};
+
+    fn to_value(pattern: &str) -> Value {
+        serialize(&Regex::new(pattern).unwrap(), serde_json::value::Serializer)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_registry() {
+        // all in one test, as the registry is global
+        let mut registry = HashMap::new();
+        registry.insert("b".to_string(), "x+".to_string());
+        registry.insert("a".to_string(), "x+".to_string());
+        set_registry(registry);
+
+        assert_eq!(deserialize(Value::from("@b")).unwrap().as_str(), "x+");
+        assert_eq!(deserialize(Value::from("@@b")).unwrap().as_str(), "@b");
+        assert_eq!(deserialize(Value::from("y")).unwrap().as_str(), "y");
+        assert_eq!(deserialize(Value::from("@c")).unwrap_err().to_string(),
+            r#"unknown pattern name "c""#);
+        assert_eq!(to_value("x+"), "@a");
+        assert_eq!(to_value("@b"), "@@b");
+        assert_eq!(to_value("y"), "y");
+
+        set_registry(HashMap::new());
+        assert!(deserialize(Value::from("@b")).is_err());
+        assert_eq!(to_value("x+"), "x+");
+    }
+}
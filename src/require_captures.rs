@@ -0,0 +1,94 @@
+//! Deserialization requiring a minimum number of capture groups
+//!
+//! Code which reads capture groups by index, like `caps[2]`, panics at
+//! match time if the pattern has fewer groups. This module fails at load
+//! time instead, if `Regex::captures_len()` is less than the requirement.
+//!
+//! The count includes the implicit group `0` for the whole match, the same
+//! way `captures_len` does. So a pattern without any parentheses has one
+//! group, `(\w+)=(\d+)` has three, and to be able to use `caps[2]` the
+//! minimum is `3`. Named groups count the same as numbered ones, while
+//! non-capturing groups like `(?:...)` don't count.
+//!
+//! The plain `deserialize` requires [`DEFAULT_MIN_CAPTURES`], i.e. at least
+//! one explicit group. Use `deserialize_min` to specify another minimum:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Setting {
+//!     #[serde(
+//!         serialize_with = "serde_regex::require_captures::serialize",
+//!         deserialize_with = "serde_regex::require_captures::deserialize_min::<_, 3>",
+//!     )]
+//!     pair: Regex,
+//! }
+//!
+//! let setting: Setting = serde_json::from_str(
+//!     r#"{"pair": "(\\w+)=(\\d+)"}"#).unwrap();
+//! let caps = setting.pair.captures("port=80").unwrap();
+//! assert_eq!(&caps[2], "80");
+//! assert!(serde_json::from_str::<Setting>(r#"{"pair": "(\\w+)=\\d+"}"#)
+//!     .is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Number of groups required by default, including the whole match
+pub const DEFAULT_MIN_CAPTURES: usize = 2;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_min::<D, DEFAULT_MIN_CAPTURES>(d)
+}
+
+/// Deserialize function requiring at least `MIN` groups
+pub fn deserialize_min<'de, D, const MIN: usize>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    if regex.captures_len() < MIN {
+        return Err(D::Error::custom(format_args!(
+            "pattern {:?} has {} capture groups (including the whole match), \
+             at least {} required",
+            s, regex.captures_len(), MIN)));
+    }
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{deserialize, deserialize_min};
+
+    #[test]
+    fn test_min() {
+        assert!(deserialize(Value::from("(a)")).is_ok());
+        assert!(deserialize(Value::from("(?:a)")).is_err());
+        assert!(deserialize_min::<_, 1>(Value::from("a")).is_ok());
+        assert!(deserialize_min::<_, 3>(Value::from("(a)(?P<b>b)")).is_ok());
+        assert_eq!(deserialize_min::<_, 3>(Value::from("(a)b"))
+            .unwrap_err().to_string(),
+            "pattern \"(a)b\" has 2 capture groups (including the whole \
+             match), at least 3 required");
+    }
+}
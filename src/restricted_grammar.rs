@@ -0,0 +1,240 @@
+//! Deserialization of patterns limited to a simple subset of the syntax
+//!
+//! User-facing rule editors often expose a simplified pattern language to
+//! untrusted authors. This module parses the pattern with `regex-syntax`
+//! and rejects it if it uses any construct outside of the allow-list, with
+//! an error naming the offending part of the pattern.
+//!
+//! By default only the following constructs are allowed:
+//!
+//! * literals, including escapes like `\.` or `\n`;
+//! * character classes: `.`, `[a-z]`, `\d`, `\w`, `\s`, `\pL`, etc.;
+//! * repetition with `*`, `+` and `?` (and their lazy forms like `*?`);
+//! * anchors `^`, `$`, `\A` and `\z`.
+//!
+//! Everything else is an error: groups (capturing or not), alternation,
+//! counted repetition like `{2,5}`, inline flags like `(?i)` and word
+//! boundaries like `\b`. Each of these can be allowed with a [`Grammar`]
+//! builder, which deserializes with the customized allow-list as a
+//! `DeserializeSeed`, e.g. in a `deserialize_with` function:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde::de::{DeserializeSeed, Deserializer};
+//! use serde_derive::{Serialize, Deserialize};
+//! use serde_regex::restricted_grammar::Grammar;
+//!
+//! fn keyword<'de, D: Deserializer<'de>>(d: D) -> Result<Regex, D::Error> {
+//!     Grammar::new().allow_alternation(true).deserialize(d)
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::restricted_grammar")]
+//!     path: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::restricted_grammar::serialize",
+//!         deserialize_with = "keyword",
+//!     )]
+//!     keyword: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{
+//!     "path": "^/users/\\d+/?$",
+//!     "keyword": "spam|scam"
+//! }"#).unwrap();
+//! assert!(rule.path.is_match("/users/12/"));
+//!
+//! let err = serde_json::from_str::<Rule>(r#"{
+//!     "path": "^/(users|groups)/",
+//!     "keyword": "spam"
+//! }"#).err().unwrap();
+//! assert!(err.to_string()
+//!     .starts_with("group `(users|groups)` is not allowed"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{Ast, AssertionKind, RepetitionKind, parse::Parser};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+    de::{DeserializeSeed, Error},
+};
+
+use crate::syntax::visit;
+
+/// Builder of the allow-list of pattern constructs
+///
+/// [`Grammar::new`] allows only the constructs listed in the module docs,
+/// and each method allows one more kind of construct. Use the grammar to
+/// check a pattern with [`compile`](Grammar::compile) or as a
+/// `DeserializeSeed` producing a `Regex`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Grammar {
+    groups: bool,
+    alternation: bool,
+    counted_repetition: bool,
+    inline_flags: bool,
+    word_boundaries: bool,
+}
+
+impl Grammar {
+    /// Creates a grammar with the default allow-list
+    pub fn new() -> Grammar {
+        Grammar::default()
+    }
+
+    /// Allows groups: `(a)`, `(?:a)`, `(?P<name>a)` and `(?i:a)`
+    pub fn allow_groups(mut self, allow: bool) -> Grammar {
+        self.groups = allow;
+        self
+    }
+
+    /// Allows alternation like `a|b`
+    pub fn allow_alternation(mut self, allow: bool) -> Grammar {
+        self.alternation = allow;
+        self
+    }
+
+    /// Allows counted repetition like `a{3}` or `a{2,5}`
+    pub fn allow_counted_repetition(mut self, allow: bool) -> Grammar {
+        self.counted_repetition = allow;
+        self
+    }
+
+    /// Allows inline flags like `(?i)`
+    ///
+    /// Flags in a group like `(?i:a)` need [`allow_groups`] too.
+    ///
+    /// [`allow_groups`]: Grammar::allow_groups
+    pub fn allow_inline_flags(mut self, allow: bool) -> Grammar {
+        self.inline_flags = allow;
+        self
+    }
+
+    /// Allows word boundary assertions like `\b`, `\B` or `\<`
+    pub fn allow_word_boundaries(mut self, allow: bool) -> Grammar {
+        self.word_boundaries = allow;
+        self
+    }
+
+    fn forbidden(&self, ast: &Ast) -> Option<&'static str> {
+        match ast {
+            Ast::Group(_) if !self.groups => Some("group"),
+            Ast::Alternation(_) if !self.alternation => Some("alternation"),
+            Ast::Flags(_) if !self.inline_flags => Some("inline flags"),
+            Ast::Repetition(rep) => match rep.op.kind {
+                RepetitionKind::Range(_) if !self.counted_repetition => {
+                    Some("counted repetition")
+                }
+                _ => None,
+            },
+            Ast::Assertion(assertion) => match assertion.kind {
+                AssertionKind::StartLine
+                | AssertionKind::EndLine
+                | AssertionKind::StartText
+                | AssertionKind::EndText => None,
+                _ if !self.word_boundaries => Some("word boundary"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Checks that the pattern only uses allowed constructs
+    pub fn check(&self, pattern: &str) -> Result<(), String> {
+        let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+        let mut error = None;
+        visit(&ast, &mut |node| {
+            if error.is_none() {
+                if let Some(what) = self.forbidden(node) {
+                    let span = node.span();
+                    error = Some(format!("{} `{}` is not allowed", what,
+                        &pattern[span.start.offset..span.end.offset]));
+                }
+            }
+        });
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Checks the pattern and compiles it
+    pub fn compile(&self, pattern: &str) -> Result<Regex, String> {
+        self.check(pattern)?;
+        Regex::new(pattern).map_err(|e| e.to_string())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Grammar {
+    type Value = Regex;
+
+    fn deserialize<D>(self, d: D) -> Result<Regex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <Cow<str>>::deserialize(d)?;
+        self.compile(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Grammar::new().deserialize(d)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{Grammar, deserialize};
+
+    #[test]
+    fn test_default() {
+        let grammar = Grammar::new();
+        for pattern in &[r"^a.b*c+d?$", r"[a-z]\d\w+\pL*?", r"\Aa\.\n\z", ""] {
+            assert_eq!(grammar.check(pattern), Ok(()), "{:?}", pattern);
+        }
+        let error = |pattern| grammar.check(pattern).unwrap_err();
+        assert_eq!(error("a(b)"), "group `(b)` is not allowed");
+        assert_eq!(error("(?:a)"), "group `(?:a)` is not allowed");
+        assert_eq!(error("ab|c"), "alternation `ab|c` is not allowed");
+        assert_eq!(error("a{2,}"), "counted repetition `a{2,}` is not allowed");
+        assert_eq!(error("(?i)a"), "inline flags `(?i)` is not allowed");
+        assert_eq!(error(r"\bx"), r"word boundary `\b` is not allowed");
+        assert!(error("(").contains("unclosed group"));
+    }
+
+    #[test]
+    fn test_builder() {
+        let grammar = Grammar::new()
+            .allow_groups(true)
+            .allow_alternation(true)
+            .allow_counted_repetition(true)
+            .allow_inline_flags(true)
+            .allow_word_boundaries(true);
+        assert!(grammar.check(r"(?i)\b(a|b{2})(?s:.)\B").is_ok());
+        let flags_only = Grammar::new().allow_inline_flags(true);
+        assert!(flags_only.check("(?i)a").is_ok());
+        assert!(flags_only.check("(?i:a)").is_err());
+        assert!(grammar.allow_groups(false).check("(a)").is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert_eq!(deserialize(Value::from("^a+$")).unwrap().as_str(), "^a+$");
+        assert!(deserialize(Value::from("a|b")).is_err());
+        assert!(deserialize(Value::from("[")).is_err());
+    }
+}
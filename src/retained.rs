@@ -0,0 +1,161 @@
+use std::{fmt, ops::Deref};
+
+use regex::{Regex, RegexBuilder};
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, Visitor},
+};
+
+/// A regex which remembers whether its pattern was borrowed from the input
+///
+/// This is a diagnostic type for profiling allocations: the compiled regex
+/// is the same as with `Serde<Regex>`, but [`is_borrowed`] tells whether the
+/// deserializer handed out a string borrowed from the input
+/// (`visit_borrowed_str`) or one it had to buffer or allocate itself
+/// (`visit_str` or `visit_string`).
+///
+/// Most formats deliver owned or buffered strings, so expect `false` in
+/// most cases: e.g. `serde_json::from_value` and `serde_json::from_reader`
+/// never borrow, and `serde_json::from_str` borrows only strings without
+/// escapes, because an escape has to be decoded into a new buffer.
+///
+/// ```rust
+/// use serde_regex::RetainedRegex;
+///
+/// let plain: RetainedRegex = serde_json::from_str(r#""a+""#).unwrap();
+/// assert!(plain.is_borrowed());
+/// let escaped: RetainedRegex = serde_json::from_str(r#""\\d+""#).unwrap();
+/// assert!(!escaped.is_borrowed());
+/// assert!(escaped.is_match("12"));
+/// ```
+///
+/// [`is_borrowed`]: RetainedRegex::is_borrowed
+#[derive(Debug, Clone)]
+pub struct RetainedRegex {
+    regex: Regex,
+    borrowed: bool,
+}
+
+impl RetainedRegex {
+    /// Returns true if the pattern was borrowed from the input
+    pub fn is_borrowed(&self) -> bool {
+        self.borrowed
+    }
+
+    /// Returns the compiled regex
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Unwraps the compiled regex
+    pub fn into_inner(self) -> Regex {
+        self.regex
+    }
+
+    /// Returns a builder for the same pattern, to compile it differently
+    ///
+    /// Flags such as case insensitivity are kept, since they are part of
+    /// the pattern as inline groups like `(?i)`. Settings which exist only
+    /// on `RegexBuilder`, like `size_limit`, aren't retained by `Regex`, so
+    /// the builder starts with defaults for those:
+    ///
+    /// ```rust
+    /// use serde_regex::RetainedRegex;
+    ///
+    /// let re: RetainedRegex = serde_json::from_str(r#""(?i)a{1000}""#)
+    ///     .unwrap();
+    /// assert!(re.to_builder().size_limit(100).build().is_err());
+    /// let bigger = re.to_builder().size_limit(1 << 30).build().unwrap();
+    /// assert!(bigger.is_match(&"A".repeat(1000)));
+    /// ```
+    pub fn to_builder(&self) -> RegexBuilder {
+        RegexBuilder::new(self.regex.as_str())
+    }
+}
+
+struct RetainedVisitor;
+
+impl<'de> Visitor<'de> for RetainedVisitor {
+    type Value = RetainedRegex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid regular expression")
+    }
+
+    fn visit_borrowed_str<E>(self, value: &'de str)
+        -> Result<RetainedRegex, E>
+    where
+        E: Error,
+    {
+        let regex = Regex::new(value).map_err(E::custom)?;
+        Ok(RetainedRegex { regex, borrowed: true })
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<RetainedRegex, E>
+    where
+        E: Error,
+    {
+        let regex = Regex::new(value).map_err(E::custom)?;
+        Ok(RetainedRegex { regex, borrowed: false })
+    }
+}
+
+impl<'de> Deserialize<'de> for RetainedRegex {
+    fn deserialize<D>(d: D) -> Result<RetainedRegex, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_str(RetainedVisitor)
+    }
+}
+
+impl Serialize for RetainedRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.regex.as_str().serialize(serializer)
+    }
+}
+
+impl Deref for RetainedRegex {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, from_value, json, to_string};
+
+    use super::RetainedRegex;
+
+    #[test]
+    fn test_borrowed() {
+        let re: RetainedRegex = from_str(r#""a+""#).unwrap();
+        assert!(re.is_borrowed());
+        assert_eq!(to_string(&re).unwrap(), r#""a+""#);
+        let re: RetainedRegex = from_str(r#""\\d+""#).unwrap();
+        assert!(!re.is_borrowed());
+        assert_eq!(re.into_inner().as_str(), r"\d+");
+        let re: RetainedRegex = from_value(json!("a+")).unwrap();
+        assert!(!re.is_borrowed());
+        let re: RetainedRegex = serde_yaml::from_str("a+").unwrap();
+        assert!(re.is_borrowed());
+        assert!(from_str::<RetainedRegex>(r#""(""#).is_err());
+    }
+
+    #[test]
+    fn test_to_builder() {
+        let re: RetainedRegex = from_str(r#""(?s)a.b""#).unwrap();
+        let rebuilt = re.to_builder().case_insensitive(true).build().unwrap();
+        assert_eq!(rebuilt.as_str(), re.as_str());
+        assert!(rebuilt.is_match("A\nB"));
+        assert!(!re.is_match("A\nB"));
+    }
+}
@@ -0,0 +1,110 @@
+//! `DeserializeSeed` implementations for custom `Deserialize` impls
+//!
+//! A seed is a stateful deserializer: the state is passed in by the caller
+//! of `next_value_seed` / `next_element_seed` instead of being created from
+//! scratch by `Deserialize::deserialize`. This is useful when writing your
+//! own `Deserialize` impl (or `Visitor`) over a nested config, to pass
+//! context down to the pattern.
+//!
+//! [`RegexWithPath`] carries the config path of the value being read, e.g.
+//! `rules.spam`, and returns it together with the compiled regex, so the
+//! caller can record where each pattern came from. The path is also
+//! prepended to the compile error.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::fmt;
+//!
+//! use regex::Regex;
+//! use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+//! use serde_regex::seed::RegexWithPath;
+//!
+//! /// Rules with the config path of every pattern
+//! struct Rules(Vec<(String, Regex)>);
+//!
+//! struct RulesVisitor;
+//!
+//! impl<'de> Visitor<'de> for RulesVisitor {
+//!     type Value = Rules;
+//!
+//!     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//!         f.write_str("map of rules")
+//!     }
+//!     fn visit_map<A: MapAccess<'de>>(self, mut map: A)
+//!         -> Result<Rules, A::Error>
+//!     {
+//!         let mut rules = Vec::new();
+//!         while let Some(name) = map.next_key::<String>()? {
+//!             let path = format!("rules.{}", name);
+//!             rules.push(map.next_value_seed(RegexWithPath::new(path))?);
+//!         }
+//!         Ok(Rules(rules))
+//!     }
+//! }
+//!
+//! impl<'de> Deserialize<'de> for Rules {
+//!     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Rules, D::Error> {
+//!         d.deserialize_map(RulesVisitor)
+//!     }
+//! }
+//!
+//! let rules: Rules = serde_json::from_str(r#"{"spam": "(?i)viagra"}"#)
+//!     .unwrap();
+//! assert_eq!(rules.0[0].0, "rules.spam");
+//! let err = serde_json::from_str::<Rules>(r#"{"ads": "("}"#).err().unwrap();
+//! assert!(err.to_string().starts_with("rules.ads: regex parse error"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::de::{Deserialize, DeserializeSeed, Deserializer, Error};
+
+/// A seed producing a regex together with the config path it came from
+#[derive(Debug, Clone)]
+pub struct RegexWithPath {
+    path: String,
+}
+
+impl RegexWithPath {
+    /// Creates a seed for the value at `path`
+    pub fn new<P: Into<String>>(path: P) -> RegexWithPath {
+        RegexWithPath { path: path.into() }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for RegexWithPath {
+    type Value = (String, Regex);
+
+    fn deserialize<D>(self, d: D) -> Result<(String, Regex), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <Cow<str>>::deserialize(d)?;
+        match Regex::new(&s) {
+            Ok(regex) => Ok((self.path, regex)),
+            Err(err) => Err(D::Error::custom(format_args!(
+                "{}: {}", self.path, err))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::de::DeserializeSeed;
+    use serde_json::Value;
+
+    use super::RegexWithPath;
+
+    #[test]
+    fn test_seed() {
+        let (path, regex) = RegexWithPath::new("a.b")
+            .deserialize(Value::from("x+")).unwrap();
+        assert_eq!(path, "a.b");
+        assert_eq!(regex.as_str(), "x+");
+        let err = RegexWithPath::new("a.c")
+            .deserialize(Value::from("(")).unwrap_err();
+        assert!(err.to_string().starts_with("a.c: regex parse error"));
+        assert!(RegexWithPath::new("a").deserialize(Value::from(1)).is_err());
+    }
+}
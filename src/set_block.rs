@@ -0,0 +1,113 @@
+//! (De)serialization of a `RegexSet` as a block of lines with comments
+//!
+//! The set is written as a single string with one pattern per line, which
+//! is convenient for rule files and multi-line strings in YAML or TOML:
+//!
+//! ```yaml
+//! rules: |
+//!   # bots
+//!   (?i)bot\b
+//!   (?i)crawler
+//!
+//!   # scanners
+//!   ^/wp-admin
+//! ```
+//!
+//! When deserializing, blank lines (empty or whitespace only) and comment
+//! lines, whose first non-whitespace character is `#`, are skipped. Every
+//! other line is a pattern, taken as is, including leading and trailing
+//! whitespace (only the `\r` of a `\r\n` line ending is removed). Write
+//! `\#` for a pattern which starts with a literal `#`.
+//!
+//! Serialization writes the patterns one per line, without comments or
+//! blank lines: comments are dropped on a round trip. A set which can't
+//! be represented this way is a serialization error, i.e. if it contains
+//! an empty or whitespace-only pattern, a pattern starting with `#` (after
+//! optional whitespace), or a pattern with a line break.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::RegexSet;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::set_block")]
+//!     rules: RegexSet,
+//! }
+//!
+//! let filter: Filter = serde_yaml::from_str(
+//!     "rules: |\n  # bots\n  (?i)bot\\b\n\n  ^/wp-admin\n").unwrap();
+//! assert_eq!(filter.rules.patterns(), [r"(?i)bot\b", "^/wp-admin"]);
+//! assert_eq!(serde_json::to_string(&filter).unwrap(),
+//!     r#"{"rules":"(?i)bot\\b\n^/wp-admin"}"#);
+//! ```
+use std::borrow::Cow;
+
+use regex::RegexSet;
+use serde::{Deserialize, Deserializer, Serializer, de, ser};
+
+fn is_skipped(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<RegexSet, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let patterns = s.lines()
+        .filter(|line| !is_skipped(line));
+    RegexSet::new(patterns).map_err(de::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &RegexSet, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    for pattern in value.patterns() {
+        if is_skipped(pattern) || pattern.contains(['\n', '\r']) {
+            return Err(ser::Error::custom(format_args!(
+                "pattern {:?} can't be written as a line", pattern)));
+        }
+    }
+    serializer.serialize_str(&value.patterns().join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::RegexSet;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize};
+
+    fn to_value(patterns: &[&str]) -> Result<Value, serde_json::Error> {
+        serialize(&RegexSet::new(patterns).unwrap(),
+            serde_json::value::Serializer)
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let set = deserialize(Value::from(
+            "# comment\r\na\r\n\n  \t\n  # indented\n\\#b\n c \n")).unwrap();
+        assert_eq!(set.patterns(), ["a", r"\#b", " c "]);
+        assert_eq!(set.matches("#b").into_iter().collect::<Vec<_>>(), [1]);
+        assert!(deserialize(Value::from("# only comments\n")).unwrap()
+            .is_empty());
+        assert!(deserialize(Value::from("a\n(")).is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(to_value(&["a", r"\#b", " c"]).unwrap(), "a\n\\#b\n c");
+        assert_eq!(to_value(&[]).unwrap(), "");
+        assert!(to_value(&["#a"]).is_err());
+        assert!(to_value(&[" "]).is_err());
+        assert!(to_value(&[""]).is_err());
+        assert!(to_value(&["a\nb"]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Deserialization which rejects patterns with a large estimated size
+//!
+//! `RegexBuilder::size_limit` bounds memory used while compiling, but it's
+//! expressed in bytes of internal structures that change between versions
+//! of the `regex` crate. This module compiles the pattern normally and
+//! then rejects it if its estimated size is above the threshold, with an
+//! error naming both numbers, which is easier to report and to quarantine.
+//!
+//! The `regex` crate doesn't expose the size of a compiled program, so the
+//! size is approximated from the syntax tree parsed by `regex-syntax`:
+//!
+//! * every node counts as one, including groups, concatenations and each
+//!   item of a bracketed class;
+//! * a counted repetition like `x{n}`, `x{n,}` or `x{n,m}` multiplies the
+//!   size of its operand by `n` (or `m` if given), because the compiler
+//!   unrolls it; `*`, `+` and `?` don't multiply.
+//!
+//! So `abc` is 4, `[a-z]+` is 3 and `(ab){100}` is 401. Unicode classes
+//! like `\w` still count as one, even though they compile to much larger
+//! automata, so this is a guard against unreasonable patterns rather than
+//! a precise memory limit.
+//!
+//! The default limit is [`DEFAULT_MAX_SIZE`]. Use `deserialize_max` with an
+//! explicit limit to override it:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(
+//!         serialize_with = "serde_regex::size_guarded::serialize",
+//!         deserialize_with = "serde_regex::size_guarded::deserialize_max::<_, 100>",
+//!     )]
+//!     pattern: Regex,
+//! }
+//!
+//! assert!(serde_json::from_str::<Config>(r#"{"pattern": "a{50}"}"#).is_ok());
+//! assert!(serde_json::from_str::<Config>(r#"{"pattern": "(ab){50}"}"#)
+//!     .is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{Ast, RepetitionKind, RepetitionRange, parse::Parser};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::visit_class;
+
+/// Estimated size allowed by default
+pub const DEFAULT_MAX_SIZE: usize = 10_000;
+
+pub(crate) fn size(ast: &Ast) -> usize {
+    match ast {
+        Ast::Group(group) => size(&group.ast).saturating_add(1),
+        Ast::Repetition(rep) => {
+            let times = match rep.op.kind {
+                RepetitionKind::Range(RepetitionRange::Exactly(n))
+                | RepetitionKind::Range(RepetitionRange::AtLeast(n))
+                | RepetitionKind::Range(RepetitionRange::Bounded(_, n))
+                => n.max(1) as usize,
+                _ => 1,
+            };
+            size(&rep.ast).saturating_mul(times).saturating_add(1)
+        }
+        Ast::Alternation(alt) => alt.asts.iter()
+            .fold(1, |total, ast| total.saturating_add(size(ast))),
+        Ast::Concat(concat) => concat.asts.iter()
+            .fold(1, |total, ast| total.saturating_add(size(ast))),
+        Ast::ClassBracketed(class) => {
+            let mut total = 1usize;
+            visit_class(&class.kind, &mut |_| total = total.saturating_add(1));
+            total
+        }
+        _ => 1,
+    }
+}
+
+fn compile<'de, D>(d: D, max: usize) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    let ast = Parser::new().parse(&s).map_err(D::Error::custom)?;
+    let estimate = size(&ast);
+    if estimate > max {
+        return Err(D::Error::custom(format_args!(
+            "pattern has estimated size {}, at most {} allowed",
+            estimate, max)));
+    }
+    Ok(regex)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, DEFAULT_MAX_SIZE)
+}
+
+/// Deserialize function allowing at most `MAX` estimated size
+pub fn deserialize_max<'de, D, const MAX: usize>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    compile(d, MAX)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex_syntax::ast::parse::Parser;
+    use serde_json::Value;
+
+    use super::{deserialize_max, size};
+
+    fn estimate(pattern: &str) -> usize {
+        size(&Parser::new().parse(pattern).unwrap())
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(estimate("a"), 1);
+        assert_eq!(estimate("abc"), 4);
+        assert_eq!(estimate("[a-z]+"), 3);
+        assert_eq!(estimate("(ab){100}"), 401);
+        assert_eq!(estimate("a{2,}|b{0}"), 6);
+        assert_eq!(estimate(r"\w*"), 2);
+    }
+
+    #[test]
+    fn test_limit() {
+        assert!(deserialize_max::<_, 4>(Value::from("abc")).is_ok());
+        assert_eq!(deserialize_max::<_, 3>(Value::from("abc"))
+            .unwrap_err().to_string(),
+            "pattern has estimated size 4, at most 3 allowed");
+        // compile errors are reported as usual
+        assert!(deserialize_max::<_, 3>(Value::from("a{5000000}"))
+            .unwrap_err().to_string().contains("size limit"));
+    }
+}
@@ -0,0 +1,114 @@
+//! (De)serialization of literal search strings with an opt-in regex prefix
+//!
+//! Meant for search boxes: by default the input is matched literally (it's
+//! passed through `regex::escape`, like in [`literal`](crate::literal)),
+//! but an input starting with [`PREFIX`] (`re:`) is compiled as a regex,
+//! without the prefix:
+//!
+//! * `1+1` matches the text `1+1`;
+//! * `re:\d+` matches any number.
+//!
+//! To search literally for text which itself starts with `re:`, use the
+//! prefix and escape the text: `re:re:x\.y` matches the text `re:x.y`
+//! (a regex without special characters, like `re:`, matches itself).
+//!
+//! On serialization, a regex which matches a plain literal is written as
+//! that literal, unless the literal starts with `re:`. Anything else is
+//! written with the prefix. Values round-trip to regexes that match the
+//! same text, although the pattern may be spelled differently: e.g.
+//! `re:a\.b` is written back as `a.b`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Search {
+//!     #[serde(with = "serde_regex::smart_literal")]
+//!     term: Regex,
+//! }
+//!
+//! let search: Search = serde_json::from_str(r#"{"term": "1+1"}"#).unwrap();
+//! assert!(search.term.is_match("is 1+1 two?"));
+//! assert_eq!(serde_json::to_string(&search).unwrap(), r#"{"term":"1+1"}"#);
+//!
+//! let search: Search = serde_json::from_str(r#"{"term": "re:^\\d+$"}"#)
+//!     .unwrap();
+//! assert!(search.term.is_match("42"));
+//! assert_eq!(serde_json::to_string(&search).unwrap(),
+//!     r#"{"term":"re:^\\d+$"}"#);
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::as_literal;
+
+/// Prefix marking the input as a regex
+pub const PREFIX: &str = "re:";
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    match s.strip_prefix(PREFIX) {
+        Some(pattern) => Regex::new(pattern),
+        None => Regex::new(&regex::escape(&s)),
+    }.map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match as_literal(value.as_str()) {
+        Some(literal) if !literal.starts_with(PREFIX) => {
+            serializer.serialize_str(&literal)
+        }
+        _ => serializer.collect_str(
+            &format_args!("{}{}", PREFIX, value.as_str())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::Value;
+
+    use super::{deserialize, serialize};
+
+    fn round_trip(input: &str) -> (String, Value) {
+        let regex = deserialize(Value::from(input)).unwrap();
+        let output = serialize(&regex, serde_json::value::Serializer).unwrap();
+        (regex.as_str().to_string(), output)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(round_trip("a.b"), (r"a\.b".into(), "a.b".into()));
+        assert_eq!(round_trip(r"re:a\.b"), (r"a\.b".into(), "a.b".into()));
+        assert_eq!(round_trip("re:a+"), ("a+".into(), "re:a+".into()));
+        assert_eq!(round_trip(r"re:re:x\.y"),
+            (r"re:x\.y".into(), r"re:re:x\.y".into()));
+        assert_eq!(round_trip(""), ("".into(), "".into()));
+        assert_eq!(round_trip("re:"), ("".into(), "".into()));
+        assert!(deserialize(Value::from("re:(")).is_err());
+        assert!(deserialize(Value::from("(")).is_ok());
+    }
+
+    #[test]
+    fn test_literal_prefix() {
+        let regex = deserialize(Value::from(r"re:re:x\.y")).unwrap();
+        assert!(regex.is_match("re:x.y"));
+        assert!(!regex.is_match("x.y"));
+        let regex = Regex::new("(?i)re:a").unwrap();
+        assert_eq!(serialize(&regex, serde_json::value::Serializer).unwrap(),
+            "re:(?i)re:a");
+    }
+}
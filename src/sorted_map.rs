@@ -0,0 +1,105 @@
+//! Serialization of regex maps with entries sorted by key
+//!
+//! Iteration order of a `HashMap` is random, so serializing it with the
+//! default module gives a different key order on every run, which hurts
+//! diffing, content hashing and reproducible builds. This module sorts
+//! entries by key (which must be `Ord`) before emitting them, while the
+//! field keeps its `HashMap` type.
+//!
+//! Only the serialization order is affected: the in-memory map is neither
+//! changed nor copied (entries are sorted by reference), and
+//! deserialization works exactly like the default one. Works for both
+//! `Regex` and `bytes::Regex` values.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Routes {
+//!     #[serde(with = "serde_regex::sorted_map")]
+//!     routes: HashMap<String, Regex>,
+//! }
+//!
+//! let routes: Routes = serde_json::from_str(r#"{"routes": {
+//!     "users": "^/users/\\d+$",
+//!     "index": "^/$"
+//! }}"#).unwrap();
+//! assert_eq!(serde_json::to_string(&routes).unwrap(),
+//!     r#"{"routes":{"index":"^/$","users":"^/users/\\d+$"}}"#);
+//! ```
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serializer, ser::SerializeMap};
+
+use crate::Serde;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D, K, V, H>(d: D) -> Result<HashMap<K, V, H>, D::Error>
+where
+    D: Deserializer<'de>,
+    Serde<HashMap<K, V, H>>: Deserialize<'de>,
+{
+    Serde::<HashMap<K, V, H>>::deserialize(d).map(Serde::into_inner)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<K, V, H, S>(value: &HashMap<K, V, H>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    K: Ord + serde::Serialize,
+    for<'a> Serde<&'a V>: serde::Serialize,
+    S: Serializer,
+{
+    let mut entries = value.iter().collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    let mut map = serializer.serialize_map(Some(entries.len()))?;
+    for (key, value) in entries {
+        map.serialize_entry(key, &Serde(value))?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::{Regex, bytes};
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Maps {
+        #[serde(with = "crate::sorted_map")]
+        text: HashMap<u32, Regex>,
+        #[serde(with = "crate::sorted_map")]
+        bytes: HashMap<String, bytes::Regex>,
+    }
+
+    #[test]
+    fn test_sorted() {
+        let mut text = HashMap::new();
+        let mut bytes = HashMap::new();
+        let b = bytes::Regex::new("b").unwrap();
+        for i in (0..20).rev() {
+            text.insert(i, Regex::new(&format!("a{{{}}}", i)).unwrap());
+            bytes.insert(format!("k{:02}", i), b.clone());
+        }
+        let json = to_string(&Maps { text, bytes }).unwrap();
+        let expected_text = (0..20)
+            .map(|i| format!(r#""{}":"a{{{}}}""#, i, i))
+            .collect::<Vec<_>>().join(",");
+        let expected_bytes = (0..20)
+            .map(|i| format!(r#""k{:02}":"b""#, i))
+            .collect::<Vec<_>>().join(",");
+        assert_eq!(json, format!(r#"{{"text":{{{}}},"bytes":{{{}}}}}"#,
+            expected_text, expected_bytes));
+        let back: Maps = from_str(&json).unwrap();
+        assert_eq!(back.text[&7].as_str(), "a{7}");
+        assert_eq!(to_string(&back).unwrap(), json);
+    }
+}
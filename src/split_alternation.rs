@@ -0,0 +1,96 @@
+//! Serialization of top-level alternations as a list of alternatives
+//!
+//! Long generated patterns like `foo|bar|baz|...` are hard to read and diff
+//! as a single string. This module serializes a pattern whose top level is
+//! an alternation as a sequence of the alternatives instead, which keeps
+//! one alternative per line in pretty-printed output. Alternatives are
+//! found in the `regex-syntax` AST, so `|` inside groups and classes is not
+//! split on.
+//!
+//! Patterns which aren't a top-level alternation, or where some
+//! alternative contains a top-level flag group like `(?i)` affecting the
+//! alternatives after it, are serialized as a plain string.
+//!
+//! Deserialization is the same as in [`or_combined`](crate::or_combined),
+//! which joins alternatives back into exactly the original pattern.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::split_alternation")]
+//!     pattern: Regex,
+//! }
+//!
+//! let config = Config { pattern: Regex::new("foo|(a|b)|[|]").unwrap() };
+//! assert_eq!(serde_json::to_string(&config).unwrap(),
+//!            r#"{"pattern":["foo","(a|b)","[|]"]}"#);
+//! ```
+use regex::Regex;
+use regex_syntax::ast::{Ast, parse::Parser};
+use serde::{Deserializer, Serializer};
+
+use crate::syntax::has_top_level_flags;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    crate::or_combined::deserialize(d)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let pattern = value.as_str();
+    if let Ok(Ast::Alternation(ref alt)) = Parser::new().parse(pattern) {
+        if !alt.asts.iter().any(has_top_level_flags) {
+            return serializer.collect_seq(alt.asts.iter().map(|ast| {
+                let span = ast.span();
+                &pattern[span.start.offset..span.end.offset]
+            }));
+        }
+    }
+    serializer.serialize_str(pattern)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "crate::split_alternation")]
+        re: Regex,
+    }
+
+    fn round_trip(pattern: &str) -> String {
+        let json = to_string(&Config { re: Regex::new(pattern).unwrap() })
+            .unwrap();
+        let back: Config = from_str(&json).unwrap();
+        assert_eq!(back.re.as_str(), pattern);
+        json
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(round_trip("a|b(c|d)|"), r#"{"re":["a","b(c|d)",""]}"#);
+        assert_eq!(round_trip(r"\||x"), r#"{"re":["\\|","x"]}"#);
+    }
+
+    #[test]
+    fn test_not_split() {
+        assert_eq!(round_trip("abc"), r#"{"re":"abc"}"#);
+        assert_eq!(round_trip("(a|b)"), r#"{"re":"(a|b)"}"#);
+        assert_eq!(round_trip("(?i)a|b"), r#"{"re":"(?i)a|b"}"#);
+    }
+}
@@ -0,0 +1,120 @@
+//! Aggregate statistics of deserialized patterns
+//!
+//! Requires the `stats` feature. When enabled, the `Serde` impls (and so
+//! `#[serde(with = "serde_regex")]`) count every pattern they compile
+//! successfully, including elements of a `Vec`, a `HashMap`, an `Option`
+//! or a `RegexSet`, into a per-thread accumulator. After loading a config
+//! call [`take`] to get the numbers for capacity planning, without having
+//! to instrument every place patterns are loaded.
+//!
+//! The accumulator is thread-local: only patterns deserialized by the
+//! current thread are counted, so loading configs on several threads gives
+//! separate stats for each of them. Call [`reset`] before loading to
+//! discard patterns counted earlier. Patterns are measured after the
+//! pattern transform, i.e. as compiled. Modules of this crate which compile
+//! patterns themselves aren't counted.
+//!
+//! The overhead is a thread-local access and a few additions per pattern,
+//! which is negligible compared to compiling it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex")]
+//!     include: Vec<Regex>,
+//!     #[serde(with = "serde_regex")]
+//!     exclude: Option<Regex>,
+//! }
+//!
+//! serde_regex::stats::reset();
+//! let _config: Config = serde_json::from_str(r#"{
+//!     "include": ["^/api/", "^/static/"],
+//!     "exclude": "\\.bak$"
+//! }"#).unwrap();
+//! let stats = serde_regex::stats::take();
+//! assert_eq!(stats.patterns, 3);
+//! assert_eq!(stats.total_bytes, 21);
+//! assert_eq!(stats.max_bytes, 9);
+//! ```
+use std::cell::Cell;
+
+/// Statistics of patterns deserialized by the current thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of patterns
+    pub patterns: usize,
+    /// Total length of all patterns in bytes
+    pub total_bytes: usize,
+    /// Length of the longest pattern in bytes
+    pub max_bytes: usize,
+}
+
+thread_local! {
+    static STATS: Cell<Stats> = const { Cell::new(Stats {
+        patterns: 0,
+        total_bytes: 0,
+        max_bytes: 0,
+    }) };
+}
+
+/// Returns stats accumulated by the current thread and resets them
+pub fn take() -> Stats {
+    STATS.with(|stats| stats.take())
+}
+
+/// Resets stats accumulated by the current thread
+pub fn reset() {
+    STATS.with(|stats| stats.set(Stats::default()));
+}
+
+pub(crate) fn record(pattern: &str) {
+    STATS.with(|stats| {
+        let mut value = stats.get();
+        value.patterns += 1;
+        value.total_bytes += pattern.len();
+        value.max_bytes = value.max_bytes.max(pattern.len());
+        stats.set(value);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::{Regex, RegexSet, bytes};
+    use serde_json::from_str;
+
+    use crate::Serde;
+
+    use super::{Stats, reset, take};
+
+    #[test]
+    fn test_collections() {
+        reset();
+        from_str::<Serde<Vec<Regex>>>(r#"["a", "bcd"]"#).unwrap();
+        from_str::<Serde<RegexSet>>(r#"["ef", "g"]"#).unwrap();
+        from_str::<Serde<HashMap<String, bytes::Regex>>>(r#"{"k": "hijkl"}"#)
+            .unwrap();
+        from_str::<Serde<Option<bytes::RegexSet>>>("null").unwrap();
+        assert_eq!(take(), Stats { patterns: 5, total_bytes: 12, max_bytes: 5 });
+        assert_eq!(take(), Stats::default());
+    }
+
+    #[test]
+    fn test_failed() {
+        reset();
+        assert!(from_str::<Serde<Vec<Regex>>>(r#"["a", "("]"#).is_err());
+        assert!(from_str::<Serde<RegexSet>>(r#"["bb", "("]"#).is_err());
+        assert_eq!(take().patterns, 1);
+        std::thread::spawn(|| {
+            from_str::<Serde<Regex>>(r#""x""#).unwrap();
+            assert_eq!(take().patterns, 1);
+        }).join().unwrap();
+        assert_eq!(take(), Stats::default());
+    }
+}
@@ -0,0 +1,86 @@
+//! Deserialization of verbose patterns into a compact form
+//!
+//! Patterns using extended mode (the `x` flag) have their comments and
+//! insignificant whitespace removed and all `x` flags dropped. The result is
+//! re-printed from the `regex-syntax` AST, so it's semantically equivalent
+//! to the input, but the original formatting is lost. Serialization emits
+//! the compact form. Patterns not using the `x` flag are kept as is.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::strip_comments")]
+//!     pattern: Regex,
+//! }
+//!
+//! let config: Config = serde_json::from_str(
+//!     r#"{"pattern": "(?x) \\d+  # digits\n - [a-z]+  # word"}"#,
+//! ).unwrap();
+//! assert_eq!(config.pattern.as_str(), r"\d+-[a-z]+");
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{Flag, parse::Parser, print::Printer};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::remove_flag;
+
+fn strip(pattern: &str)
+    -> Result<Cow<'_, str>, Box<regex_syntax::ast::Error>>
+{
+    let mut ast = Parser::new().parse(pattern)?;
+    if !remove_flag(&mut ast, Flag::IgnoreWhitespace) {
+        return Ok(Cow::Borrowed(pattern));
+    }
+    let mut result = String::with_capacity(pattern.len());
+    Printer::new().print(&ast, &mut result).expect("writing to string");
+    Ok(Cow::Owned(result))
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let compact = strip(&s).map_err(D::Error::custom)?;
+    Regex::new(&compact).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use super::strip;
+
+    #[test]
+    fn test_strip() {
+        assert_eq!(strip("(?x) a b # comment\n c").unwrap(), "abc");
+        assert_eq!(strip(r"(?xi)a\ b\#[ c]").unwrap(), r"(?i)a\ b\#[c]");
+        assert_eq!(strip("(?x: a (?-x: b ) )").unwrap(), "(?:a(?: b ))");
+        assert_eq!(strip("(?i-x)a b").unwrap(), "(?i)a b");
+        assert_eq!(strip("a b # c").unwrap(), "a b # c");
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let verbose = regex::Regex::new(r"(?x)^ \d{2} \# [ab ]+ $").unwrap();
+        let compact = regex::Regex::new(
+            &strip(verbose.as_str()).unwrap()).unwrap();
+        for text in &["12#ab", "12 #ab", "12# a", "12#a b"] {
+            assert_eq!(verbose.is_match(text), compact.is_match(text));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Macros implementing serde traits for helper structs of the modules
+//!
+//! These replace `#[derive(Serialize, Deserialize)]`, so that the crate
+//! doesn't depend on `serde_derive`. Deserialization behaves like a derive
+//! with `#[serde(deny_unknown_fields)]`: unknown, duplicate and missing
+//! fields are errors with the same messages, unless a field has a default.
+use std::{convert::TryFrom, fmt, str};
+
+use serde::de::{DeserializeSeed, Deserializer, Error, Unexpected, Visitor};
+
+/// Implements `Serialize` writing the listed fields in order
+///
+/// ```text
+/// serialize_struct!(PatternRef<'_> as PatternRef { pattern });
+/// ```
+macro_rules! serialize_struct {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        serialize_struct!($name as $name { $($field),* });
+    };
+    ($ty:ty as $name:ident { $($field:ident),* $(,)? }) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S>(&self, serializer: S)
+                -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::SerializeStruct;
+
+                let len = [$(stringify!($field)),*].len();
+                let mut s = serializer.serialize_struct(
+                    stringify!($name), len)?;
+                $( s.serialize_field(stringify!($field), &self.$field)?; )*
+                s.end()
+            }
+        }
+    };
+}
+
+/// Implements `Deserialize` from a map (or a sequence) of fields
+///
+/// Each field may have aliases and a default used when it's missing:
+///
+/// ```text
+/// deserialize_struct!(Flagged<String> as Flagged {
+///     pattern: String,
+///     multi_line | "multiline": bool = false,
+/// });
+/// ```
+macro_rules! deserialize_struct {
+    (@missing $field:ident) => {
+        return Err(serde::de::Error::missing_field(stringify!($field)))
+    };
+    (@missing $field:ident $default:expr) => { $default };
+    (@short $index:ident $expected:expr) => {
+        return Err(serde::de::Error::invalid_length($index, $expected))
+    };
+    (@short $index:ident $expected:expr, $default:expr) => { $default };
+    ($name:ident {
+        $($field:ident $(| $alias:literal)*: $fty:ty $(= $default:expr)?),*
+        $(,)?
+    }) => {
+        deserialize_struct!($name as $name {
+            $($field $(| $alias)*: $fty $(= $default)?),*
+        });
+    };
+    ($ty:ty as $name:ident {
+        $($field:ident $(| $alias:literal)*: $fty:ty $(= $default:expr)?),*
+        $(,)?
+    }) => {
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D>(d: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                const FIELDS: &[&str] = &[$(stringify!($field)),*];
+                const ALIASES: &[(&str, &str)] =
+                    &[$($(($alias, stringify!($field)),)*)*];
+
+                struct StructVisitor;
+
+                impl<'de> serde::de::Visitor<'de> for StructVisitor {
+                    type Value = $ty;
+
+                    fn expecting(&self, formatter: &mut ::std::fmt::Formatter)
+                        -> ::std::fmt::Result
+                    {
+                        formatter.write_str(
+                            concat!("struct ", stringify!($name)))
+                    }
+                    fn visit_map<A>(self, mut map: A)
+                        -> ::core::result::Result<$ty, A::Error>
+                    where
+                        A: serde::de::MapAccess<'de>,
+                    {
+                        $( let mut $field: Option<$fty> = None; )*
+                        let seed = crate::structs::FieldSeed {
+                            fields: FIELDS,
+                            aliases: ALIASES,
+                        };
+                        while let Some(key) = map.next_key_seed(seed)? {
+                            $(
+                                if key == stringify!($field) {
+                                    if $field.is_some() {
+                                        return Err(
+                                            serde::de::Error::duplicate_field(
+                                                stringify!($field)));
+                                    }
+                                    $field = Some(map.next_value()?);
+                                    continue;
+                                }
+                            )*
+                        }
+                        Ok($name {$(
+                            $field: match $field {
+                                Some(value) => value,
+                                None => deserialize_struct!(
+                                    @missing $field $($default)?),
+                            },
+                        )*})
+                    }
+                    fn visit_seq<A>(self, mut seq: A)
+                        -> ::core::result::Result<$ty, A::Error>
+                    where
+                        A: serde::de::SeqAccess<'de>,
+                    {
+                        let mut index = 0;
+                        $(
+                            let $field = match seq.next_element::<$fty>()? {
+                                Some(value) => value,
+                                None => deserialize_struct!(
+                                    @short index &StructVisitor
+                                    $(, $default)?),
+                            };
+                            index += 1;
+                        )*
+                        let _ = index;
+                        Ok($name { $($field),* })
+                    }
+                }
+
+                d.deserialize_struct(stringify!($name), FIELDS, StructVisitor)
+            }
+        }
+    };
+}
+
+/// Deserializes a field name into one of `fields`
+#[derive(Clone, Copy)]
+pub(crate) struct FieldSeed {
+    pub fields: &'static [&'static str],
+    /// Pairs of an alias and the field it stands for
+    pub aliases: &'static [(&'static str, &'static str)],
+}
+
+impl<'de> DeserializeSeed<'de> for FieldSeed {
+    type Value = &'static str;
+
+    fn deserialize<D>(self, d: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed {
+    type Value = &'static str;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("field identifier")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        self.fields.iter().copied().find(|&field| field == v)
+            .or_else(|| {
+                self.aliases.iter()
+                    .find(|&&(alias, _)| alias == v)
+                    .map(|&(_, field)| field)
+            })
+            .ok_or_else(|| E::unknown_field(v, self.fields))
+    }
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        match str::from_utf8(v) {
+            Ok(v) => self.visit_str(v),
+            Err(_) => {
+                Err(E::unknown_field(&String::from_utf8_lossy(v),
+                    self.fields))
+            }
+        }
+    }
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        usize::try_from(v).ok()
+            .and_then(|index| self.fields.get(index).copied())
+            .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(v),
+                &"field index"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{from_str, to_string};
+
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    deserialize_struct!(Point {
+        x: u32,
+        y | "why": u32 = 7,
+    });
+    serialize_struct!(Point { x, y });
+
+    fn load(json: &str) -> Result<Point, String> {
+        from_str(json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(load(r#"{"x": 1, "y": 2}"#), Ok(Point { x: 1, y: 2 }));
+        assert_eq!(load(r#"{"why": 2, "x": 1}"#), Ok(Point { x: 1, y: 2 }));
+        assert_eq!(load(r#"{"x": 1}"#), Ok(Point { x: 1, y: 7 }));
+        assert_eq!(load("[1, 2]"), Ok(Point { x: 1, y: 2 }));
+        assert_eq!(load("[1]"), Ok(Point { x: 1, y: 7 }));
+        assert_eq!(to_string(&Point { x: 1, y: 2 }).unwrap(),
+            r#"{"x":1,"y":2}"#);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(load(r#"{"y": 2}"#).unwrap_err(),
+            "missing field `x` at line 1 column 8");
+        assert_eq!(load(r#"{"x": 1, "z": 2}"#).unwrap_err(),
+            "unknown field `z`, expected `x` or `y` at line 1 column 12");
+        assert_eq!(load(r#"{"x": 1, "x": 2}"#).unwrap_err(),
+            "duplicate field `x` at line 1 column 12");
+        assert_eq!(load("[]").unwrap_err(),
+            "invalid length 0, expected struct Point at line 1 column 2");
+    }
+}
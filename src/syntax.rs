@@ -0,0 +1,225 @@
+//! Helpers for inspecting and rewriting patterns with `regex-syntax`
+use std::borrow::Cow;
+
+use regex_syntax::{
+    ast::{
+        self,
+        Ast,
+        ClassSet,
+        ClassSetItem,
+        Flag,
+        Flags,
+        FlagsItemKind,
+        GroupKind,
+    },
+    hir::{Hir, HirKind},
+};
+
+/// Returns the string matched by the pattern if it's a plain literal
+pub(crate) fn as_literal(pattern: &str) -> Option<String> {
+    let hir = regex_syntax::Parser::new().parse(pattern).ok()?;
+    match hir.kind() {
+        HirKind::Empty => Some(String::new()),
+        HirKind::Literal(lit) => String::from_utf8(lit.0.to_vec()).ok(),
+        _ => None,
+    }
+}
+
+/// Returns true if both patterns translate to the same `Hir`
+///
+/// Translation applies flags and simplifies some constructs, so cosmetic
+/// differences disappear: order of flags, `(?i)a` vs `[aA]`, `[a]` vs `a`,
+/// non-capturing groups around a single item, etc.
+pub(crate) fn semantic_eq(a: &str, b: &str) -> bool {
+    match (translate(a), translate(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Parses the pattern into the `Hir` compared by [`semantic_eq`]
+pub(crate) fn translate(pattern: &str) -> Result<Hir, String> {
+    regex_syntax::Parser::new().parse(pattern).map_err(|e| e.to_string())
+}
+
+/// Splits a leading group of flags like `(?im)` from the pattern
+///
+/// Returns flag letters and the rest of the pattern, or `None` if the
+/// pattern doesn't start with a non-empty group consisting only of letters
+/// from `allowed`.
+pub(crate) fn split_flags<'a>(pattern: &'a str, allowed: &[char])
+    -> Option<(&'a str, &'a str)>
+{
+    let rest = pattern.strip_prefix("(?")?;
+    let end = rest.find(')')?;
+    let flags = &rest[..end];
+    if flags.is_empty() || !flags.chars().all(|c| allowed.contains(&c)) {
+        return None;
+    }
+    Some((flags, &rest[end + 1..]))
+}
+
+/// Prepends a group of inline flags like `(?im)` to the pattern
+///
+/// Flags are written in the order of `order`, once each, skipping `u` which
+/// is on by default. If no flags are left the pattern is returned as is, so
+/// there is never an empty or no-op group which would change the output
+/// of the next serialization.
+pub(crate) fn prefix_flags<'a>(order: &[char], flags: &[char], pattern: &'a str)
+    -> Cow<'a, str>
+{
+    let flags = order.iter()
+        .filter(|&&c| c != 'u' && flags.contains(&c))
+        .collect::<String>();
+    if flags.is_empty() {
+        Cow::Borrowed(pattern)
+    } else {
+        Cow::Owned(format!("(?{}){}", flags, pattern))
+    }
+}
+
+/// Returns a backreference-like token which makes the pattern invalid
+///
+/// Detects PCRE-style `\1`, `\k<name>`, `\k{name}`, `\k'name'`, `\g1`,
+/// `\g{name}` and Python-style `(?P=name)` by looking at the parse error.
+pub(crate) fn backreference(pattern: &str) -> Option<&str> {
+    let err = ast::parse::Parser::new().parse(pattern).err()?;
+    let start = err.span().start.offset;
+    let end = err.span().end.offset;
+    let rest = &pattern[end..];
+    let extra = match (err.kind(), &pattern[start..end]) {
+        (ast::ErrorKind::UnsupportedBackreference, _) => 0,
+        (ast::ErrorKind::EscapeUnrecognized, r"\k" | r"\g") => {
+            let close = match rest.chars().next() {
+                Some('<') => '>',
+                Some('{') => '}',
+                Some('\'') => '\'',
+                _ => return Some(&pattern[start..end + rest.chars()
+                    .take_while(|c| c.is_ascii_digit()).count()]),
+            };
+            rest[1..].find(close).map(|idx| idx + 2).unwrap_or(0)
+        }
+        (ast::ErrorKind::FlagUnrecognized, "P")
+            if pattern[..start].ends_with("(?") && rest.starts_with('=')
+        => {
+            return Some(&pattern[start - 2..end
+                + rest.find(')').map(|idx| idx + 1).unwrap_or(0)]);
+        }
+        _ => return None,
+    };
+    Some(&pattern[start..end + extra])
+}
+
+/// Calls `f` on every node of the tree, parents before children
+pub(crate) fn visit(ast: &Ast, f: &mut dyn FnMut(&Ast)) {
+    f(ast);
+    match ast {
+        Ast::Group(group) => visit(&group.ast, f),
+        Ast::Repetition(rep) => visit(&rep.ast, f),
+        Ast::Alternation(alt) => {
+            for ast in alt.asts.iter() {
+                visit(ast, f);
+            }
+        }
+        Ast::Concat(concat) => {
+            for ast in concat.asts.iter() {
+                visit(ast, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Calls `f` on every item of the class set, including nested ones
+pub(crate) fn visit_class(set: &ClassSet, f: &mut dyn FnMut(&ClassSetItem)) {
+    match set {
+        ClassSet::Item(item) => visit_class_item(item, f),
+        ClassSet::BinaryOp(op) => {
+            visit_class(&op.lhs, f);
+            visit_class(&op.rhs, f);
+        }
+    }
+}
+
+fn visit_class_item(item: &ClassSetItem, f: &mut dyn FnMut(&ClassSetItem)) {
+    f(item);
+    match item {
+        ClassSetItem::Bracketed(class) => visit_class(&class.kind, f),
+        ClassSetItem::Union(union) => {
+            for item in &union.items {
+                visit_class_item(item, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Calls `f` on every node of the tree, parents before children
+///
+/// If `f` replaces a node, children of the new node are visited.
+pub(crate) fn visit_mut(ast: &mut Ast, f: &mut dyn FnMut(&mut Ast)) {
+    f(ast);
+    match ast {
+        Ast::Group(group) => visit_mut(&mut group.ast, f),
+        Ast::Repetition(rep) => visit_mut(&mut rep.ast, f),
+        Ast::Alternation(alt) => {
+            for ast in alt.asts.iter_mut() {
+                visit_mut(ast, f);
+            }
+        }
+        Ast::Concat(concat) => {
+            for ast in concat.asts.iter_mut() {
+                visit_mut(ast, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes `flag` from flag set, returns true if it was there
+fn strip_flag(flags: &mut Flags, flag: Flag) -> bool {
+    let before = flags.items.len();
+    flags.items.retain(|item| item.kind != FlagsItemKind::Flag(flag));
+    if flags.items.last()
+        .map(|item| item.kind == FlagsItemKind::Negation)
+        .unwrap_or(false)
+    {
+        // dangling negation like `(?i-)` is a syntax error
+        flags.items.pop();
+    }
+    flags.items.len() != before
+}
+
+/// Removes all occurrences of the inline `flag`, returns true if any
+///
+/// Flag groups which end up empty, like `(?x)`, are removed entirely.
+pub(crate) fn remove_flag(ast: &mut Ast, flag: Flag) -> bool {
+    let mut found = false;
+    visit_mut(ast, &mut |node| match node {
+        Ast::Flags(set) => {
+            found |= strip_flag(&mut set.flags, flag);
+            if set.flags.items.is_empty() {
+                let span = set.span;
+                *node = Ast::empty(span);
+            }
+        }
+        Ast::Group(group) => {
+            if let GroupKind::NonCapturing(ref mut flags) = group.kind {
+                found |= strip_flag(flags, flag);
+            }
+        }
+        _ => {}
+    });
+    found
+}
+
+/// Returns true if a flag group like `(?i)` applies to the rest of the
+/// enclosing group, i.e. it is not nested in any other group
+pub(crate) fn has_top_level_flags(ast: &Ast) -> bool {
+    match ast {
+        Ast::Flags(_) => true,
+        Ast::Concat(concat) => concat.asts.iter().any(has_top_level_flags),
+        Ast::Alternation(alt) => alt.asts.iter().any(has_top_level_flags),
+        _ => false,
+    }
+}
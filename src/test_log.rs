@@ -0,0 +1,33 @@
+//! Logger capturing messages of the current thread in tests
+use std::cell::RefCell;
+
+use log::{Log, Metadata, Record};
+
+struct Capture;
+
+static LOGGER: Capture = Capture;
+
+thread_local! {
+    static MESSAGES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, record: &Record) {
+        let line = format!("{}: {}", record.level(), record.args());
+        MESSAGES.with(|messages| messages.borrow_mut().push(line));
+    }
+    fn flush(&self) {}
+}
+
+/// Runs `f` and returns messages logged by it
+pub fn capture<F: FnOnce()>(f: F) -> Vec<String> {
+    // fails if already set by other test, which is fine
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+    MESSAGES.with(|messages| messages.borrow_mut().clear());
+    f();
+    MESSAGES.with(|messages| messages.borrow_mut().split_off(0))
+}
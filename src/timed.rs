@@ -0,0 +1,121 @@
+//! Deserialization which records how long each pattern takes to compile
+//!
+//! Timings are reported to a per-thread sink registered with [`set_sink`],
+//! which receives the pattern and its compile duration. The compiled regex
+//! is returned as usual. When no sink is registered on the current thread
+//! the clock isn't read at all, so the only overhead is a thread-local
+//! lookup.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::{cell::RefCell, rc::Rc, time::Duration};
+//! use regex::Regex;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::timed")]
+//!     pattern: Regex,
+//! }
+//!
+//! let timings = Rc::new(RefCell::new(Vec::<(String, Duration)>::new()));
+//! let sink = timings.clone();
+//! serde_regex::timed::set_sink(move |pattern: &str, elapsed| {
+//!     sink.borrow_mut().push((pattern.to_string(), elapsed));
+//! });
+//! let _: Config = serde_json::from_str(r#"{"pattern": "a+"}"#).unwrap();
+//! serde_regex::timed::clear_sink();
+//! assert_eq!(timings.borrow()[0].0, "a+");
+//! ```
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+type Sink = Box<dyn FnMut(&str, Duration)>;
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = RefCell::new(None);
+}
+
+/// Registers a sink receiving compile timings on the current thread
+///
+/// Replaces the previously registered sink, if any.
+pub fn set_sink<F>(sink: F)
+where
+    F: FnMut(&str, Duration) + 'static,
+{
+    SINK.with(|cell| *cell.borrow_mut() = Some(Box::new(sink)));
+}
+
+/// Removes the sink registered on the current thread, disabling timing
+pub fn clear_sink() {
+    SINK.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    // The sink is taken out while being called, so that it may deserialize
+    // more patterns itself without a `RefCell` double borrow.
+    let sink = SINK.with(|cell| cell.borrow_mut().take());
+    let result = match sink {
+        Some(mut sink) => {
+            let start = Instant::now();
+            let result = Regex::new(&s);
+            sink(&s, start.elapsed());
+            SINK.with(|cell| {
+                cell.borrow_mut().get_or_insert(sink);
+            });
+            result
+        }
+        None => Regex::new(&s),
+    };
+    result.map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use regex::Regex;
+    use serde_derive::Deserialize;
+    use serde_json::from_str;
+
+    #[derive(Deserialize)]
+    struct Config {
+        #[serde(with = "crate::timed")]
+        pattern: Regex,
+    }
+
+    #[test]
+    fn test_sink() {
+        let patterns = Rc::new(RefCell::new(Vec::new()));
+        let sink = patterns.clone();
+        super::set_sink(move |pattern: &str, _| {
+            sink.borrow_mut().push(pattern.to_string());
+        });
+        let config: Config = from_str(r#"{"pattern": "a+"}"#).unwrap();
+        assert_eq!(config.pattern.as_str(), "a+");
+        assert!(from_str::<Config>(r#"{"pattern": "a("}"#).is_err());
+        super::clear_sink();
+        let _: Config = from_str(r#"{"pattern": "b+"}"#).unwrap();
+        assert_eq!(*patterns.borrow(), vec!["a+", "a("]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Deserialization stripping unsupported inline flags instead of failing
+//!
+//! Meant for migrations, when patterns written for one engine may contain
+//! flags another part of the system doesn't support. Rather than rejecting
+//! such a pattern like [`flag_whitelist`](crate::flag_whitelist) does, this
+//! module removes the unsupported flags and compiles the rest. With the
+//! `log` feature enabled, a `log::warn!` message is emitted for every
+//! pattern changed this way, naming the stripped flags.
+//!
+//! Flags are removed from the parsed syntax tree, wherever they are: both
+//! global ones like `(?x)` and scoped ones like `(?sx:...)`, whether
+//! enabled or disabled. Groups left without flags are dropped or turned
+//! into plain non-capturing groups, and the pattern is printed back from
+//! the tree. Patterns without unsupported flags are compiled unchanged.
+//!
+//! The set of unsupported flags is a bit mask of the constants from
+//! [`flag_whitelist`](crate::flag_whitelist). The default is
+//! [`DEFAULT_STRIPPED`], i.e. `x`, which is harmless to strip, because
+//! whitespace and comments it enables are dropped when parsing. Stripping
+//! other flags **changes what the pattern matches**, e.g. without `(?i)`
+//! the pattern becomes case-sensitive, which is why this is opt-in. Use
+//! `deserialize_strip` to pick the flags:
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//! use serde_regex::flag_whitelist::{DOT_MATCHES_NEW_LINE, SWAP_GREED};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "serde_regex::tolerant_flags")]
+//!     pattern: Regex,
+//!     #[serde(
+//!         serialize_with = "serde_regex::tolerant_flags::serialize",
+//!         deserialize_with = "serde_regex::tolerant_flags::deserialize_strip::\
+//!             <_, { DOT_MATCHES_NEW_LINE | SWAP_GREED }>",
+//!     )]
+//!     legacy: Regex,
+//! }
+//!
+//! let config: Config = serde_json::from_str(r#"{
+//!     "pattern": "(?x) \\d+ - \\d+",
+//!     "legacy": "(?is)a.+b"
+//! }"#).unwrap();
+//! assert_eq!(config.pattern.as_str(), r"\d+-\d+");
+//! assert_eq!(config.legacy.as_str(), "(?i)a.+b");
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{parse::Parser, print::Printer};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::flag_whitelist::{ALL_FLAGS, IGNORE_WHITESPACE, bit_and_name};
+use crate::syntax::remove_flag;
+
+/// Flags stripped by default
+pub const DEFAULT_STRIPPED: u32 = IGNORE_WHITESPACE;
+
+/// Returns the pattern without flags in `mask` and names of removed flags
+fn strip(pattern: &str, mask: u32)
+    -> Result<(Cow<'_, str>, Vec<char>), String>
+{
+    let mut ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut stripped = Vec::new();
+    for &flag in ALL_FLAGS {
+        let (bit, name) = bit_and_name(flag);
+        if mask & bit != 0 && remove_flag(&mut ast, flag) {
+            stripped.push(name);
+        }
+    }
+    if stripped.is_empty() {
+        return Ok((Cow::Borrowed(pattern), stripped));
+    }
+    let mut result = String::with_capacity(pattern.len());
+    Printer::new().print(&ast, &mut result).expect("writing to string");
+    Ok((Cow::Owned(result), stripped))
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_strip::<D, DEFAULT_STRIPPED>(d)
+}
+
+/// Deserialize function stripping flags in the `STRIPPED` mask
+pub fn deserialize_strip<'de, D, const STRIPPED: u32>(d: D)
+    -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let (pattern, stripped) = strip(&s, STRIPPED).map_err(D::Error::custom)?;
+    #[cfg(feature = "log")]
+    if !stripped.is_empty() {
+        log::warn!("stripped unsupported flags {:?} from pattern {:?}",
+            stripped.iter().collect::<String>(), s);
+    }
+    #[cfg(not(feature = "log"))]
+    let _ = stripped;
+    Regex::new(&pattern).map_err(D::Error::custom)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use crate::flag_whitelist::{CASE_INSENSITIVE, IGNORE_WHITESPACE, UNICODE};
+    use super::{deserialize, deserialize_strip, strip};
+
+    #[test]
+    fn test_strip() {
+        let strip_i = |p| strip(p, CASE_INSENSITIVE).unwrap();
+        assert_eq!(strip_i("(?i)a"), ("a".into(), vec!['i']));
+        assert_eq!(strip_i("(?im)a(?-i:b)"), ("(?m)a(?:b)".into(), vec!['i']));
+        assert_eq!(strip_i("(?s)a"), ("(?s)a".into(), vec![]));
+        assert_eq!(strip("(?xi-u) a", !0).unwrap(), ("a".into(),
+            vec!['i', 'u', 'x']));
+        assert!(strip("(", !0).is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let re = deserialize(Value::from("(?x) a b (?i:c)")).unwrap();
+        assert_eq!(re.as_str(), "ab(?i:c)");
+        let re = deserialize_strip::<_, { IGNORE_WHITESPACE | UNICODE }>(
+            Value::from("(?-u:\\w)+")).unwrap();
+        assert!(re.is_match("é"));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_warn() {
+        let messages = crate::test_log::capture(|| {
+            deserialize(Value::from("(?x)a b")).unwrap();
+            deserialize(Value::from("ab")).unwrap();
+        });
+        assert_eq!(messages, vec![
+            r#"WARN: stripped unsupported flags "x" from pattern "(?x)a b""#,
+        ]);
+    }
+}
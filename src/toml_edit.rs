@@ -0,0 +1,117 @@
+//! Reading and writing regexes in `toml_edit` documents
+//!
+//! Enabled by the `toml_edit` feature. Config editing tools use
+//! `toml_edit` to keep comments and formatting intact, so going through
+//! serde isn't an option there. These helpers work on a single
+//! `toml_edit::Item`:
+//!
+//! * [`get`] reads a string item and compiles it. An item of another type
+//!   (including a missing one, i.e. `Item::None`) is an
+//!   [`Error::NotString`] naming the type found, an invalid pattern is an
+//!   [`Error::Regex`]. The document is never modified.
+//! * [`set`] writes the pattern as a string. If the item already holds a
+//!   value, its decoration (whitespace and comments around it) is kept, so
+//!   only the value itself changes in the output. Otherwise the item is
+//!   replaced by a new string value. Quoting is chosen by `toml_edit`,
+//!   which prefers literal `'...'` strings for patterns with backslashes.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//!
+//! let mut doc = r#"
+//! ## matches release tags
+//! tag = "^v\\d+$"  # keep in sync with CI
+//! "#.parse::<toml_edit::DocumentMut>().unwrap();
+//! let tag = serde_regex::toml_edit::get(&doc["tag"]).unwrap();
+//! assert!(tag.is_match("v12"));
+//!
+//! let new = Regex::new(r"^v\d+\.\d+$").unwrap();
+//! serde_regex::toml_edit::set(&mut doc["tag"], &new);
+//! assert_eq!(doc.to_string(), r#"
+//! ## matches release tags
+//! tag = '^v\d+\.\d+$'  # keep in sync with CI
+//! "#);
+//! ```
+use std::fmt;
+
+use ::toml_edit::{Item, Value};
+use regex::Regex;
+
+/// Error returned by [`get`]
+#[derive(Debug)]
+pub enum Error {
+    /// The item is not a string, contains the name of its actual type
+    NotString(&'static str),
+    /// The string is not a valid regular expression
+    Regex(regex::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NotString(type_name) => {
+                write!(f, "expected a string pattern, found {}", type_name)
+            }
+            Error::Regex(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NotString(_) => None,
+            Error::Regex(e) => Some(e),
+        }
+    }
+}
+
+/// Compiles the string stored in `item`
+pub fn get(item: &Item) -> Result<Regex, Error> {
+    let pattern = item.as_str().ok_or(Error::NotString(item.type_name()))?;
+    Regex::new(pattern).map_err(Error::Regex)
+}
+
+/// Stores the pattern into `item` keeping its decoration
+pub fn set(item: &mut Item, regex: &Regex) {
+    match item.as_value_mut() {
+        Some(value) => {
+            let decor = value.decor().clone();
+            *value = Value::from(regex.as_str());
+            *value.decor_mut() = decor;
+        }
+        None => *item = ::toml_edit::value(regex.as_str()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::toml_edit::{DocumentMut, Item};
+    use regex::Regex;
+
+    use super::{Error, get, set};
+
+    #[test]
+    fn test_get() {
+        let doc = "a = 'x+'\nb = 1\nc = '('\n[d]\n"
+            .parse::<DocumentMut>().unwrap();
+        assert_eq!(get(&doc["a"]).unwrap().as_str(), "x+");
+        assert!(matches!(get(&doc["b"]), Err(Error::NotString("integer"))));
+        assert!(matches!(get(&doc["c"]), Err(Error::Regex(_))));
+        assert_eq!(get(&doc["d"]).unwrap_err().to_string(),
+            "expected a string pattern, found table");
+        assert!(matches!(get(&Item::None), Err(Error::NotString("none"))));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut doc = "a = 1 # old\n".parse::<DocumentMut>().unwrap();
+        let regex = Regex::new("y*").unwrap();
+        set(&mut doc["a"], &regex);
+        set(&mut doc["b"], &regex);
+        assert_eq!(doc.to_string(), "a = \"y*\" # old\nb = \"y*\"\n");
+        assert_eq!(get(&doc["b"]).unwrap().as_str(), "y*");
+    }
+}
@@ -0,0 +1,194 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::Serde;
+
+/// An ordered pipeline of find and replace steps
+///
+/// (De)serializes as a sequence of `{"find": pattern, "replace": template}`
+/// maps:
+///
+/// ```rust
+/// use serde_regex::TransformChain;
+///
+/// let chain: TransformChain = serde_json::from_str(r#"[
+///     {"find": "\\s+", "replace": " "},
+///     {"find": "(?P<y>\\d{4})-(?P<m>\\d{2})", "replace": "$m/$y"}
+/// ]"#).unwrap();
+/// assert_eq!(chain.apply("due\t 2024-05"), "due 05/2024");
+/// ```
+///
+/// Steps are evaluated in order, each one running `Regex::replace_all` on
+/// the output of the previous one. So a step sees replacements made by
+/// earlier steps, but not its own: within a step, matches are found in the
+/// input of that step, non-overlapping and leftmost-first.
+///
+/// Replacement templates use the syntax of `Regex::replace`: `$name`,
+/// `${name}`, `$1` or `${1}` refer to a capture group, and `$$` is a
+/// literal dollar. While `regex` silently replaces a reference to a group
+/// which doesn't exist with an empty string, it's a deserialization error
+/// here. This catches the common mistake of writing `$1_suffix`, which
+/// refers to the group named `1_suffix` rather than to group `1`.
+#[derive(Debug, Clone, Default)]
+pub struct TransformChain {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    find: Regex,
+    replace: String,
+}
+
+struct StepDef {
+    find: Serde<Regex>,
+    replace: String,
+}
+
+deserialize_struct!(StepDef {
+    find: Serde<Regex>,
+    replace: String,
+});
+
+struct StepRef<'a> {
+    find: Serde<&'a Regex>,
+    replace: &'a str,
+}
+
+serialize_struct!(StepRef<'_> as StepRef { find, replace });
+
+fn is_cap_letter(b: u8) -> bool {
+    b == b'_' || b.is_ascii_alphanumeric()
+}
+
+/// Checks that every capture reference in `template` exists in `regex`
+///
+/// References are parsed the same way `regex` does it when interpolating.
+fn check_template(regex: &Regex, template: &str) -> Result<(), String> {
+    let mut rest = template.as_bytes();
+    while let Some(pos) = rest.iter().position(|&b| b == b'$') {
+        rest = &rest[pos + 1..];
+        let (name, len) = match rest.first() {
+            Some(b'$') => (None, 1),
+            Some(b'{') => match rest.iter().position(|&b| b == b'}') {
+                Some(end) => (Some(&rest[1..end]), end + 1),
+                None => (None, 0),
+            },
+            _ => {
+                let end = rest.iter().position(|&b| !is_cap_letter(b))
+                    .unwrap_or(rest.len());
+                (Some(&rest[..end]).filter(|name| !name.is_empty()), end)
+            }
+        };
+        rest = &rest[len..];
+        let name = match name.map(String::from_utf8_lossy) {
+            Some(name) => name,
+            None => continue,
+        };
+        let exists = match name.parse::<usize>() {
+            Ok(index) => index < regex.captures_len(),
+            Err(_) => regex.capture_names().any(|n| n == Some(&name[..])),
+        };
+        if !exists {
+            return Err(format!(
+                "replacement {:?} refers to unknown group {:?} of {:?}",
+                template, name, regex.as_str()));
+        }
+    }
+    Ok(())
+}
+
+impl TransformChain {
+    /// Runs all steps in order and returns the result
+    pub fn apply(&self, input: &str) -> String {
+        let mut result = input.to_string();
+        for step in &self.steps {
+            if let Cow::Owned(replaced) =
+                step.find.replace_all(&result, &step.replace[..])
+            {
+                result = replaced;
+            }
+        }
+        result
+    }
+
+    /// Iterates over patterns and replacement templates in order
+    pub fn iter(&self) -> impl Iterator<Item = (&Regex, &str)> {
+        self.steps.iter().map(|step| (&step.find, &step.replace[..]))
+    }
+
+    /// Returns number of steps
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if there are no steps
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for TransformChain {
+    fn deserialize<D>(d: D) -> Result<TransformChain, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let defs = <Vec<StepDef>>::deserialize(d)?;
+        let steps = defs.into_iter()
+            .map(|StepDef { find: Serde(find), replace }| {
+                check_template(&find, &replace)
+                    .map(|()| Step { find, replace })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(D::Error::custom)?;
+        Ok(TransformChain { steps })
+    }
+}
+
+impl Serialize for TransformChain {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.steps.iter().map(|step| StepRef {
+            find: Serde(&step.find),
+            replace: &step.replace,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_json::{from_str, to_string};
+
+    use super::{TransformChain, check_template};
+
+    fn check(pattern: &str, template: &str) -> Result<(), String> {
+        check_template(&Regex::new(pattern).unwrap(), template)
+    }
+
+    #[test]
+    fn test_check_template() {
+        assert!(check("(a)(?P<x>b)", "$0 $1 $2 ${1}z $x ${x} $$3 $ ${").is_ok());
+        assert!(check("a", "$1").is_err());
+        assert!(check("(?P<x>a)", "${y}").is_err());
+        assert_eq!(check("(a)", "$1_x").unwrap_err(),
+            r#"replacement "$1_x" refers to unknown group "1_x" of "(a)""#);
+    }
+
+    #[test]
+    fn test_apply() {
+        let json = r#"[{"find":"a","replace":"b"},{"find":"b+","replace":"[$0]"}]"#;
+        let chain: TransformChain = from_str(json).unwrap();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.apply("abc ab"), "[bb]c [bb]");
+        assert_eq!(chain.apply("xyz"), "xyz");
+        assert_eq!(to_string(&chain).unwrap(), json);
+        assert!(from_str::<TransformChain>(
+            r#"[{"find":"a","replace":"$1"}]"#).is_err());
+        assert!(from_str::<TransformChain>("[]").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! (De)serialization of maps with case-insensitive keys
+//!
+//! Enabled by the `unicase` feature. Works for `HashMap<UniCase<String>,
+//! Regex, S>` fields, which is convenient for keys like HTTP header names:
+//! lookups ignore the case of the key, and so does deserialization. The
+//! `unicase` crate doesn't implement `serde` traits, so a key can't be
+//! used with the generic `HashMap` support of the main module, hence this
+//! module.
+//!
+//! Keys which differ only in case are duplicates. Like for other maps the
+//! last value wins, while the spelling of the key that came first is kept
+//! (that's what `HashMap::insert` does). Patterns are compiled as usual,
+//! case-insensitivity applies to keys only.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//!
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//! use unicase::UniCase;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Validators {
+//!     #[serde(with = "serde_regex::unicase_map")]
+//!     headers: HashMap<UniCase<String>, Regex>,
+//! }
+//!
+//! let config: Validators = serde_json::from_str(r#"{"headers": {
+//!     "Content-Type": "^application/json$",
+//!     "X-Request-Id": "^[0-9a-f]{32}$"
+//! }}"#).unwrap();
+//! let validator = &config.headers[&UniCase::new("content-type".into())];
+//! assert!(validator.is_match("application/json"));
+//! ```
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::BuildHasher,
+    marker::PhantomData,
+};
+
+use regex::Regex;
+use serde::{
+    Deserializer,
+    Serializer,
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+};
+use unicase::UniCase;
+
+use crate::Serde;
+
+struct UniCaseMapVisitor<S>(PhantomData<S>);
+
+impl<'de, S> Visitor<'de> for UniCaseMapVisitor<S>
+where
+    S: BuildHasher + Default,
+{
+    type Value = HashMap<UniCase<String>, Regex, S>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid map")
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut hashmap = match map.size_hint() {
+            Some(size) => HashMap::with_capacity_and_hasher(size, S::default()),
+            None => HashMap::with_hasher(S::default()),
+        };
+        while let Some((key, Serde(value))) = map.next_entry::<String, _>()? {
+            hashmap.insert(UniCase::new(key), value);
+        }
+        Ok(hashmap)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, S, D>(d: D)
+    -> Result<HashMap<UniCase<String>, Regex, S>, D::Error>
+where
+    D: Deserializer<'de>,
+    S: BuildHasher + Default,
+{
+    d.deserialize_map(UniCaseMapVisitor(PhantomData))
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S, Se>(value: &HashMap<UniCase<String>, Regex, S>,
+    serializer: Se)
+    -> Result<Se::Ok, Se::Error>
+where
+    S: BuildHasher,
+    Se: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(value.len()))?;
+    for (key, regex) in value {
+        map.serialize_entry(key.as_str(), &Serde(regex))?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, json, to_value};
+    use unicase::UniCase;
+
+    #[derive(Serialize, Deserialize)]
+    struct Headers {
+        #[serde(with = "crate::unicase_map")]
+        headers: HashMap<UniCase<String>, Regex>,
+    }
+
+    fn key(name: &str) -> UniCase<String> {
+        UniCase::new(name.to_string())
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let parsed: Headers = from_str(r#"{"headers": {
+            "Accept": "^text/",
+            "ACCEPT": "^application/",
+            "Host": "^example\\.com$"
+        }}"#).unwrap();
+        assert_eq!(parsed.headers.len(), 2);
+        assert_eq!(parsed.headers[&key("accept")].as_str(), "^application/");
+        let (name, _) = parsed.headers.get_key_value(&key("aCCept")).unwrap();
+        assert_eq!(name.as_str(), "Accept");
+        assert_eq!(to_value(&parsed).unwrap()["headers"]["Accept"],
+            json!("^application/"));
+        assert!(from_str::<Headers>(r#"{"headers": {"a": "("}}"#).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Deserialization of patterns restricted for matching URL paths
+//!
+//! This module is opt-in and meant for HTTP routers. The router matches a
+//! pattern against a single path, so anything dealing with line breaks is
+//! either a mistake or an attempt to match across the framing of the
+//! request. The pattern is compiled first, then rejected if it contains
+//! any of the following:
+//!
+//! * the `m` flag (`(?m)` or `(?m:...)`), which makes `^` and `$` match
+//!   at line breaks instead of only at the start and end of the path;
+//! * the `s` flag, which makes `.` match `\n`;
+//! * a literal `\n` or `\r`, whether escaped or not;
+//! * `\s` or `[[:space:]]`, which match `\n` and `\r`;
+//! * a bracketed class listing `\n` or `\r`, or a range containing them,
+//!   like `[\x00-\x1F]`.
+//!
+//! Disabling flags, like `(?-s)`, is allowed. Negated classes like `[^/]`,
+//! `[^\n]` and `\S` are allowed too: they are the usual way to match a path
+//! segment, and a path never contains a raw line break, since the router
+//! rejects such requests before matching. Unicode classes like
+//! `\p{White_Space}` are not inspected.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Route {
+//!     #[serde(with = "serde_regex::url_path")]
+//!     path: Regex,
+//! }
+//!
+//! let route: Route = serde_json::from_str(
+//!     r#"{"path": "^/users/([^/]+)$"}"#).unwrap();
+//! assert!(route.path.is_match("/users/alice"));
+//! assert!(serde_json::from_str::<Route>(r#"{"path": "(?m)^/users$"}"#)
+//!     .is_err());
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::ast::{
+    Ast,
+    ClassAsciiKind,
+    ClassPerlKind,
+    ClassSetItem,
+    Flag,
+    Flags,
+    FlagsItemKind,
+    GroupKind,
+    Span,
+    parse::Parser,
+};
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::{visit, visit_class};
+
+fn is_newline(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+fn enabled_flag(flags: &Flags) -> Option<char> {
+    let mut negated = false;
+    for item in &flags.items {
+        match item.kind {
+            FlagsItemKind::Negation => negated = true,
+            FlagsItemKind::Flag(Flag::MultiLine) if !negated => return Some('m'),
+            FlagsItemKind::Flag(Flag::DotMatchesNewLine) if !negated => {
+                return Some('s');
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn check(pattern: &str) -> Result<(), String> {
+    let ast = Parser::new().parse(pattern).map_err(|e| e.to_string())?;
+    let mut error = None;
+    let mut fail = |what: &str, span: &Span| {
+        error.get_or_insert_with(|| format!(
+            "{} {:?} is not allowed in a URL path pattern",
+            what, &pattern[span.start.offset..span.end.offset]));
+    };
+    let mut flag = None;
+    visit(&ast, &mut |node| match node {
+        Ast::Flags(set) => {
+            flag = flag.or(enabled_flag(&set.flags));
+        }
+        Ast::Group(group) => {
+            if let GroupKind::NonCapturing(ref flags) = group.kind {
+                flag = flag.or(enabled_flag(flags));
+            }
+        }
+        Ast::Literal(lit) if is_newline(lit.c) => fail("newline", &lit.span),
+        Ast::ClassPerl(class)
+            if class.kind == ClassPerlKind::Space && !class.negated
+        => {
+            fail("whitespace class", &class.span);
+        }
+        Ast::ClassBracketed(class) if !class.negated => {
+            visit_class(&class.kind, &mut |item| match item {
+                ClassSetItem::Literal(lit) if is_newline(lit.c) => {
+                    fail("newline", &lit.span);
+                }
+                ClassSetItem::Range(range)
+                    if range.start.c <= '\r' && range.end.c >= '\n'
+                => {
+                    fail("range with newlines", &range.span);
+                }
+                ClassSetItem::Perl(class)
+                    if class.kind == ClassPerlKind::Space && !class.negated
+                => {
+                    fail("whitespace class", &class.span);
+                }
+                ClassSetItem::Ascii(class)
+                    if class.kind == ClassAsciiKind::Space && !class.negated
+                => {
+                    fail("whitespace class", &class.span);
+                }
+                _ => {}
+            })
+        }
+        _ => {}
+    });
+    if let Some(flag) = flag {
+        return Err(format!(
+            "flag {:?} is not allowed in a URL path pattern", flag));
+    }
+    error.map_or(Ok(()), Err)
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    check(&s).map_err(D::Error::custom)?;
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{check, deserialize};
+
+    #[test]
+    fn test_check() {
+        assert!(check(r"^/api/v\d+/([^/]+)/?$").is_ok());
+        assert!(check(r"(?i-ms)^/a[^\n]\S(?-s:.)").is_ok());
+        assert_eq!(check(r"^/a(?m:$)").unwrap_err(),
+            r#"flag 'm' is not allowed in a URL path pattern"#);
+        assert!(check("(?s)/.*").is_err());
+        assert_eq!(check(r"/a\n").unwrap_err(),
+            r#"newline "\\n" is not allowed in a URL path pattern"#);
+        assert!(check("/a\r").is_err());
+        assert!(check(r"/a\s").is_err());
+        assert!(check(r"/[a\r]").is_err());
+        assert!(check(r"/[\x00-\x1F]").is_err());
+        assert!(check(r"/[\x0E-\x1F]").is_ok());
+        assert!(check(r"/[[:space:]]").is_err());
+        assert!(check(r"/[a[\s]]").is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert!(deserialize(Value::from("^/a/(b)$")).is_ok());
+        assert!(deserialize(Value::from("(?m)^/a")).is_err());
+        assert!(deserialize(Value::from("(")).unwrap_err().to_string()
+            .contains("regex parse error"));
+    }
+}
@@ -0,0 +1,184 @@
+use regex::Regex;
+use serde_json::Value;
+
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// Splits a pointer into unescaped reference tokens
+fn split(pointer: &str) -> Vec<String> {
+    if pointer.is_empty() {
+        return Vec::new();
+    }
+    match pointer.strip_prefix('/') {
+        Some(rest) => rest.split('/').map(unescape).collect(),
+        None => panic!("JSON pointer {:?} must start with '/'", pointer),
+    }
+}
+
+fn walk(value: &Value, segments: &[String], path: &mut String,
+    failures: &mut Vec<(String, regex::Error)>)
+{
+    let (segment, rest) = match segments.split_first() {
+        Some(pair) => pair,
+        None => {
+            if let Value::String(pattern) = value {
+                if let Err(e) = Regex::new(pattern) {
+                    failures.push((path.clone(), e));
+                }
+            }
+            return;
+        }
+    };
+    let len = path.len();
+    match value {
+        Value::Array(items) if segment == "*" => {
+            for (idx, item) in items.iter().enumerate() {
+                path.push_str(&format!("/{}", idx));
+                walk(item, rest, path, failures);
+                path.truncate(len);
+            }
+        }
+        Value::Object(map) if segment == "*" => {
+            for (key, item) in map {
+                path.push('/');
+                path.push_str(&escape(key));
+                walk(item, rest, path, failures);
+                path.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item) = segment.parse::<usize>().ok()
+                .and_then(|idx| items.get(idx))
+            {
+                path.push('/');
+                path.push_str(segment);
+                walk(item, rest, path, failures);
+                path.truncate(len);
+            }
+        }
+        Value::Object(map) => {
+            if let Some(item) = map.get(segment) {
+                path.push('/');
+                path.push_str(&escape(segment));
+                walk(item, rest, path, failures);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks that all patterns in a JSON document compile
+///
+/// Requires the `serde_json` feature. This is a dry run for large configs:
+/// it reports every invalid pattern at once, before anything is
+/// deserialized, so that a config can be rejected as a whole (e.g. by a
+/// validation endpoint) instead of failing at the first bad pattern.
+///
+/// Patterns are found with JSON pointers ([RFC 6901]), like
+/// `/filters/exclude`, extended with a `*` segment which matches every
+/// element of an array or every value of an object, e.g. `/rules/*/pattern`.
+/// Each string node a pointer resolves to is compiled with `Regex::new`.
+/// Pointers which don't resolve to anything, and nodes which are not
+/// strings (like `null` of an optional field), are skipped: deserialization
+/// reports those if they are wrong.
+///
+/// As in RFC 6901, `~1` and `~0` in a segment stand for `/` and `~`, and
+/// `//` is a segment with an empty key.
+///
+/// Returns the pointer of every pattern which failed to compile, with the
+/// error. The pointers are concrete, i.e. `*` is replaced by the actual
+/// index or key. Failures are grouped by pointer in the order `pointers`
+/// are given; a `*` visits array elements by index and object values in
+/// the iteration order of `serde_json::Map` (sorted by key, unless the
+/// `preserve_order` feature of `serde_json` is enabled).
+///
+/// ```rust
+/// use serde_json::json;
+///
+/// let config = json!({
+///     "rules": [
+///         {"name": "a", "pattern": "^a+$"},
+///         {"name": "b", "pattern": "(b"},
+///     ],
+///     "exclude": ["x", "[y"],
+/// });
+/// let failures = serde_regex::validate_all(
+///     &config, &["/rules/*/pattern", "/exclude/*"]).unwrap_err();
+/// let pointers = failures.iter().map(|(p, _)| &p[..]).collect::<Vec<_>>();
+/// assert_eq!(pointers, ["/rules/1/pattern", "/exclude/1"]);
+/// ```
+///
+/// # Panics
+///
+/// Panics if a non-empty pointer doesn't start with `/`.
+///
+/// [RFC 6901]: https://www.rfc-editor.org/rfc/rfc6901
+pub fn validate_all(value: &Value, pointers: &[&str])
+    -> Result<(), Vec<(String, regex::Error)>>
+{
+    let mut failures = Vec::new();
+    for pointer in pointers {
+        walk(value, &split(pointer), &mut String::new(), &mut failures);
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::validate_all;
+
+    fn failed(value: serde_json::Value, pointers: &[&str]) -> Vec<String> {
+        match validate_all(&value, pointers) {
+            Ok(()) => Vec::new(),
+            Err(failures) => failures.into_iter().map(|(p, _)| p).collect(),
+        }
+    }
+
+    #[test]
+    fn test_pointers() {
+        let value = json!({
+            "a/b": {"x": "(", "y": "ok"},
+            "list": ["(", null, "[", 1],
+            "plain": "(",
+        });
+        assert_eq!(failed(value.clone(), &["/a~1b/*"]), ["/a~1b/x"]);
+        assert_eq!(failed(value.clone(), &["/list/*"]), ["/list/0", "/list/2"]);
+        assert_eq!(failed(value.clone(), &["/list/2", "/plain", "/none"]),
+            ["/list/2", "/plain"]);
+        assert!(failed(value.clone(), &["/a~1b/y", "/list/1", "/list/9"])
+            .is_empty());
+        assert_eq!(failed(json!("("), &[""]), [""]);
+        let err = validate_all(&value, &["/plain"]).unwrap_err();
+        assert!(err[0].1.to_string().contains("unclosed group"));
+    }
+
+    #[test]
+    fn test_rfc6901() {
+        let value = json!({
+            "": {"a": "("},
+            "~1": "(",
+            "/": "ok",
+        });
+        assert_eq!(failed(value.clone(), &["//a"]), ["//a"]);
+        assert_eq!(failed(value.clone(), &["/~01"]), ["/~01"]);
+        assert!(failed(value.clone(), &["/~1", "/a"]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "must start with '/'")]
+    fn test_relative() {
+        failed(json!({"a": "("}), &["a"]);
+    }
+}
@@ -0,0 +1,99 @@
+//! Deserialization of `Vec<Regex>` dropping equivalent patterns
+//!
+//! Large rule sets merged from several sources often contain the same
+//! pattern written differently, e.g. `[0-9]+` and `\d+` (in ASCII mode),
+//! `[abc]` and `[a-c]`, or `a{1,}` and `a+`. This module parses every
+//! pattern with `regex-syntax` and drops any pattern whose high-level IR
+//! is equal to the one of a pattern already kept, the same check as
+//! [`Serde::semantic_eq`](crate::Serde::semantic_eq). The first occurrence
+//! is kept with its original text, so order and spelling of the remaining
+//! patterns are preserved.
+//!
+//! The check is best-effort: patterns with equal IR are equivalent, but
+//! equivalent patterns don't always have equal IR (e.g. `a|b` and `[ab]`,
+//! or `aa*` and `a+`). Deciding regex equivalence in general is way too
+//! expensive for deserialization.
+//!
+//! Every pattern is parsed once more in addition to compiling, which adds
+//! some CPU cost proportional to total pattern length. Serialization is
+//! the same as in the default module.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rules {
+//!     #[serde(with = "serde_regex::vec_semantic_dedup")]
+//!     patterns: Vec<Regex>,
+//! }
+//!
+//! let rules: Rules = serde_json::from_str(r#"{
+//!     "patterns": ["[a-c]x", "y+", "[abc]x", "yy*", "y{1,}"]
+//! }"#).unwrap();
+//! let patterns = rules.patterns.iter().map(|p| p.as_str())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(patterns, ["[a-c]x", "y+", "yy*"]);
+//! ```
+use std::{borrow::Cow, collections::HashMap};
+
+use regex::Regex;
+use regex_syntax::hir::Hir;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+use crate::syntax::translate;
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let patterns = <Vec<Cow<str>>>::deserialize(d)?;
+    // equal IR always prints the same, so only patterns printed the same
+    // are compared
+    let mut seen = HashMap::<String, Vec<Hir>>::with_capacity(patterns.len());
+    let mut result = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        let hir = translate(&pattern).map_err(D::Error::custom)?;
+        let bucket = seen.entry(hir.to_string()).or_default();
+        if !bucket.contains(&hir) {
+            bucket.push(hir);
+            result.push(Regex::new(&pattern).map_err(D::Error::custom)?);
+        }
+    }
+    Ok(result)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Vec<Regex>, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::deserialize;
+
+    fn dedup(patterns: &[&str]) -> Vec<String> {
+        deserialize(Value::from(patterns.to_vec())).unwrap()
+            .iter().map(|re| re.as_str().to_string()).collect()
+    }
+
+    #[test]
+    fn test_dedup() {
+        assert_eq!(dedup(&["a", "b", "a"]), ["a", "b"]);
+        assert_eq!(dedup(&["(?-u)[0-9]+", r"(?-u)\d+"]), ["(?-u)[0-9]+"]);
+        assert_eq!(dedup(&["(?i)a", "[aA]", "(?:a)"]), ["(?i)a", "(?:a)"]);
+        assert_eq!(dedup(&["(a)", "(?:a)", "(?P<x>a)"]),
+            ["(a)", "(?:a)", "(?P<x>a)"]);
+        assert!(dedup(&[]).is_empty());
+        assert!(deserialize(Value::from(vec!["a", "("])).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! Deserialization which warns about patterns that aren't anchored
+//!
+//! Enabled by the `log` feature. A frequent mistake is writing `\d+` for a
+//! field meant to validate a whole string, while `Regex::is_match` finds
+//! the pattern anywhere, so `abc123` passes. This module compiles patterns
+//! as usual, but if a match isn't anchored to the start (`^` or `\A`) and
+//! to the end (`$` or `\z`) of the text, a `log::warn!` message suggesting
+//! anchoring is emitted. The pattern itself is never modified and loading
+//! never fails because of the warning.
+//!
+//! The check is a heuristic based on the parsed pattern: every alternative
+//! must start and end with an anchor, so `^a$|^b$` is fine, but `^a|b$`
+//! isn't. In multi-line mode `(?m)` the `^` and `$` match at line
+//! boundaries, so they don't count as anchors. Patterns which are meant to
+//! match a substring, e.g. for search rather than validation, will warn
+//! too, so use this module only for fields that validate whole values.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Field {
+//!     #[serde(with = "serde_regex::warn_unanchored")]
+//!     validate: Regex,
+//! }
+//!
+//! // loads, but logs a warning suggesting `^\d+$`
+//! let field: Field = serde_json::from_str(r#"{"validate": "\\d+"}"#)
+//!     .unwrap();
+//! assert!(field.validate.is_match("abc123"));
+//! ```
+use std::borrow::Cow;
+
+use regex::Regex;
+use regex_syntax::hir::Look;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Returns which ends of the pattern lack an anchor, if any
+fn missing_anchors(pattern: &str) -> Option<&'static str> {
+    let hir = regex_syntax::Parser::new().parse(pattern).ok()?;
+    let start = hir.properties().look_set_prefix().contains(Look::Start);
+    let end = hir.properties().look_set_suffix().contains(Look::End);
+    match (start, end) {
+        (true, true) => None,
+        (false, true) => Some("start"),
+        (true, false) => Some("end"),
+        (false, false) => Some("start and end"),
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <Cow<str>>::deserialize(d)?;
+    let regex = Regex::new(&s).map_err(D::Error::custom)?;
+    if let Some(missing) = missing_anchors(&s) {
+        log::warn!("pattern {:?} is not anchored at the {}, it matches \
+                    substrings; use ^ and $ to match the whole value",
+            s, missing);
+    }
+    Ok(regex)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    crate::serialize(value, serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use crate::test_log::capture;
+    use super::{deserialize, missing_anchors};
+
+    #[test]
+    fn test_anchors() {
+        assert_eq!(missing_anchors(r"^\d+$"), None);
+        assert_eq!(missing_anchors(r"\A\d+\z"), None);
+        assert_eq!(missing_anchors(r"^a$|^b$"), None);
+        assert_eq!(missing_anchors(r"^(?:a|b)$"), None);
+        assert_eq!(missing_anchors(r"\d+"), Some("start and end"));
+        assert_eq!(missing_anchors(r"^\d+"), Some("end"));
+        assert_eq!(missing_anchors(r"\d+$"), Some("start"));
+        assert_eq!(missing_anchors(r"^a|b$"), Some("start and end"));
+        assert_eq!(missing_anchors(r"(?m)^a$"), Some("start and end"));
+    }
+
+    #[test]
+    fn test_warn() {
+        let messages = capture(|| {
+            let regex = deserialize(Value::from(r"\d+")).unwrap();
+            assert_eq!(regex.as_str(), r"\d+");
+            deserialize(Value::from(r"^\d+$")).unwrap();
+        });
+        assert_eq!(messages, vec![
+            r#"WARN: pattern "\\d+" is not anchored at the start and end, it matches substrings; use ^ and $ to match the whole value"#,
+        ]);
+    }
+}
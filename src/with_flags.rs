@@ -0,0 +1,271 @@
+//! (De)serialization as a pattern with explicit flags
+//!
+//! The value is a map with the pattern and every `RegexBuilder` setting
+//! that has an inline flag, each under the name of the builder method:
+//!
+//! ```json
+//! {
+//!   "pattern": "^error:",
+//!   "case_insensitive": true,
+//!   "multi_line": true,
+//!   "dot_matches_new_line": false,
+//!   "ignore_whitespace": false,
+//!   "swap_greed": false,
+//!   "crlf": false,
+//!   "unicode": true
+//! }
+//! ```
+//!
+//! Every setting may be omitted, defaults are the same as for
+//! `RegexBuilder` (`unicode` is on, others are off), and `multiline` is
+//! accepted as an alias of `multi_line`. A plain string is accepted too,
+//! so existing configs keep working.
+//!
+//! A compiled `Regex` doesn't expose builder settings (see
+//! [Builder flags](crate#builder-flags)), so the settings are applied as a
+//! leading inline group like `(?im)`, which is equivalent to calling the
+//! builder methods but is kept in the pattern. Serialization splits such a
+//! group back into settings and always writes all of them, so a value
+//! written by this module round-trips byte-for-byte. A pattern without a
+//! leading group is written with default settings.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Rule {
+//!     #[serde(with = "serde_regex::with_flags")]
+//!     pattern: Regex,
+//! }
+//!
+//! let rule: Rule = serde_json::from_str(r#"{"pattern": {
+//!     "pattern": "^error:",
+//!     "case_insensitive": true,
+//!     "multi_line": true
+//! }}"#).unwrap();
+//! assert!(rule.pattern.is_match("ok\nERROR: failed"));
+//!
+//! let plain: Rule = serde_json::from_str(r#"{"pattern": "^ok"}"#).unwrap();
+//! assert_eq!(plain.pattern.as_str(), "^ok");
+//! ```
+use std::fmt;
+
+use regex::Regex;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{Error, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+
+/// Flags in the order they are written in the inline group
+const FLAGS: &[char] = &['i', 'm', 's', 'x', 'U', 'R'];
+
+fn default_unicode() -> bool {
+    true
+}
+
+struct Flagged<P> {
+    pattern: P,
+    case_insensitive: bool,
+    multi_line: bool,
+    dot_matches_new_line: bool,
+    ignore_whitespace: bool,
+    swap_greed: bool,
+    crlf: bool,
+    unicode: bool,
+}
+
+deserialize_struct!(Flagged<String> as Flagged {
+    pattern: String,
+    case_insensitive: bool = false,
+    multi_line | "multiline": bool = false,
+    dot_matches_new_line: bool = false,
+    ignore_whitespace: bool = false,
+    swap_greed: bool = false,
+    crlf: bool = false,
+    unicode: bool = default_unicode(),
+});
+
+serialize_struct!(Flagged<&str> as Flagged {
+    pattern,
+    case_insensitive,
+    multi_line,
+    dot_matches_new_line,
+    ignore_whitespace,
+    swap_greed,
+    crlf,
+    unicode,
+});
+
+impl<P> Flagged<P> {
+    fn enabled(&self) -> [bool; 6] {
+        [self.case_insensitive, self.multi_line, self.dot_matches_new_line,
+         self.ignore_whitespace, self.swap_greed, self.crlf]
+    }
+}
+
+fn compile(flagged: &Flagged<String>) -> Result<Regex, regex::Error> {
+    let mut group = FLAGS.iter().zip(flagged.enabled())
+        .filter(|&(_, on)| on)
+        .map(|(&c, _)| c)
+        .collect::<String>();
+    if !flagged.unicode {
+        group.push_str("-u");
+    }
+    if group.is_empty() {
+        Regex::new(&flagged.pattern)
+    } else {
+        Regex::new(&format!("(?{}){}", group, flagged.pattern))
+    }
+}
+
+/// Splits a leading group like `(?im)` or `(?i-u)` into settings
+fn split(pattern: &str) -> Flagged<&str> {
+    let mut flagged = Flagged {
+        pattern,
+        case_insensitive: false,
+        multi_line: false,
+        dot_matches_new_line: false,
+        ignore_whitespace: false,
+        swap_greed: false,
+        crlf: false,
+        unicode: true,
+    };
+    let Some((group, rest)) = pattern.strip_prefix("(?")
+        .and_then(|tail| tail.split_once(')'))
+    else {
+        return flagged;
+    };
+    let (on, unicode) = match group.strip_suffix("-u") {
+        Some(on) => (on, false),
+        None => (group, true),
+    };
+    let mut seen = String::new();
+    for c in on.chars() {
+        if !FLAGS.contains(&c) || seen.contains(c) {
+            return flagged;
+        }
+        seen.push(c);
+    }
+    if seen.is_empty() && unicode {
+        return flagged;
+    }
+    flagged.pattern = rest;
+    flagged.case_insensitive = seen.contains('i');
+    flagged.multi_line = seen.contains('m');
+    flagged.dot_matches_new_line = seen.contains('s');
+    flagged.ignore_whitespace = seen.contains('x');
+    flagged.swap_greed = seen.contains('U');
+    flagged.crlf = seen.contains('R');
+    flagged.unicode = unicode;
+    flagged
+}
+
+struct WithFlagsVisitor;
+
+impl<'de> Visitor<'de> for WithFlagsVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("pattern or map with pattern and flags")
+    }
+    fn visit_str<E>(self, v: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(v).map_err(E::custom)
+    }
+    fn visit_map<A>(self, map: A) -> Result<Regex, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let flagged = Flagged::deserialize(MapAccessDeserializer::new(map))?;
+        compile(&flagged).map_err(A::Error::custom)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(WithFlagsVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    split(value.as_str()).serialize(serializer)
+}
+
+#[cfg(test)]
+mod test {
+    use regex::{Regex, RegexBuilder};
+    use serde_derive::{Serialize, Deserialize};
+    use serde_json::{from_str, to_string};
+
+    #[derive(Serialize, Deserialize)]
+    struct Rule {
+        #[serde(with = "crate::with_flags")]
+        re: Regex,
+    }
+
+    #[test]
+    fn test_byte_for_byte() {
+        for json in &[
+            r#"{"re":{"pattern":"^a.b$","case_insensitive":true,"multi_line":true,"dot_matches_new_line":true,"ignore_whitespace":false,"swap_greed":true,"crlf":true,"unicode":true}}"#,
+            r#"{"re":{"pattern":"a","case_insensitive":false,"multi_line":false,"dot_matches_new_line":false,"ignore_whitespace":true,"swap_greed":false,"crlf":false,"unicode":false}}"#,
+            r#"{"re":{"pattern":"(?i-s)a","case_insensitive":false,"multi_line":false,"dot_matches_new_line":false,"ignore_whitespace":false,"swap_greed":false,"crlf":false,"unicode":true}}"#,
+            r#"{"re":{"pattern":"(?m)a","case_insensitive":true,"multi_line":false,"dot_matches_new_line":false,"ignore_whitespace":false,"swap_greed":false,"crlf":false,"unicode":true}}"#,
+        ] {
+            let rule: Rule = from_str(json).unwrap();
+            assert_eq!(&to_string(&rule).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn test_builder_equivalence() {
+        let rule: Rule = from_str(r#"{"re": {
+            "pattern": "^a.b$",
+            "case_insensitive": true,
+            "multiline": true,
+            "dot_matches_new_line": true
+        }}"#).unwrap();
+        assert_eq!(rule.re.as_str(), "(?ims)^a.b$");
+        let built = RegexBuilder::new("^a.b$")
+            .case_insensitive(true)
+            .multi_line(true)
+            .dot_matches_new_line(true)
+            .build().unwrap();
+        for text in &["A\nB", "x\nab\ny", "ab", "a\nc"] {
+            assert_eq!(rule.re.is_match(text), built.is_match(text));
+        }
+    }
+
+    #[test]
+    fn test_plain() {
+        let rule: Rule = from_str(r#"{"re": "(?mi)a"}"#).unwrap();
+        assert_eq!(rule.re.as_str(), "(?mi)a");
+        assert_eq!(to_string(&rule).unwrap(), r#"{"re":{"pattern":"a","case_insensitive":true,"multi_line":true,"dot_matches_new_line":false,"ignore_whitespace":false,"swap_greed":false,"crlf":false,"unicode":true}}"#);
+        let rule: Rule = from_str(r#"{"re": "(?i:a)b"}"#).unwrap();
+        assert!(to_string(&rule).unwrap().contains(r#""pattern":"(?i:a)b""#));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_str::<Rule>(r#"{"re": {"pattern": "a", "global": true}}"#)
+            .is_err());
+        assert!(from_str::<Rule>(r#"{"re": {"case_insensitive": true}}"#)
+            .is_err());
+        assert!(from_str::<Rule>(r#"{"re": {"pattern": "(", "crlf": true}}"#)
+            .is_err());
+        assert!(from_str::<Rule>(r#"{"re": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! (De)serialization of YAML scalars tagged with `!re`
+//!
+//! Lets YAML mark pattern scalars explicitly:
+//!
+//! ```yaml
+//! include: !re '^src/.*\.rs$'
+//! exclude: '^target/'
+//! ```
+//!
+//! `serde_yaml` drops unknown tags on scalars if a string is requested, so
+//! the default module accepts the above, but any tag is silently ignored,
+//! e.g. `!glob 'src/*'` is compiled as a regex too.
+//!
+//! This module asks the deserializer for any value, which makes
+//! `serde_yaml` deliver the tag, both when reading from text and from
+//! `serde_yaml::Value`. Supported forms are:
+//!
+//! * an untagged string or one tagged with the standard `!!str`;
+//! * a string tagged with `!re` (also written as `!<!re>`).
+//!
+//! Any other tag is an error. Since `deserialize_any` is used, this only
+//! works with self-describing formats, which includes JSON.
+//!
+//! Serialization always emits the tagged form, i.e. `!re 'pattern'` in
+//! YAML. Other formats represent it as an enum variant named `re`, e.g.
+//! `{"re": "pattern"}` in JSON, so don't use this module for them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use regex::Regex;
+//! use serde_derive::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Filter {
+//!     #[serde(with = "serde_regex::yaml_tagged")]
+//!     include: Regex,
+//! }
+//!
+//! let filter: Filter = serde_yaml::from_str("include: !re '^src/'")
+//!     .unwrap();
+//! assert_eq!(filter.include.as_str(), "^src/");
+//! assert_eq!(serde_yaml::to_string(&filter).unwrap(),
+//!     "include: !re ^src/\n");
+//! assert!(serde_yaml::from_str::<Filter>("include: !glob 'src/*'").is_err());
+//! ```
+use std::fmt;
+
+use regex::Regex;
+use serde::{
+    Deserializer,
+    Serializer,
+    de::{EnumAccess, Error, VariantAccess, Visitor},
+};
+
+use crate::Serde;
+
+/// Tag accepted and emitted by this module, without the leading `!`
+pub const TAG: &str = "re";
+
+struct TaggedVisitor;
+
+impl<'de> Visitor<'de> for TaggedVisitor {
+    type Value = Regex;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("valid regular expression, optionally tagged !re")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Regex, E>
+    where
+        E: Error,
+    {
+        Regex::new(value).map_err(E::custom)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Regex, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (tag, value) = data.variant::<String>()?;
+        if tag != TAG {
+            return Err(A::Error::custom(format_args!(
+                "unsupported tag !{}, expected !{}", tag, TAG)));
+        }
+        value.newtype_variant::<Serde<Regex>>().map(Serde::into_inner)
+    }
+}
+
+/// Deserialize function, see module docs to see how to use it
+pub fn deserialize<'de, D>(d: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(TaggedVisitor)
+}
+
+/// Serialize function, see module docs to see how to use it
+pub fn serialize<S>(value: &Regex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_variant("Regex", 0, TAG, &Serde(value))
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+    use serde_derive::{Serialize, Deserialize};
+    use serde_yaml::{Value, from_str, from_value, to_string};
+
+    use crate::Serde;
+    use super::deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Filter {
+        #[serde(with = "crate::yaml_tagged")]
+        re: Regex,
+    }
+
+    fn load(yaml: &str) -> Result<String, String> {
+        from_str::<Filter>(yaml)
+            .map(|filter| filter.re.as_str().to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(load("re: !re 'a+'").unwrap(), "a+");
+        assert_eq!(load("re: !<!re> 'a+'").unwrap(), "a+");
+        assert_eq!(load("re: !!str 'a+'").unwrap(), "a+");
+        assert_eq!(load("re: 'a+'").unwrap(), "a+");
+        assert_eq!(load("re: !glob 'a*'").unwrap_err(),
+            "re: unsupported tag !glob, expected !re at line 1 column 5");
+        assert!(load("re: !re 'a('").is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let value: Value = from_str("re: !re 'a+'").unwrap();
+        let filter: Filter = from_value(value).unwrap();
+        assert_eq!(filter.re.as_str(), "a+");
+        // the default module ignores the tag
+        let value: Value = from_str("!glob 'a*'").unwrap();
+        assert!(from_value::<Serde<Regex>>(value.clone()).is_ok());
+        assert!(deserialize(value).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let filter = Filter { re: Regex::new(r"^\d+: .*").unwrap() };
+        let yaml = to_string(&filter).unwrap();
+        assert_eq!(yaml, "re: !re '^\\d+: .*'\n");
+        assert_eq!(load(&yaml).unwrap(), r"^\d+: .*");
+    }
+}
@@ -0,0 +1,34 @@
+//! The prefix and suffix are process-wide, so this runs as a separate test
+//! binary with a single test, not to affect patterns of any other test
+use regex::{Regex, RegexSet};
+use serde_json::{from_str, to_string};
+use serde_regex::Serde;
+
+#[test]
+fn test_round_trip() {
+    serde_regex::set_prefix_suffix("^/t/", "$");
+
+    let Serde(regex) = from_str::<Serde<Regex>>(r#""a|b""#).unwrap();
+    assert_eq!(regex.as_str(), "^/t/(?:a|b)$");
+    assert_eq!(to_string(&Serde(&regex)).unwrap(), r#""a|b""#);
+    let Serde(back) = from_str::<Serde<Regex>>(r#""a|b""#).unwrap();
+    assert_eq!(back.as_str(), regex.as_str());
+
+    let Serde(set) = from_str::<Serde<RegexSet>>(r#"["x", "y"]"#).unwrap();
+    assert_eq!(set.patterns(), ["^/t/(?:x)$", "^/t/(?:y)$"]);
+    assert_eq!(to_string(&Serde(&set)).unwrap(), r#"["x","y"]"#);
+
+    // not wrapped by this crate, even though it looks like it was
+    let built = Regex::new("^/t/(?:c)$").unwrap();
+    assert_eq!(to_string(&Serde(&built)).unwrap(), r#""^/t/(?:c)$""#);
+
+    serde_regex::set_prefix_suffix("", "");
+    let built = Regex::new("(?:a)|(?:b)").unwrap();
+    assert_eq!(to_string(&Serde(&built)).unwrap(), r#""(?:a)|(?:b)""#);
+    let Serde(regex) = from_str::<Serde<Regex>>(r#""(?:a)|(?:b)""#).unwrap();
+    assert_eq!(regex.as_str(), "(?:(?:a)|(?:b))");
+    assert_eq!(to_string(&Serde(&regex)).unwrap(), r#""(?:a)|(?:b)""#);
+
+    serde_regex::clear_prefix_suffix();
+    assert_eq!(to_string(&Serde(&regex)).unwrap(), r#""(?:(?:a)|(?:b))""#);
+}